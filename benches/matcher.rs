@@ -0,0 +1,80 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use klogrs::log_processor::{GrepFilter, PatternMatcher};
+
+/// Build a watchlist of error signatures similar to the ones used in production
+fn watchlist(size: usize) -> Vec<String> {
+    (0..size)
+        .map(|i| format!(r"E{:04}: (timeout|refused) in service-{}", i, i % 37))
+        .collect()
+}
+
+/// Build a batch of log lines where roughly one in a hundred hits the watchlist
+fn log_lines(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| {
+            if i % 100 == 0 {
+                format!(
+                    "2024-05-01T10:00:00Z ERROR E{:04}: timeout in service-{}",
+                    i % 500,
+                    (i % 500) % 37
+                )
+            } else {
+                format!(
+                    "2024-05-01T10:00:00Z INFO request {} served in {}ms",
+                    i,
+                    i % 250
+                )
+            }
+        })
+        .collect()
+}
+
+fn bench_matching(c: &mut Criterion) {
+    let lines = log_lines(10_000);
+    let mut group = c.benchmark_group("watchlist");
+    group.throughput(Throughput::Elements(lines.len() as u64));
+
+    for size in [10, 100, 500] {
+        let patterns = watchlist(size);
+
+        let matcher = PatternMatcher::new(&patterns).unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("pattern_matcher", size),
+            &lines,
+            |b, lines| {
+                b.iter(|| {
+                    lines
+                        .iter()
+                        .filter(|l| matcher.is_match(black_box(l)))
+                        .count()
+                })
+            },
+        );
+
+        let filters: Vec<GrepFilter> = patterns
+            .iter()
+            .map(|p| GrepFilter::new(p).unwrap())
+            .collect();
+        group.bench_with_input(
+            BenchmarkId::new("grep_filters", size),
+            &lines,
+            |b, lines| {
+                b.iter(|| {
+                    lines
+                        .iter()
+                        .filter(|l| {
+                            filters
+                                .iter()
+                                .any(|f| !f.find_matches(black_box(l)).is_empty())
+                        })
+                        .count()
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_matching);
+criterion_main!(benches);
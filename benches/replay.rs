@@ -0,0 +1,104 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use klogrs::kubernetes::LogEntry;
+use klogrs::log_processor::LogFormatter;
+use klogrs::output::OutputSink;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Arc;
+use termcolor::{Ansi, ColorChoice};
+
+/// Number of lines in the replay file
+const LINES: usize = 20_000;
+
+/// Pods the replayed lines are spread over
+const PODS: [&str; 4] = [
+    "api-7d9f8b6c4-x2x9k",
+    "api-7d9f8b6c4-q7w3e",
+    "worker-5c4b2a1d9-m8n4p",
+    "worker-5c4b2a1d9-z1t6r",
+];
+
+/// Write a replay file of timestamped lines, one pod per line in turn
+fn write_replay(path: &Path) {
+    let mut file = File::create(path).unwrap();
+    for i in 0..LINES {
+        let level = if i % 50 == 0 { "ERROR" } else { "INFO" };
+        writeln!(
+            file,
+            "{}\t2024-05-01T10:{:02}:{:02}.{:06}Z {} request {} served in {}ms by handler /api/v1/items",
+            PODS[i % PODS.len()],
+            i / 3600 % 60,
+            i / 60 % 60,
+            i,
+            level,
+            i,
+            i % 250
+        )
+        .unwrap();
+    }
+}
+
+/// Read the replay file into log entries, sharing the names of each pod
+fn read_replay(path: &Path) -> Vec<LogEntry> {
+    let pods: Vec<Arc<str>> = PODS.iter().map(|&pod| Arc::from(pod)).collect();
+    let namespace: Arc<str> = Arc::from("default");
+    let container: Arc<str> = Arc::from("main");
+    BufReader::new(File::open(path).unwrap())
+        .lines()
+        .map(|line| {
+            let line = line.unwrap();
+            let (pod, raw_line) = line.split_once('\t').unwrap();
+            let pod = pods.iter().find(|name| &***name == pod).unwrap();
+            LogEntry::parse(
+                namespace.clone(),
+                pod.clone(),
+                container.clone(),
+                raw_line.to_string(),
+            )
+        })
+        .collect()
+}
+
+fn bench_replay(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let replay = dir.path().join("replay.log");
+    let output = dir.path().join("output.log");
+    write_replay(&replay);
+    let entries = read_replay(&replay);
+
+    let mut group = c.benchmark_group("replay");
+    group.throughput(Throughput::Elements(LINES as u64));
+    group.sample_size(10);
+
+    group.bench_function("parse", |b| b.iter(|| black_box(read_replay(&replay))));
+
+    // A new unbuffered writer per entry, as before the output sink
+    group.bench_function("unbuffered_writer", |b| {
+        b.iter(|| {
+            let mut formatter = LogFormatter::new(None, false);
+            let file = File::create(&output).unwrap();
+            for entry in &entries {
+                let mut out = Ansi::new(&file);
+                formatter.write_entry(&mut out, black_box(entry)).unwrap();
+            }
+        })
+    });
+
+    group.bench_function("output_sink", |b| {
+        b.iter(|| {
+            let mut formatter = LogFormatter::new(None, false);
+            let mut sink = OutputSink::new(File::create(&output).unwrap(), ColorChoice::Always);
+            for entry in &entries {
+                formatter.write_entry(&mut sink, black_box(entry)).unwrap();
+            }
+            sink.flush().unwrap();
+        })
+    });
+
+    group.finish();
+    fs::remove_file(&output).unwrap();
+}
+
+criterion_group!(benches, bench_replay);
+criterion_main!(benches);
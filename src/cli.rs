@@ -1,49 +1,303 @@
 use anyhow::Result;
-use clap::{CommandFactory, FromArgMatches, Parser};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use std::ffi::OsString;
+use std::path::PathBuf;
+use std::time::Duration;
 use tracing::debug;
 
+use crate::log_processor::encoder::OutputFormat;
+use crate::log_processor::filter::MatchScope;
+use crate::log_processor::pretty::JsonStyle;
+use crate::log_processor::template::Template;
+use crate::log_processor::throttle::Sampling;
+use crate::log_processor::timestamp::TimestampMode;
+use crate::output::Compression;
+use crate::utils::{parse_duration, parse_size, ColorMode};
+
 /// A command-line tool for reading and processing Kubernetes pod logs
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
+    /// Configuration file with alert rules
+    /// (default: $XDG_CONFIG_HOME/klogrs/config.yaml or ~/.config/klogrs/config.yaml)
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
     /// Namespace to use
-    #[arg(short = 'n', long, default_value = "default")]
+    #[arg(short = 'n', long, default_value = "default", global = true)]
     pub namespace: String,
 
-    /// Deployment to get logs from
-    #[arg(short = 'd', long)]
+    /// Deployment to get logs from (required)
+    #[arg(short = 'd', long, required = false, global = true)]
     pub deployment: String,
 
     /// Follow logs
-    #[arg(long, short = 'f', default_value_t = false)]
+    #[arg(long, short = 'f', default_value_t = false, global = true)]
     pub follow: bool,
 
     /// Grep pattern to filter logs
     /// Multiple patterns can be separated by:
     /// - comma (,) for OR logic: "error,warning" matches logs containing either "error" OR "warning"
     /// - ampersand (&) for AND logic: "error&warning" matches logs containing both "error" AND "warning"
-    #[arg(long, short = 'g')]
+    #[arg(long, short = 'g', global = true)]
     pub grep: Option<String>,
 
+    /// File with grep patterns, one per line (lines starting with # are ignored)
+    /// Patterns are combined with --grep patterns using the same logic
+    #[arg(long, global = true)]
+    pub grep_file: Option<PathBuf>,
+
+    /// Part of each log entry that grep patterns match against:
+    /// message (without the timestamp), raw (whole line), level, pod, container or field:<name> (JSON/logfmt field)
+    #[arg(long, default_value = "message", value_parser = parse_match_scope, global = true)]
+    pub grep_scope: MatchScope,
+
     /// Number of log entries to display (tail mode)
-    #[arg(long, short = 't')]
+    #[arg(long, short = 't', global = true)]
     pub tail: Option<usize>,
 
+    /// Number of most recent log entries to display across all pods, after filtering
+    /// and merged by timestamp
+    #[arg(long, conflicts_with = "follow", global = true)]
+    pub tail_total: Option<usize>,
+
     /// Filter logs by minimum level (TRACE, DEBUG, INFO, WARN, ERROR, FATAL)
     /// Multiple levels can be separated by comma (,) for OR logic:
     /// "ERROR,WARN" matches logs with either ERROR OR WARN level
-    #[arg(long, short = 'l')]
+    #[arg(long, short = 'l', global = true)]
     pub level: Option<String>,
 
+    /// Part of each log entry that level filters match against: level (the detected level)
+    /// or any grep scope, e.g. field:severity for JSON logs
+    #[arg(long, default_value = "level", value_parser = parse_match_scope, global = true)]
+    pub level_scope: MatchScope,
+
     /// Use AND logic to combine filters within the same parameter (deprecated, use & separator instead)
     /// Note: Grep and level filters are always combined with AND logic
-    #[arg(long, default_value_t = false)]
+    #[arg(long, default_value_t = false, global = true)]
     pub and: bool,
 
+    /// Without --follow, show the logs of each pod in turn, in pod name order,
+    /// instead of merging all pods into one stream ordered by timestamp
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "follow",
+        global = true
+    )]
+    pub group_by_pod: bool,
+
+    /// Output format: text, json (JSON Lines), logfmt or csv.
+    /// Machine-readable formats include namespace, pod, container, timestamp,
+    /// level, message and parsed fields, without colors
+    #[arg(long, short = 'o', default_value = "text", value_parser = parse_output_format,
+          conflicts_with_all = ["summarize", "stats"], global = true)]
+    pub output: OutputFormat,
+
+    /// Output template for each line, e.g. "{timestamp:%H:%M:%S} {pod:<20.20} {level:5} {message}".
+    /// Placeholders: timestamp (ts), namespace (ns), pod, short_pod, container, node,
+    /// level, message (msg) and field.<name>; modifiers: <, > or ^ alignment, width and .max
+    #[arg(long, value_parser = parse_template,
+          conflicts_with_all = ["output", "summarize", "stats"], global = true)]
+    pub template: Option<Template>,
+
+    /// Show the Kubernetes timestamp of each line: utc, local, an IANA zone (e.g. Europe/Berlin),
+    /// relative (time since the previous line), elapsed (time since the first line),
+    /// a strftime format (in UTC) or a zone followed by a format (e.g. "local %H:%M:%S")
+    #[arg(long, value_parser = parse_timestamp_mode,
+          conflicts_with_all = ["output", "template", "summarize", "stats"], global = true)]
+    pub timestamps: Option<TimestampMode>,
+
+    /// How JSON log lines are shown in text output: raw (as logged), pretty (indented
+    /// and syntax-colored) or compact (the message followed by key=value pairs)
+    #[arg(long, default_value = "raw", value_parser = parse_json_style,
+          conflicts_with_all = ["output", "template", "summarize", "stats"], global = true)]
+    pub json_style: JsonStyle,
+
+    /// When to use colors: auto (when stdout is a terminal and NO_COLOR is not set),
+    /// always or never
+    #[arg(long, default_value = "auto", value_parser = parse_color_mode, global = true)]
+    pub color: ColorMode,
+
+    /// Color theme: default, plain or a theme defined in the config file
+    #[arg(long, global = true)]
+    pub theme: Option<String>,
+
+    /// Also write the output to this file, without colors. Lines are appended
+    /// if the file exists
+    #[arg(long, conflicts_with_all = ["summarize", "stats"], global = true)]
+    pub output_file: Option<PathBuf>,
+
+    /// Write each container to its own file NAMESPACE/POD/CONTAINER.log in this
+    /// directory, with a manifest.json listing pods, time ranges and line counts,
+    /// instead of showing the logs in the terminal
+    #[arg(long, conflicts_with_all = ["summarize", "stats"], global = true)]
+    pub output_dir: Option<PathBuf>,
+
+    /// Rotate the output file before it grows beyond this size (e.g. 100M, 1G)
+    #[arg(long, value_parser = parse_size_arg, requires = "output_file", global = true)]
+    pub rotate_size: Option<u64>,
+
+    /// Rotate the output file after this duration (e.g. 1h)
+    #[arg(long, value_parser = parse_duration_arg, requires = "output_file", global = true)]
+    pub rotate_interval: Option<Duration>,
+
+    /// Compress rotated output files: none, gzip or zstd
+    #[arg(long, default_value = "none", value_parser = parse_compression, requires = "output_file", global = true)]
+    pub compress: Compression,
+
+    /// Number of rotated output files to keep, older ones are deleted
+    #[arg(long, requires = "output_file", global = true)]
+    pub keep: Option<usize>,
+
+    /// Mode to run in instead of printing the logs
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Don't show output in a pager. Without --follow, output to a terminal is shown in
+    /// $KLOGRS_PAGER or $PAGER (less by default) when it doesn't fit on the screen
+    #[arg(long, default_value_t = false, global = true)]
+    pub no_pager: bool,
+
+    /// Cut lines at the terminal width with a marker instead of wrapping them
+    #[arg(long, default_value_t = false, global = true)]
+    pub truncate: bool,
+
+    /// Don't wrap long lines at the terminal width, leave them to the terminal
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "truncate",
+        global = true
+    )]
+    pub no_wrap: bool,
+
     /// Disable highlighting of matched keywords in grep results
-    #[arg(long, default_value_t = false)]
+    #[arg(long, default_value_t = false, global = true)]
     pub no_highlight: bool,
+
+    /// Replace tokens, passwords, emails, card numbers and custom patterns from the
+    /// config file with hashed placeholders before anything else sees the lines
+    #[arg(long, default_value_t = false, global = true)]
+    pub redact: bool,
+
+    /// Hold lines for up to this duration (e.g. 500ms) and show them in timestamp order
+    /// across pods in follow mode. Lines are released early once every active pod
+    /// has caught up, and pods silent for longer than the duration don't hold others back
+    #[arg(long, value_parser = parse_duration_arg, global = true)]
+    pub reorder: Option<Duration>,
+
+    /// Collapse consecutive identical lines from the same pod into "last message repeated N times"
+    #[arg(long, default_value_t = false, global = true)]
+    pub dedup: bool,
+
+    /// Merge identical lines from multiple pods arriving within this window (e.g. 500ms, 2s)
+    /// into one line listing the pods. Output is delayed by the window
+    #[arg(long, value_parser = parse_duration_arg, global = true)]
+    pub dedup_window: Option<Duration>,
+
+    /// Keep only a deterministic sample of lines: N keeps every Nth line per pod,
+    /// hash:N keeps lines whose message hashes into 1 of N buckets
+    #[arg(long, value_parser = parse_sampling, global = true)]
+    pub sample: Option<Sampling>,
+
+    /// Maximum lines per second for each pod, excess lines are dropped
+    #[arg(long, global = true)]
+    pub rate_limit: Option<f64>,
+
+    /// Maximum lines per second across all pods, excess lines are dropped
+    #[arg(long, global = true)]
+    pub global_rate_limit: Option<f64>,
+
+    /// Cluster messages into templates and print counts, first/last seen time,
+    /// level and pods per template instead of the log lines
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "follow",
+        global = true
+    )]
+    pub summarize: bool,
+
+    /// Show line counts, lines per second and error ratio per pod and level
+    /// instead of the log lines, refreshing in place in follow mode
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "summarize",
+        global = true
+    )]
+    pub stats: bool,
+
+    /// Follow logs until every pod logged a line matching this regex, then exit with code 0
+    #[arg(long, conflicts_with_all = ["summarize", "stats"], global = true)]
+    pub wait_for: Option<String>,
+
+    /// Exit with code 2 as soon as any pod logs a line matching this regex
+    #[arg(long, conflicts_with_all = ["summarize", "stats"], global = true)]
+    pub fail_on: Option<String>,
+
+    /// Give up waiting after this duration (e.g. 2m) and exit with code 3
+    #[arg(long, value_parser = parse_duration_arg, global = true)]
+    pub wait_timeout: Option<Duration>,
+}
+
+/// Parse a match scope argument
+fn parse_match_scope(s: &str) -> Result<MatchScope, String> {
+    s.parse().map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// Parse an output format argument
+fn parse_output_format(s: &str) -> Result<OutputFormat, String> {
+    s.parse().map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// Parse an output template argument
+fn parse_template(s: &str) -> Result<Template, String> {
+    Template::parse(s).map_err(|e| e.to_string())
+}
+
+/// Parse a timestamp mode argument
+fn parse_timestamp_mode(s: &str) -> Result<TimestampMode, String> {
+    s.parse().map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// Parse a JSON style argument
+fn parse_json_style(s: &str) -> Result<JsonStyle, String> {
+    s.parse().map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// Parse a color mode argument
+fn parse_color_mode(s: &str) -> Result<ColorMode, String> {
+    s.parse().map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// Parse a duration argument
+fn parse_duration_arg(s: &str) -> Result<Duration, String> {
+    parse_duration(s).map_err(|e| e.to_string())
+}
+
+/// Parse a size argument
+fn parse_size_arg(s: &str) -> Result<u64, String> {
+    parse_size(s).map_err(|e| e.to_string())
+}
+
+/// Parse a compression argument
+fn parse_compression(s: &str) -> Result<Compression, String> {
+    s.parse().map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// Parse a sampling argument
+fn parse_sampling(s: &str) -> Result<Sampling, String> {
+    s.parse().map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// Modes replacing the regular output
+#[derive(Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Follow the logs in a full-screen terminal UI with a pod sidebar,
+    /// search, level and filter toggles and pausing
+    Tui,
 }
 
 /// Parse command-line arguments
@@ -73,8 +327,10 @@ where
         }
     };
 
-    // Build Args from matches
-    let args = Args::from_arg_matches(&matches)?;
+    // Build Args from matches. Options are global so they can come before or
+    // after the subcommand, and global options can't be required by clap, so a
+    // missing deployment is reported here
+    let args = Args::from_arg_matches(&matches).map_err(|e| e.format(&mut app))?;
 
     // Debug log the arguments
     debug!("Parsed arguments: {:?}", args);
@@ -106,11 +362,11 @@ mod tests {
         assert_eq!(args.namespace, "default");
         assert_eq!(args.deployment, "nginx");
         assert_eq!(args.grep, Some("error".to_string()));
-        assert_eq!(args.follow, false);
+        assert!(!args.follow);
         assert_eq!(args.tail, Some(10));
         assert_eq!(args.level, Some("INFO".to_string()));
-        assert_eq!(args.and, false);
-        assert_eq!(args.no_highlight, false);
+        assert!(!args.and);
+        assert!(!args.no_highlight);
     }
 
     #[test]
@@ -133,7 +389,7 @@ mod tests {
         assert_eq!(args.deployment, "nginx");
         assert_eq!(args.grep, Some("error,warning".to_string()));
         assert_eq!(args.level, Some("INFO".to_string()));
-        assert_eq!(args.and, true);
-        assert_eq!(args.no_highlight, false);
+        assert!(args.and);
+        assert!(!args.no_highlight);
     }
 }
@@ -0,0 +1,186 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::debug;
+
+use crate::utils::parse_duration;
+
+/// Settings loaded from the klogrs configuration file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Alert rules evaluated over the filtered stream
+    pub alerts: Vec<AlertConfig>,
+    /// Custom redaction rules used in addition to the built-in detectors
+    pub redact: Vec<RedactConfig>,
+    /// Name of the color theme used when `--theme` is not given
+    pub theme: Option<String>,
+    /// Custom color themes by name
+    pub themes: BTreeMap<String, ThemeConfig>,
+}
+
+/// Color theme as written in the configuration file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    /// Palette for pod colors: auto, basic, 256 or truecolor
+    pub palette: Option<String>,
+    /// Explicit pod colors, used instead of the palette
+    pub pods: Vec<String>,
+    /// Message style per level, e.g. `ERROR: bold red`
+    pub levels: BTreeMap<String, String>,
+    /// Style of highlighted matches
+    pub highlight: Option<String>,
+    /// Style of timestamps
+    pub timestamp: Option<String>,
+    /// Style of JSON syntax elements: punctuation, key, string, number, literal or message
+    pub json: BTreeMap<String, String>,
+}
+
+/// Custom redaction rule as written in the configuration file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RedactConfig {
+    /// Name of the rule, shown in placeholders
+    pub name: String,
+    /// Regex for the sensitive value; if it has a capture group, only the first group is redacted
+    pub pattern: String,
+}
+
+/// Alert rule as written in the configuration file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertConfig {
+    /// Name of the rule, included in notifications
+    pub name: String,
+    /// Regex the message has to match
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Level the line has to have
+    #[serde(default)]
+    pub level: Option<String>,
+    /// The rule fires when more than this many lines match within the window
+    pub threshold: usize,
+    /// Sliding window for counting matching lines
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub window: Duration,
+    /// Minimum time between two notifications of the rule
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub cooldown: Option<Duration>,
+    /// Shell command that receives the alert as JSON on stdin
+    #[serde(default)]
+    pub command: Option<String>,
+    /// URL that receives the alert as a JSON POST request
+    #[serde(default)]
+    pub webhook: Option<String>,
+}
+
+/// Deserialize a duration such as "500ms" or "1m"
+fn deserialize_duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    parse_duration(&value).map_err(serde::de::Error::custom)
+}
+
+/// Deserialize an optional duration
+fn deserialize_optional_duration<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_duration(deserializer).map(Some)
+}
+
+impl Config {
+    /// Parse a configuration from YAML
+    pub fn from_yaml(content: &str) -> Result<Self> {
+        if content.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        serde_yaml::from_str(content).map_err(|e| anyhow!("Invalid configuration: {}", e))
+    }
+
+    /// Load the configuration from the given path, or from the default path if it exists
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match Self::default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+
+        debug!("Loading configuration from {}", path.display());
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        Self::from_yaml(&content).with_context(|| format!("In config file {}", path.display()))
+    }
+
+    /// Get the default configuration path, `$XDG_CONFIG_HOME/klogrs/config.yaml`
+    /// or `~/.config/klogrs/config.yaml`
+    pub fn default_path() -> Option<PathBuf> {
+        let base = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(base.join("klogrs").join("config.yaml"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_yaml() {
+        let config = Config::from_yaml(
+            r#"
+alerts:
+  - name: db-timeouts
+    pattern: timeout
+    level: ERROR
+    threshold: 20
+    window: 1m
+    cooldown: 10m
+    webhook: http://localhost:8080/hook
+  - name: panics
+    pattern: panic
+    threshold: 0
+    window: 30s
+    command: notify-send klogrs
+redact:
+  - name: customer
+    pattern: cust_[0-9a-f]{16}
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.alerts.len(), 2);
+        let rule = &config.alerts[0];
+        assert_eq!(rule.name, "db-timeouts");
+        assert_eq!(rule.level.as_deref(), Some("ERROR"));
+        assert_eq!(rule.window, Duration::from_secs(60));
+        assert_eq!(rule.cooldown, Some(Duration::from_secs(600)));
+        assert_eq!(config.alerts[1].cooldown, None);
+        assert_eq!(
+            config.alerts[1].command.as_deref(),
+            Some("notify-send klogrs")
+        );
+        assert_eq!(config.redact[0].name, "customer");
+    }
+
+    #[test]
+    fn test_invalid_yaml() {
+        assert!(Config::from_yaml("").unwrap().alerts.is_empty());
+        assert!(
+            Config::from_yaml("alerts:\n  - name: x\n    threshold: 1\n    window: soon\n")
+                .is_err()
+        );
+        assert!(Config::from_yaml("unknown: true\n").is_err());
+    }
+}
@@ -124,21 +124,35 @@ impl KubeClient {
             .clone()
             .unwrap_or_else(|| "default".to_string());
 
-        // Get pod status
-        let status = if let Some(status) = pod.status {
-            self.determine_pod_status(&status)
+        // Get pod status and the restarts of its containers
+        let status = if let Some(status) = &pod.status {
+            self.determine_pod_status(status)
         } else {
             PodStatus::Unknown
         };
-
-        // Get container name (use the first container)
-        let container_name = pod.spec?.containers.first()?.name.clone();
+        let restart_count = pod
+            .status
+            .as_ref()
+            .and_then(|status| status.container_statuses.as_ref())
+            .map_or(0, |statuses| {
+                statuses
+                    .iter()
+                    .map(|cs| cs.restart_count.max(0) as u32)
+                    .sum()
+            });
+
+        // Get container name (use the first container) and the node it runs on
+        let spec = pod.spec?;
+        let container_name = spec.containers.first()?.name.clone();
+        let node_name = spec.node_name;
 
         Some(PodInfo {
             name,
             namespace,
             status,
             container_name,
+            node_name,
+            restart_count,
         })
     }
 
@@ -250,7 +264,7 @@ impl KubeClient {
                         if let Some(stderr) = child.stderr.take() {
                             let mut stderr_reader = BufReader::new(stderr);
                             let mut error_message = String::new();
-                            if let Ok(_) = stderr_reader.read_line(&mut error_message).await {
+                            if stderr_reader.read_line(&mut error_message).await.is_ok() {
                                 error!(
                                     "kubectl logs for pod {} failed: {}",
                                     pod_name,
@@ -285,7 +299,9 @@ impl Clone for KubeClient {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use k8s_openapi::api::core::v1::{Container, PodSpec, PodStatus as K8sPodStatus};
+    use k8s_openapi::api::core::v1::{
+        Container, ContainerStatus, PodSpec, PodStatus as K8sPodStatus,
+    };
     use kube::api::ObjectMeta;
 
     // Mock version of KubeClient for testing
@@ -300,21 +316,35 @@ mod tests {
                 .clone()
                 .unwrap_or_else(|| "default".to_string());
 
-            // Get pod status
-            let status = if let Some(status) = pod.status {
-                self.determine_pod_status(&status)
+            // Get pod status and the restarts of its containers
+            let status = if let Some(status) = &pod.status {
+                self.determine_pod_status(status)
             } else {
                 PodStatus::Unknown
             };
+            let restart_count = pod
+                .status
+                .as_ref()
+                .and_then(|status| status.container_statuses.as_ref())
+                .map_or(0, |statuses| {
+                    statuses
+                        .iter()
+                        .map(|cs| cs.restart_count.max(0) as u32)
+                        .sum()
+                });
 
-            // Get container name (use the first container)
-            let container_name = pod.spec?.containers.first()?.name.clone();
+            // Get container name (use the first container) and the node it runs on
+            let spec = pod.spec?;
+            let container_name = spec.containers.first()?.name.clone();
+            let node_name = spec.node_name;
 
             Some(PodInfo {
                 name,
                 namespace,
                 status,
                 container_name,
+                node_name,
+                restart_count,
             })
         }
 
@@ -369,6 +399,16 @@ mod tests {
             }),
             status: Some(K8sPodStatus {
                 phase: Some("Running".to_string()),
+                container_statuses: Some(vec![
+                    ContainerStatus {
+                        restart_count: 2,
+                        ..Default::default()
+                    },
+                    ContainerStatus {
+                        restart_count: 3,
+                        ..Default::default()
+                    },
+                ]),
                 ..Default::default()
             }),
         };
@@ -378,6 +418,7 @@ mod tests {
         assert_eq!(pod_info.namespace, "test-namespace");
         assert_eq!(pod_info.container_name, "main-container");
         assert!(matches!(pod_info.status, PodStatus::Running));
+        assert_eq!(pod_info.restart_count, 5);
     }
 
     #[test]
@@ -1,7 +1,14 @@
 use super::PodInfo;
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use futures::{Stream, StreamExt};
+use regex::Regex;
+use serde::de::{MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::value::RawValue;
+use std::fmt;
 use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
 use tokio::sync::mpsc;
 use tokio::time::{sleep, Duration};
 use tracing::{debug, error, info};
@@ -9,39 +16,394 @@ use tracing::{debug, error, info};
 /// Type alias for a boxed stream of log lines
 pub type LogStream = Pin<Box<dyn Stream<Item = Result<Vec<u8>>> + Send>>;
 
+/// Structured fields and level of a message, parsed when first needed
+#[derive(Debug, Clone, Default)]
+struct Parsed {
+    fields: OnceLock<Fields>,
+    level: OnceLock<Option<&'static str>>,
+}
+
+/// Structured fields of a message, with where each value is written in it
+#[derive(Debug, Clone, Default)]
+struct Fields {
+    values: Vec<(String, String)>,
+    /// Byte range of each value in the message, inside the quotes of strings
+    spans: Vec<(usize, usize)>,
+    /// Whether the message is a JSON object
+    json: bool,
+}
+
+/// Where a log entry comes from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Source {
+    /// A line of the entry's own container
+    #[default]
+    Container,
+    /// Identical lines of several pods merged into one entry
+    Merged(Vec<Arc<str>>),
+    /// A note written by klogrs itself, such as how many lines were dropped
+    Note,
+}
+
 /// Log entry with metadata
-#[derive(Debug, Clone)]
+///
+/// Names are shared by all entries of a container stream, so cloning an
+/// entry or creating one per line doesn't copy them. The fields and level of
+/// the message are parsed once, so the message is changed with `set_message`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogEntry {
+    /// Pod namespace
+    pub namespace: Arc<str>,
     /// Pod name
-    pub pod_name: String,
+    pub pod_name: Arc<str>,
+    /// Container name
+    pub container_name: Arc<str>,
     /// Raw log line
     pub raw_line: String,
-    /// Log message (without timestamp)
-    pub message: String,
+    /// Timestamp added by Kubernetes, if present
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Log message (without timestamp), changed with `set_message`
+    message: String,
+    /// Whether the entry is a container's line, merged from several pods or a note
+    #[serde(default)]
+    pub source: Source,
+    /// Fields and level parsed from the message
+    #[serde(skip)]
+    parsed: Parsed,
 }
 
 impl LogEntry {
+    /// Create an entry for a message without a Kubernetes timestamp
+    pub fn new(
+        namespace: impl Into<Arc<str>>,
+        pod_name: impl Into<Arc<str>>,
+        container_name: impl Into<Arc<str>>,
+        message: String,
+    ) -> Self {
+        Self {
+            namespace: namespace.into(),
+            pod_name: pod_name.into(),
+            container_name: container_name.into(),
+            raw_line: message.clone(),
+            timestamp: None,
+            message,
+            source: Source::Container,
+            parsed: Parsed::default(),
+        }
+    }
+
+    /// Set where the entry comes from
+    pub fn with_source(mut self, source: Source) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Get the pods the entry comes from, none for notes
+    pub fn pods(&self) -> &[Arc<str>] {
+        match &self.source {
+            Source::Container => std::slice::from_ref(&self.pod_name),
+            Source::Merged(pods) => pods,
+            Source::Note => &[],
+        }
+    }
+
+    /// Create an entry of a pod's `main` container in the `default` namespace
+    #[cfg(test)]
+    pub fn for_test(pod_name: &str, message: &str) -> Self {
+        Self::new("default", pod_name, "main", message.to_string())
+    }
+
     /// Parse a raw log line into a LogEntry
-    pub fn parse(pod_name: String, raw_line: String) -> Self {
+    ///
+    /// The line is only cleaned when it needs to be, and the message is the one
+    /// copy made of it. Pass shared names so they aren't allocated per line.
+    pub fn parse(
+        namespace: impl Into<Arc<str>>,
+        pod_name: impl Into<Arc<str>>,
+        container_name: impl Into<Arc<str>>,
+        mut raw_line: String,
+    ) -> Self {
         // Clean the line
-        let clean_line = raw_line.replace('\r', "").replace('\0', "");
+        if raw_line.contains(['\r', '\0']) {
+            raw_line.retain(|c| c != '\r' && c != '\0');
+        }
 
         // Try to extract the message without timestamp
         // Kubernetes log timestamp format is typically: YYYY-MM-DDTHH:MM:SS.sssssssssZ
-        let message = if let Some(timestamp_end) = clean_line.find(" ") {
+        let message = match raw_line.find(' ') {
             // If we find a space after the timestamp, extract everything after it
-            clean_line[timestamp_end + 1..].to_string()
-        } else {
+            Some(timestamp_end) => raw_line[timestamp_end + 1..].to_string(),
             // If we can't find a timestamp pattern, use the whole line
-            clean_line.clone()
+            None => raw_line.clone(),
         };
 
+        // Parse the timestamp added by `kubectl logs --timestamps`
+        let timestamp = raw_line
+            .split(' ')
+            .next()
+            .and_then(|prefix| DateTime::parse_from_rfc3339(prefix).ok())
+            .map(|ts| ts.with_timezone(&Utc));
+
         Self {
-            pod_name,
-            raw_line: clean_line,
+            namespace: namespace.into(),
+            pod_name: pod_name.into(),
+            container_name: container_name.into(),
+            raw_line,
+            timestamp,
             message,
+            source: Source::Container,
+            parsed: Parsed::default(),
+        }
+    }
+
+    /// Detect the log level of the message
+    ///
+    /// A `level`, `lvl` or `severity` field takes precedence, otherwise the
+    /// first level keyword in the message is used. Returns the canonical level
+    /// name (TRACE, DEBUG, INFO, WARN, ERROR or FATAL).
+    pub fn level(&self) -> Option<&'static str> {
+        *self.parsed.level.get_or_init(|| self.detect_level())
+    }
+
+    /// Detect the level from a level field or the first level keyword
+    fn detect_level(&self) -> Option<&'static str> {
+        static LEVEL_REGEX: OnceLock<Regex> = OnceLock::new();
+        let regex = LEVEL_REGEX.get_or_init(|| {
+            Regex::new(r"(?i)\b(TRACE|DEBUG|INFO|WARN(?:ING)?|ERR(?:OR)?|FATAL|CRITICAL|PANIC)\b")
+                .expect("valid level regex")
+        });
+
+        let field = self
+            .fields()
+            .iter()
+            .find(|(key, _)| matches!(key.as_str(), "level" | "lvl" | "severity"))
+            .map(|(_, value)| value.as_str());
+
+        let text = field.unwrap_or(&self.message);
+        regex
+            .captures(text)
+            .and_then(|caps| canonical_level(&caps[1]))
+    }
+
+    /// Get the structured fields of the message
+    ///
+    /// JSON objects are flattened to their top-level keys, with nested values
+    /// kept as compact JSON. Other messages are parsed as logfmt `key=value`
+    /// pairs. Returns an empty list when the message has no fields.
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.parsed_fields().values
+    }
+
+    /// Get the structured fields as JSON values
+    ///
+    /// Values of JSON messages keep their type, strings and logfmt values
+    /// become JSON strings.
+    pub fn json_fields(&self) -> serde_json::Map<String, serde_json::Value> {
+        let fields = self.parsed_fields();
+        fields
+            .values
+            .iter()
+            .zip(&fields.spans)
+            .map(|((key, value), &(start, _))| {
+                // Only non-string JSON values are written without quotes
+                let quoted = start > 0 && self.message.as_bytes()[start - 1] == b'"';
+                let value = if fields.json && !quoted {
+                    serde_json::from_str(value)
+                        .unwrap_or_else(|_| serde_json::Value::String(value.clone()))
+                } else {
+                    serde_json::Value::String(value.clone())
+                };
+                (key.clone(), value)
+            })
+            .collect()
+    }
+
+    /// Get the value of a single structured field
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields()
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Get the byte range of a field's value as written in the message
+    ///
+    /// String values are covered without their quotes but with any escapes.
+    pub fn field_span(&self, name: &str) -> Option<(usize, usize)> {
+        let fields = self.parsed_fields();
+        let index = fields.values.iter().position(|(key, _)| key == name)?;
+        Some(fields.spans[index])
+    }
+
+    /// Get the fields of the message, parsing them on first use
+    fn parsed_fields(&self) -> &Fields {
+        self.parsed
+            .fields
+            .get_or_init(|| parse_fields(&self.message))
+    }
+
+    /// Get the log message, without the Kubernetes timestamp
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Replace the message, parsing its fields and level again when needed
+    pub fn set_message(&mut self, message: String) {
+        self.message = message;
+        self.parsed = Parsed::default();
+    }
+}
+
+/// Top-level members of a JSON object, with their values as written
+struct RawMembers<'a>(Vec<(String, &'a RawValue)>);
+
+impl<'de> Deserialize<'de> for RawMembers<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MembersVisitor;
+
+        impl<'de> Visitor<'de> for MembersVisitor {
+            type Value = RawMembers<'de>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut members = Vec::new();
+                while let Some(member) = map.next_entry()? {
+                    members.push(member);
+                }
+                Ok(RawMembers(members))
+            }
+        }
+
+        deserializer.deserialize_map(MembersVisitor)
+    }
+}
+
+/// Parse the fields of a JSON object or logfmt message
+fn parse_fields(message: &str) -> Fields {
+    let offset = message.len() - message.trim_start().len();
+    let trimmed = message.trim();
+    if trimmed.starts_with('{') {
+        if let Ok(RawMembers(members)) = serde_json::from_str(trimmed) {
+            let mut fields = Fields {
+                json: true,
+                ..Fields::default()
+            };
+            for (key, raw) in members {
+                let text = raw.get();
+                let start = offset + (text.as_ptr() as usize - trimmed.as_ptr() as usize);
+                let (value, span) = match serde_json::from_str::<serde_json::Value>(text) {
+                    Ok(serde_json::Value::String(s)) => (s, (start + 1, start + text.len() - 1)),
+                    Ok(other) => (other.to_string(), (start, start + text.len())),
+                    Err(_) => continue,
+                };
+                // A repeated key keeps its first position and its last value
+                match fields.values.iter().position(|(name, _)| *name == key) {
+                    Some(index) => {
+                        fields.values[index].1 = value;
+                        fields.spans[index] = span;
+                    }
+                    None => {
+                        fields.values.push((key, value));
+                        fields.spans.push(span);
+                    }
+                }
+            }
+            return fields;
+        }
+    }
+
+    parse_logfmt(trimmed, offset)
+}
+
+/// Map a level keyword to its canonical name
+pub fn canonical_level(level: &str) -> Option<&'static str> {
+    match level.to_uppercase().as_str() {
+        "TRACE" => Some("TRACE"),
+        "DEBUG" => Some("DEBUG"),
+        "INFO" => Some("INFO"),
+        "WARN" | "WARNING" => Some("WARN"),
+        "ERR" | "ERROR" => Some("ERROR"),
+        "FATAL" | "CRITICAL" | "PANIC" => Some("FATAL"),
+        _ => None,
+    }
+}
+
+/// Parse logfmt `key=value` pairs, where values may be double-quoted
+///
+/// Spans are shifted by `offset`, the position of the text in the message.
+fn parse_logfmt(text: &str, offset: usize) -> Fields {
+    let mut fields = Fields::default();
+    let mut chars = text.char_indices().peekable();
+    // Position of the next character, or the end of the text
+    let position = |chars: &mut std::iter::Peekable<std::str::CharIndices>| {
+        chars.peek().map_or(text.len(), |&(i, _)| i)
+    };
+
+    while chars.peek().is_some() {
+        // Skip whitespace between pairs
+        while chars.peek().is_some_and(|(_, c)| c.is_whitespace()) {
+            chars.next();
+        }
+
+        // Read the key up to '=' or whitespace
+        let mut key = String::new();
+        while let Some(&(_, c)) = chars.peek() {
+            if c == '=' || c.is_whitespace() {
+                break;
+            }
+            key.push(c);
+            chars.next();
+        }
+
+        // Bare words without '=' are not fields
+        if chars.peek().map(|&(_, c)| c) != Some('=') {
+            continue;
+        }
+        chars.next();
+
+        let mut value = String::new();
+        let start;
+        let mut end;
+        if chars.peek().map(|&(_, c)| c) == Some('"') {
+            chars.next();
+            start = position(&mut chars);
+            end = text.len();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some((_, escaped)) = chars.next() {
+                            value.push(escaped);
+                        }
+                    }
+                    '"' => {
+                        end = i;
+                        break;
+                    }
+                    _ => value.push(c),
+                }
+            }
+        } else {
+            start = position(&mut chars);
+            while let Some(&(_, c)) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                value.push(c);
+                chars.next();
+            }
+            end = position(&mut chars);
+        }
+
+        if !key.is_empty() {
+            fields.values.push((key, value));
+            fields.spans.push((offset + start, offset + end));
         }
     }
+
+    fields
 }
 
 /// Log aggregator for multiple pods
@@ -68,21 +430,29 @@ impl LogAggregator {
         mut log_stream: LogStream,
     ) -> Result<()> {
         let tx = self.tx.clone();
-        let pod_name = pod_info.name.clone();
+        let pod_name: Arc<str> = pod_info.name.into();
+        let namespace: Arc<str> = pod_info.namespace.into();
+        let container_name: Arc<str> = pod_info.container_name.into();
 
         // Spawn a task to process this pod's logs
         tokio::spawn(async move {
             while let Some(line_result) = log_stream.next().await {
                 match line_result {
                     Ok(bytes) => {
-                        // Convert bytes to string
-                        let line_str = String::from_utf8_lossy(&bytes).to_string();
+                        // Convert bytes to string, copying only invalid UTF-8
+                        let line_str = String::from_utf8(bytes)
+                            .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned());
 
                         // Process the line
                         debug!("Received log line from pod {}: {}", pod_name, line_str);
 
                         // Create log entry
-                        let entry = LogEntry::parse(pod_name.clone(), line_str);
+                        let entry = LogEntry::parse(
+                            namespace.clone(),
+                            pod_name.clone(),
+                            container_name.clone(),
+                            line_str,
+                        );
 
                         // Send to channel with minimal delay
                         if let Err(e) = tx.send(Ok(entry)).await {
@@ -114,3 +484,128 @@ impl LogAggregator {
         self.rx
     }
 }
+
+impl Default for LogAggregator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_entry(message: &str) -> LogEntry {
+        LogEntry::parse(
+            "default",
+            "test-pod".to_string(),
+            "main".to_string(),
+            format!("2024-05-01T10:00:00.000000000Z {}", message),
+        )
+    }
+
+    #[test]
+    fn test_parse() {
+        let entry = create_test_entry("Hello, world!\r");
+
+        assert_eq!(&*entry.container_name, "main");
+        assert_eq!(
+            entry.raw_line,
+            "2024-05-01T10:00:00.000000000Z Hello, world!"
+        );
+        assert_eq!(entry.message(), "Hello, world!");
+        assert_eq!(
+            entry.timestamp.map(|ts| ts.to_rfc3339()),
+            Some("2024-05-01T10:00:00+00:00".to_string())
+        );
+
+        let entry = LogEntry::parse("default", "p", "c", "no timestamp".to_string());
+        assert_eq!(&*entry.namespace, "default");
+        assert_eq!(entry.timestamp, None);
+    }
+
+    #[test]
+    fn test_level() {
+        assert_eq!(create_test_entry("[ERROR] boom").level(), Some("ERROR"));
+        assert_eq!(
+            create_test_entry("W0501 warning: disk").level(),
+            Some("WARN")
+        );
+        assert_eq!(
+            create_test_entry(r#"{"severity":"critical","msg":"info lost"}"#).level(),
+            Some("FATAL")
+        );
+        assert_eq!(
+            create_test_entry("level=debug msg=\"error budget\"").level(),
+            Some("DEBUG")
+        );
+        assert_eq!(create_test_entry("no level here").level(), None);
+    }
+
+    #[test]
+    fn test_json_fields() {
+        let entry =
+            create_test_entry(r#"{"level":"error","msg":"boom","code":500,"ctx":{"id":1}}"#);
+
+        assert_eq!(entry.field("level"), Some("error"));
+        assert_eq!(entry.field("code"), Some("500"));
+        assert_eq!(entry.field("ctx"), Some(r#"{"id":1}"#));
+        assert_eq!(entry.field("missing"), None);
+
+        let span = |name| {
+            let (start, end) = entry.field_span(name)?;
+            Some(&entry.message()[start..end])
+        };
+        assert_eq!(span("msg"), Some("boom"));
+        assert_eq!(span("ctx"), Some(r#"{"id":1}"#));
+
+        // Spans point at the value as written, escapes included
+        let entry = create_test_entry(r#"  {"a":"msg","msg":"say \"hi\""}"#);
+        let (start, end) = entry.field_span("msg").unwrap();
+        assert_eq!(&entry.message()[start..end], r#"say \"hi\""#);
+        assert_eq!(entry.field("msg"), Some(r#"say "hi""#));
+
+        // JSON values keep their type, strings that look like numbers included
+        let entry = create_test_entry(r#"{"code":"500","retry":3,"ok":null,"ctx":{"id":1}}"#);
+        assert_eq!(
+            serde_json::Value::Object(entry.json_fields()),
+            serde_json::json!({"code": "500", "retry": 3, "ok": null, "ctx": {"id": 1}})
+        );
+        let entry = create_test_entry("retry=3 empty=\"\"");
+        assert_eq!(
+            serde_json::Value::Object(entry.json_fields()),
+            serde_json::json!({"retry": "3", "empty": ""})
+        );
+    }
+
+    #[test]
+    fn test_logfmt_fields() {
+        let entry = create_test_entry(r#"level=warn msg="slow \"query\"" took=3s done"#);
+
+        assert_eq!(
+            entry.fields(),
+            [
+                ("level".to_string(), "warn".to_string()),
+                ("msg".to_string(), "slow \"query\"".to_string()),
+                ("took".to_string(), "3s".to_string()),
+            ]
+        );
+        assert!(create_test_entry("plain text message").fields().is_empty());
+
+        let (start, end) = entry.field_span("msg").unwrap();
+        assert_eq!(&entry.message()[start..end], r#"slow \"query\""#);
+        let (start, end) = entry.field_span("took").unwrap();
+        assert_eq!(&entry.message()[start..end], "3s");
+    }
+
+    #[test]
+    fn test_set_message_parses_again() {
+        let mut entry = create_test_entry("level=info msg=started");
+        assert_eq!(entry.level(), Some("INFO"));
+        assert_eq!(entry.field("msg"), Some("started"));
+
+        entry.set_message("level=error msg=crashed".to_string());
+        assert_eq!(entry.level(), Some("ERROR"));
+        assert_eq!(entry.field("msg"), Some("crashed"));
+    }
+}
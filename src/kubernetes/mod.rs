@@ -1,7 +1,9 @@
 pub mod client;
 pub mod log;
+pub mod names;
 pub mod pod;
 
 pub use client::KubeClient;
-pub use log::{LogAggregator, LogEntry, LogStream};
+pub use log::{LogAggregator, LogEntry, LogStream, Source};
+pub use names::ShortNames;
 pub use pod::{PodInfo, PodStatus};
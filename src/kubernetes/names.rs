@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+/// Unique short names for a set of pods
+///
+/// Pods of a workload share a name prefix such as `nginx-deployment`, so the
+/// short name drops the dash-separated segments that all known pods have in
+/// common and keeps the rest. Replicas of one ReplicaSet are told apart by
+/// their pod suffix, and during a rollout the ReplicaSet hash is kept too.
+/// Names are recomputed when a pod joins, so they stay unique.
+#[derive(Debug, Clone, Default)]
+pub struct ShortNames {
+    /// Short name of each known pod
+    names: HashMap<String, String>,
+}
+
+impl ShortNames {
+    /// Compute short names for the given pods
+    pub fn new<'a>(pod_names: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut names = Self::default();
+        names.extend(pod_names);
+        names
+    }
+
+    /// Add a pod, recomputing the short names if it is new
+    ///
+    /// Returns whether the pod was new.
+    pub fn insert(&mut self, pod_name: &str) -> bool {
+        self.extend([pod_name])
+    }
+
+    /// Add pods, recomputing the short names if any of them is new
+    ///
+    /// Returns whether any pod was new.
+    pub fn extend<'a>(&mut self, pod_names: impl IntoIterator<Item = &'a str>) -> bool {
+        let mut added = false;
+        for pod_name in pod_names {
+            if !self.names.contains_key(pod_name) {
+                self.names.insert(pod_name.to_string(), String::new());
+                added = true;
+            }
+        }
+        if added {
+            self.recompute();
+        }
+        added
+    }
+
+    /// Get the short name of a pod, or the full name of an unknown pod
+    pub fn get<'a>(&'a self, pod_name: &'a str) -> &'a str {
+        self.names.get(pod_name).map_or(pod_name, String::as_str)
+    }
+
+    /// Recompute the short names of all known pods
+    fn recompute(&mut self) {
+        let pod_names: Vec<String> = self.names.keys().cloned().collect();
+        let short = short_names(&pod_names);
+        for (pod_name, short) in pod_names.into_iter().zip(short) {
+            self.names.insert(pod_name, short);
+        }
+    }
+}
+
+/// Compute unique short names, in the order of the given pod names
+///
+/// The segments shared by all pods are dropped, keeping at least one segment
+/// of each name. A single pod keeps its last two segments, which are the
+/// ReplicaSet hash and pod suffix of a Deployment's pod.
+pub fn short_names(pod_names: &[String]) -> Vec<String> {
+    let segments: Vec<Vec<&str>> = pod_names
+        .iter()
+        .map(|name| name.split('-').collect())
+        .collect();
+
+    let strip = match segments.as_slice() {
+        [] => return Vec::new(),
+        [single] => single.len().saturating_sub(2),
+        [first, rest @ ..] => {
+            let common = (0..first.len())
+                .take_while(|&i| rest.iter().all(|other| other.get(i) == first.get(i)))
+                .count();
+            let shortest = segments.iter().map(Vec::len).min().unwrap_or_default();
+            common.min(shortest - 1)
+        }
+    };
+    segments
+        .iter()
+        .map(|segments| segments[strip..].join("-"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shorten(pod_names: &[&str]) -> Vec<String> {
+        short_names(
+            &pod_names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn test_short_names() {
+        // Replicas of one ReplicaSet differ only in their suffix
+        assert_eq!(
+            shorten(&[
+                "nginx-deployment-abc12-xyz34",
+                "nginx-deployment-abc12-qrs56"
+            ]),
+            ["xyz34", "qrs56"]
+        );
+        // During a rollout the ReplicaSet hash tells old and new pods apart
+        assert_eq!(
+            shorten(&[
+                "nginx-deployment-abc12-xyz34",
+                "nginx-deployment-def45-xyz34"
+            ]),
+            ["abc12-xyz34", "def45-xyz34"]
+        );
+        // StatefulSet pods keep their ordinal
+        assert_eq!(shorten(&["web-0", "web-1"]), ["0", "1"]);
+        // Names are never shortened to nothing
+        assert_eq!(shorten(&["web", "web-1"]), ["web", "web-1"]);
+        assert_eq!(
+            shorten(&["api-7d9f8-a", "worker-5c4b2-b"]),
+            ["api-7d9f8-a", "worker-5c4b2-b"]
+        );
+    }
+
+    #[test]
+    fn test_single_pod() {
+        assert_eq!(shorten(&["nginx-deployment-abc12-xyz34"]), ["abc12-xyz34"]);
+        assert_eq!(shorten(&["test-pod"]), ["test-pod"]);
+        assert_eq!(shorten(&["standalone"]), ["standalone"]);
+        assert!(shorten(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_pods_joining() {
+        let mut names = ShortNames::new(["nginx-abc12-xyz34", "nginx-abc12-qrs56"]);
+        assert_eq!(names.get("nginx-abc12-xyz34"), "xyz34");
+        assert_eq!(names.get("unknown-pod"), "unknown-pod");
+        assert!(!names.insert("nginx-abc12-xyz34"));
+
+        // A pod of the new ReplicaSet makes the hash part of every name
+        assert!(names.insert("nginx-def45-uvw78"));
+        assert_eq!(names.get("nginx-abc12-xyz34"), "abc12-xyz34");
+        assert_eq!(names.get("nginx-def45-uvw78"), "def45-uvw78");
+    }
+}
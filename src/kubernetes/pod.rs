@@ -33,24 +33,19 @@ pub struct PodInfo {
     pub status: PodStatus,
     /// Container name
     pub container_name: String,
+    /// Node the pod is scheduled on
+    pub node_name: Option<String>,
+    /// Number of container restarts, summed over all containers
+    pub restart_count: u32,
 }
 
 impl PodInfo {
     /// Check if the pod is in a state where logs can be retrieved
     pub fn can_get_logs(&self) -> bool {
-        match self.status {
-            PodStatus::Running | PodStatus::CrashLoopBackOff => true,
-            _ => false,
-        }
-    }
-
-    /// Get a short name for the pod (first 8 characters)
-    pub fn short_name(&self) -> String {
-        if self.name.len() <= 8 {
-            self.name.clone()
-        } else {
-            self.name[..8].to_string()
-        }
+        matches!(
+            self.status,
+            PodStatus::Running | PodStatus::CrashLoopBackOff
+        )
     }
 }
 
@@ -77,6 +72,8 @@ mod tests {
             namespace: "default".to_string(),
             status: PodStatus::Running,
             container_name: "main".to_string(),
+            node_name: None,
+            restart_count: 0,
         };
         assert!(running_pod.can_get_logs());
 
@@ -85,6 +82,8 @@ mod tests {
             namespace: "default".to_string(),
             status: PodStatus::CrashLoopBackOff,
             container_name: "main".to_string(),
+            node_name: None,
+            restart_count: 0,
         };
         assert!(crash_pod.can_get_logs());
 
@@ -93,26 +92,9 @@ mod tests {
             namespace: "default".to_string(),
             status: PodStatus::Terminated,
             container_name: "main".to_string(),
+            node_name: None,
+            restart_count: 0,
         };
         assert!(!terminated_pod.can_get_logs());
     }
-
-    #[test]
-    fn test_short_name() {
-        let short_pod = PodInfo {
-            name: "short".to_string(),
-            namespace: "default".to_string(),
-            status: PodStatus::Running,
-            container_name: "main".to_string(),
-        };
-        assert_eq!(short_pod.short_name(), "short");
-
-        let long_pod = PodInfo {
-            name: "very-long-pod-name".to_string(),
-            namespace: "default".to_string(),
-            status: PodStatus::Running,
-            container_name: "main".to_string(),
-        };
-        assert_eq!(long_pod.short_name(), "very-lon");
-    }
 }
@@ -1,6 +1,10 @@
 pub mod cli;
+pub mod config;
 pub mod kubernetes;
 pub mod log_processor;
+pub mod output;
+pub mod tui;
 pub mod utils;
 
 pub use cli::Args;
+pub use config::Config;
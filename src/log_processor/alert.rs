@@ -0,0 +1,546 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use hyper::{Body, Client, Request};
+use regex::Regex;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinSet;
+use tracing::{error, info, warn};
+
+use crate::config::AlertConfig;
+use crate::kubernetes::log::{canonical_level, LogEntry};
+use crate::log_processor::pipe::CommandPipe;
+use crate::log_processor::stage::Stage;
+
+/// Maximum number of matching lines included in a notification
+const MAX_ALERT_LINES: usize = 50;
+
+/// How long a webhook request may take
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What happens when an alert fires
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertAction {
+    /// Run a shell command with the alert as JSON on stdin
+    Command(String),
+    /// POST the alert as JSON to a URL
+    Webhook(String),
+}
+
+/// Threshold rule over a sliding window of matching lines
+#[derive(Debug, Clone)]
+pub struct AlertRule {
+    /// Name of the rule
+    pub name: String,
+    /// Regex the message has to match
+    pattern: Option<Regex>,
+    /// Canonical level the line has to have
+    level: Option<&'static str>,
+    /// Number of matching lines that has to be exceeded
+    pub threshold: usize,
+    /// Sliding window for counting
+    pub window: Duration,
+    /// Minimum time between two notifications
+    pub cooldown: Duration,
+    /// Action to run when the rule fires
+    pub action: AlertAction,
+}
+
+impl AlertRule {
+    /// Build a rule from its configuration
+    pub fn from_config(config: &AlertConfig) -> Result<Self> {
+        let pattern = config
+            .pattern
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| anyhow!("Invalid pattern in alert {}: {}", config.name, e))?;
+
+        let level = config
+            .level
+            .as_deref()
+            .map(|level| {
+                canonical_level(level)
+                    .ok_or_else(|| anyhow!("Invalid level in alert {}: {}", config.name, level))
+            })
+            .transpose()?;
+
+        let action = match (&config.command, &config.webhook) {
+            (Some(command), None) => AlertAction::Command(command.clone()),
+            (None, Some(url)) if url.starts_with("http://") || url.starts_with("https://") => {
+                AlertAction::Webhook(url.clone())
+            }
+            (None, Some(url)) => {
+                return Err(anyhow!(
+                    "Invalid webhook URL in alert {}: {}",
+                    config.name,
+                    url
+                ))
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Alert {} needs exactly one of command or webhook",
+                    config.name
+                ))
+            }
+        };
+
+        Ok(Self {
+            name: config.name.clone(),
+            pattern,
+            level,
+            threshold: config.threshold,
+            window: config.window,
+            cooldown: config.cooldown.unwrap_or(config.window),
+            action,
+        })
+    }
+
+    /// Check whether a line counts towards the rule
+    fn matches(&self, entry: &LogEntry) -> bool {
+        if let Some(level) = &self.level {
+            match entry.level() {
+                Some(entry_level) if entry_level == *level => {}
+                _ => return false,
+            }
+        }
+        self.pattern
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(entry.message()))
+    }
+}
+
+/// A fired alert with the lines that triggered it
+#[derive(Debug, Clone)]
+pub struct Alert {
+    /// Name of the rule that fired
+    pub rule: String,
+    /// Number of matching lines in the window
+    pub count: usize,
+    /// Threshold of the rule
+    pub threshold: usize,
+    /// Window of the rule
+    pub window: Duration,
+    /// Most recent matching lines
+    pub lines: Vec<LogEntry>,
+}
+
+impl Alert {
+    /// Build the JSON payload sent to commands and webhooks
+    pub fn to_json(&self) -> Value {
+        let lines: Vec<Value> = self
+            .lines
+            .iter()
+            .map(|entry| {
+                json!({
+                    "namespace": entry.namespace,
+                    "pod": entry.pod_name,
+                    "container": entry.container_name,
+                    "timestamp": entry.timestamp.map(|ts| ts.to_rfc3339()),
+                    "message": entry.message(),
+                })
+            })
+            .collect();
+
+        json!({
+            "alert": self.rule,
+            "count": self.count,
+            "threshold": self.threshold,
+            "window_seconds": self.window.as_secs_f64(),
+            "fired_at": Utc::now().to_rfc3339(),
+            "lines": lines,
+        })
+    }
+}
+
+/// Sliding window state of a rule
+struct RuleState {
+    rule: AlertRule,
+    /// Times of matching lines within the window
+    hits: VecDeque<DateTime<Utc>>,
+    /// Most recent matching lines within the window
+    lines: VecDeque<(DateTime<Utc>, LogEntry)>,
+    /// When the rule last fired
+    last_fired: Option<DateTime<Utc>>,
+}
+
+/// Evaluates alert rules over a stream of log entries
+pub struct AlertEngine {
+    rules: Vec<RuleState>,
+}
+
+impl AlertEngine {
+    /// Create an engine for the given rules
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            rules: rules
+                .into_iter()
+                .map(|rule| RuleState {
+                    rule,
+                    hits: VecDeque::new(),
+                    lines: VecDeque::new(),
+                    last_fired: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Get the rules of the engine
+    pub fn rules(&self) -> impl Iterator<Item = &AlertRule> {
+        self.rules.iter().map(|state| &state.rule)
+    }
+
+    /// Count an entry logged at the given time and return the alerts that fired
+    pub fn observe(&mut self, entry: &LogEntry, now: DateTime<Utc>) -> Vec<(Alert, AlertAction)> {
+        let mut fired = Vec::new();
+
+        for state in &mut self.rules {
+            if !state.rule.matches(entry) {
+                continue;
+            }
+
+            // Drop hits that left the window. Lines of different pods can
+            // arrive slightly out of order, so the whole window is checked.
+            let window = state.rule.window;
+            state.hits.retain(|&time| elapsed(time, now) < window);
+            state.lines.retain(|(time, _)| elapsed(*time, now) < window);
+
+            state.hits.push_back(now);
+            state.lines.push_back((now, entry.clone()));
+            if state.lines.len() > MAX_ALERT_LINES {
+                state.lines.pop_front();
+            }
+
+            if state.hits.len() <= state.rule.threshold {
+                continue;
+            }
+            let cooling_down = state
+                .last_fired
+                .is_some_and(|last| elapsed(last, now) < state.rule.cooldown);
+            if cooling_down {
+                continue;
+            }
+
+            fired.push((
+                Alert {
+                    rule: state.rule.name.clone(),
+                    count: state.hits.len(),
+                    threshold: state.rule.threshold,
+                    window,
+                    lines: state.lines.drain(..).map(|(_, entry)| entry).collect(),
+                },
+                state.rule.action.clone(),
+            ));
+            state.hits.clear();
+            state.last_fired = Some(now);
+        }
+
+        fired
+    }
+}
+
+/// Time between two log timestamps, zero if `later` is before `earlier`
+fn elapsed(earlier: DateTime<Utc>, later: DateTime<Utc>) -> Duration {
+    (later - earlier).to_std().unwrap_or_default()
+}
+
+/// Send an alert to a webhook as a JSON POST request
+pub async fn send_webhook(url: &str, alert: &Alert) -> Result<()> {
+    let https = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_native_roots()
+        .https_or_http()
+        .enable_http1()
+        .build();
+    let client: Client<_, Body> = Client::builder().build(https);
+
+    let request = Request::post(url)
+        .header("content-type", "application/json")
+        .body(Body::from(alert.to_json().to_string()))?;
+
+    let response = tokio::time::timeout(WEBHOOK_TIMEOUT, client.request(request))
+        .await
+        .map_err(|_| anyhow!("Webhook request to {} timed out", url))??;
+
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Webhook {} responded with status {}",
+            url,
+            response.status()
+        ));
+    }
+    Ok(())
+}
+
+/// Run the action of a fired alert
+pub async fn dispatch(alert: &Alert, action: &AlertAction) -> Result<()> {
+    match action {
+        AlertAction::Command(command) => {
+            let pipe = CommandPipe::new(command);
+            let payload = alert.to_json().to_string();
+            tokio::task::spawn_blocking(move || pipe.run(&payload))
+                .await?
+                .map(|_| ())
+        }
+        AlertAction::Webhook(url) => send_webhook(url, alert).await,
+    }
+}
+
+/// Run the actions sent over the channel concurrently until it is closed
+fn run_actions(mut receiver: UnboundedReceiver<(Alert, AlertAction)>) {
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            error!("Failed to start the alert action runtime: {}", e);
+            return;
+        }
+    };
+
+    runtime.block_on(async move {
+        let mut actions = JoinSet::new();
+        while let Some((alert, action)) = receiver.recv().await {
+            actions.spawn(async move {
+                if let Err(e) = dispatch(&alert, &action).await {
+                    error!("Failed to run action for alert {}: {}", alert.rule, e);
+                }
+            });
+            // Reap the actions that already finished
+            while actions.try_join_next().is_some() {}
+        }
+        while let Some(result) = actions.join_next().await {
+            if result.is_err() {
+                warn!("Alert action panicked");
+            }
+        }
+    });
+}
+
+/// Pass-through stage that evaluates alert rules and runs their actions
+///
+/// Actions run on a background worker so slow commands or webhooks don't
+/// hold up the stream. Pending actions are waited for when the stream ends.
+pub struct AlertStage {
+    engine: AlertEngine,
+    /// Channel to the action worker, started when the first alert fires
+    actions: Option<UnboundedSender<(Alert, AlertAction)>>,
+    /// Thread running the action worker
+    worker: Option<JoinHandle<()>>,
+}
+
+impl AlertStage {
+    /// Create a new alert stage
+    pub fn new(engine: AlertEngine) -> Self {
+        Self {
+            engine,
+            actions: None,
+            worker: None,
+        }
+    }
+
+    /// Hand a fired alert to the action worker
+    fn send(&mut self, alert: Alert, action: AlertAction) {
+        let actions = self.actions.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::unbounded_channel();
+            self.worker = Some(thread::spawn(move || run_actions(receiver)));
+            sender
+        });
+        if actions.send((alert, action)).is_err() {
+            error!("Alert action worker is not running");
+        }
+    }
+}
+
+impl Stage for AlertStage {
+    fn process(&mut self, entry: LogEntry) -> Vec<LogEntry> {
+        // Windows follow the log timestamps so a backlog or a dump is counted
+        // by when the lines were written, not by when they were read
+        let time = entry.timestamp.unwrap_or_else(Utc::now);
+        for (alert, action) in self.engine.observe(&entry, time) {
+            info!(
+                "Alert {} fired: {} matching lines within {:?}",
+                alert.rule, alert.count, alert.window
+            );
+            self.send(alert, action);
+        }
+
+        vec![entry]
+    }
+
+    fn finish(&mut self) -> Vec<LogEntry> {
+        // Closing the channel lets the worker finish the pending actions
+        self.actions = None;
+        if let Some(worker) = self.worker.take() {
+            if worker.join().is_err() {
+                warn!("Alert action worker panicked");
+            }
+        }
+        Vec::new()
+    }
+
+    fn description(&self) -> String {
+        let names: Vec<&str> = self.engine.rules().map(|rule| rule.name.as_str()).collect();
+        format!("alert({})", names.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_rule(threshold: usize, cooldown: &str) -> AlertRule {
+        let config: AlertConfig = serde_yaml::from_str(&format!(
+            "name: timeouts\npattern: timeout\nlevel: ERROR\nthreshold: {}\nwindow: 1m\ncooldown: {}\ncommand: cat\n",
+            threshold, cooldown
+        ))
+        .unwrap();
+        AlertRule::from_config(&config).unwrap()
+    }
+
+    #[test]
+    fn test_rule_from_config() {
+        let config: AlertConfig =
+            serde_yaml::from_str("name: x\nthreshold: 1\nwindow: 1m\n").unwrap();
+        assert!(AlertRule::from_config(&config).is_err());
+
+        let config: AlertConfig =
+            serde_yaml::from_str("name: x\nthreshold: 1\nwindow: 1m\nwebhook: ftp://host\n")
+                .unwrap();
+        assert!(AlertRule::from_config(&config).is_err());
+
+        let config: AlertConfig =
+            serde_yaml::from_str("name: x\nlevel: LOUD\nthreshold: 1\nwindow: 1m\ncommand: cat\n")
+                .unwrap();
+        assert!(AlertRule::from_config(&config).is_err());
+
+        // The cooldown defaults to the window
+        assert_eq!(create_rule(1, "1m").cooldown, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_threshold_and_window() {
+        let mut engine = AlertEngine::new(vec![create_rule(2, "1m")]);
+        let now = Utc::now();
+
+        // Lines that don't match the level or pattern are ignored
+        assert!(engine
+            .observe(&LogEntry::for_test("web-1", "[WARN] timeout"), now)
+            .is_empty());
+        assert!(engine
+            .observe(&LogEntry::for_test("web-1", "[ERROR] refused"), now)
+            .is_empty());
+
+        assert!(engine
+            .observe(&LogEntry::for_test("web-1", "[ERROR] timeout 1"), now)
+            .is_empty());
+        assert!(engine
+            .observe(&LogEntry::for_test("web-2", "[ERROR] timeout 2"), now)
+            .is_empty());
+
+        // The first hit left the window
+        let later = now + Duration::from_secs(61);
+        assert!(engine
+            .observe(&LogEntry::for_test("web-1", "[ERROR] timeout 3"), later)
+            .is_empty());
+        assert!(engine
+            .observe(&LogEntry::for_test("web-1", "[ERROR] timeout 4"), later)
+            .is_empty());
+
+        let fired = engine.observe(&LogEntry::for_test("web-2", "[ERROR] timeout 5"), later);
+        assert_eq!(fired.len(), 1);
+        let (alert, action) = &fired[0];
+        assert_eq!(alert.rule, "timeouts");
+        assert_eq!(alert.count, 3);
+        assert_eq!(*action, AlertAction::Command("cat".to_string()));
+
+        let messages: Vec<&str> = alert.lines.iter().map(|e| e.message()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "[ERROR] timeout 3",
+                "[ERROR] timeout 4",
+                "[ERROR] timeout 5"
+            ]
+        );
+
+        let payload = alert.to_json();
+        assert_eq!(payload["alert"], "timeouts");
+        assert_eq!(payload["lines"][2]["pod"], "web-2");
+    }
+
+    #[test]
+    fn test_cooldown() {
+        let mut engine = AlertEngine::new(vec![create_rule(0, "5m")]);
+        let now = Utc::now();
+        let entry = LogEntry::for_test("web-1", "[ERROR] timeout");
+
+        assert_eq!(engine.observe(&entry, now).len(), 1);
+        for i in 1..100 {
+            assert!(engine
+                .observe(&entry, now + Duration::from_secs(i))
+                .is_empty());
+        }
+        assert_eq!(
+            engine.observe(&entry, now + Duration::from_secs(300)).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_stage_times_backlog_by_timestamp() {
+        let config: AlertConfig = serde_yaml::from_str(
+            "name: errors\nlevel: ERROR\nthreshold: 2\nwindow: 1m\ncommand: cat\n",
+        )
+        .unwrap();
+        let mut stage = AlertStage::new(AlertEngine::new(vec![
+            AlertRule::from_config(&config).unwrap()
+        ]));
+        let start = Utc::now() - chrono::Duration::hours(6);
+
+        // A backlog of errors an hour apart is read in one go but never fires
+        for i in 0..5 {
+            let mut entry = LogEntry::for_test("web-1", "[ERROR] refused");
+            entry.timestamp = Some(start + chrono::Duration::hours(i));
+            assert_eq!(stage.process(entry).len(), 1);
+        }
+        assert!(stage.actions.is_none());
+
+        // A burst within the window does
+        let burst = start + chrono::Duration::hours(5);
+        for i in 0..3 {
+            let mut entry = LogEntry::for_test("web-1", "[ERROR] refused");
+            entry.timestamp = Some(burst + chrono::Duration::seconds(i));
+            stage.process(entry);
+        }
+        assert!(stage.actions.is_some());
+        stage.finish();
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_stage_waits_for_actions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alerts.json");
+        let config: AlertConfig = serde_yaml::from_str(&format!(
+            "name: errors\nlevel: ERROR\nthreshold: 0\nwindow: 1m\ncooldown: 0s\ncommand: 'sleep 0.1; cat >> {}'\n",
+            path.display()
+        ))
+        .unwrap();
+        let mut stage = AlertStage::new(AlertEngine::new(vec![
+            AlertRule::from_config(&config).unwrap()
+        ]));
+
+        for _ in 0..3 {
+            let output = stage.process(LogEntry::for_test("web-1", "[ERROR] refused"));
+            assert_eq!(output.len(), 1);
+        }
+        stage.finish();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(written.matches("\"alert\":\"errors\"").count(), 3);
+    }
+}
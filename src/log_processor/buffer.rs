@@ -0,0 +1,239 @@
+use anyhow::{Context, Result};
+use std::collections::{vec_deque, BTreeMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Lines, Seek, SeekFrom, Write};
+use std::sync::Arc;
+use tracing::{debug, error};
+
+use crate::kubernetes::log::LogEntry;
+
+/// Number of entries held in memory across all streams before they are spilled to disk
+pub const SPILL_THRESHOLD: usize = 100_000;
+
+/// Identifies a container stream by pod and container
+pub type StreamKey = (Arc<str>, Arc<str>);
+
+/// Buffered entries of one container stream
+#[derive(Default)]
+struct StreamBuffer {
+    /// Entries in memory, after the spilled ones
+    memory: VecDeque<LogEntry>,
+    /// Temporary file with the earlier entries as JSON lines
+    spill: Option<BufWriter<File>>,
+}
+
+/// Buffers container streams until they can be merged
+///
+/// With a capacity, each stream keeps only its most recent entries in a ring
+/// buffer, so memory stays bounded however long the logs are. Without one,
+/// entries are spilled to temporary files once more than the spill threshold
+/// are held, and read back while merging.
+pub struct StreamBuffers {
+    /// Maximum number of entries kept per stream
+    capacity: Option<usize>,
+    /// Number of entries held in memory before spilling
+    spill_threshold: usize,
+    streams: BTreeMap<StreamKey, StreamBuffer>,
+    /// Number of entries held in memory
+    held: usize,
+}
+
+impl StreamBuffers {
+    /// Create buffers keeping at most `capacity` entries per stream, or all of them
+    pub fn new(capacity: Option<usize>) -> Self {
+        Self::with_spill_threshold(capacity, SPILL_THRESHOLD)
+    }
+
+    /// Create buffers spilling to disk beyond the given number of entries
+    pub fn with_spill_threshold(capacity: Option<usize>, spill_threshold: usize) -> Self {
+        Self {
+            capacity,
+            spill_threshold,
+            streams: BTreeMap::new(),
+            held: 0,
+        }
+    }
+
+    /// Add an entry to the buffer of its container
+    pub fn push(&mut self, entry: LogEntry) -> Result<()> {
+        let buffer = self
+            .streams
+            .entry((entry.pod_name.clone(), entry.container_name.clone()))
+            .or_default();
+
+        match self.capacity {
+            Some(0) => {}
+            Some(capacity) => {
+                if buffer.memory.len() == capacity {
+                    buffer.memory.pop_front();
+                } else {
+                    self.held += 1;
+                }
+                buffer.memory.push_back(entry);
+            }
+            None => {
+                buffer.memory.push_back(entry);
+                self.held += 1;
+                if self.held > self.spill_threshold {
+                    self.spill()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Move the entries held in memory to the temporary files of their streams
+    fn spill(&mut self) -> Result<()> {
+        debug!("Spilling {} buffered log entries to disk", self.held);
+        for buffer in self.streams.values_mut() {
+            if buffer.memory.is_empty() {
+                continue;
+            }
+            if buffer.spill.is_none() {
+                let file = tempfile::tempfile().context("Failed to create spill file")?;
+                buffer.spill = Some(BufWriter::new(file));
+            }
+            let writer = buffer.spill.as_mut().expect("spill file was just created");
+            for entry in buffer.memory.drain(..) {
+                serde_json::to_writer(&mut *writer, &entry)?;
+                writer
+                    .write_all(b"\n")
+                    .context("Failed to write spill file")?;
+            }
+        }
+        self.held = 0;
+        Ok(())
+    }
+
+    /// Get the number of entries held in memory
+    pub fn held(&self) -> usize {
+        self.held
+    }
+
+    /// Get the buffered streams in pod and container order
+    pub fn into_streams(self) -> Result<BTreeMap<StreamKey, BufferedStream>> {
+        self.streams
+            .into_iter()
+            .map(|(key, buffer)| {
+                let spilled = match buffer.spill {
+                    Some(writer) => {
+                        let mut file = writer
+                            .into_inner()
+                            .map_err(|e| e.into_error())
+                            .context("Failed to write spill file")?;
+                        file.seek(SeekFrom::Start(0))?;
+                        Some(BufReader::new(file).lines())
+                    }
+                    None => None,
+                };
+                Ok((
+                    key,
+                    BufferedStream {
+                        spilled,
+                        memory: buffer.memory.into_iter(),
+                    },
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Entries of one buffered stream, read back from disk first if they were spilled
+pub struct BufferedStream {
+    spilled: Option<Lines<BufReader<File>>>,
+    memory: vec_deque::IntoIter<LogEntry>,
+}
+
+impl Iterator for BufferedStream {
+    type Item = LogEntry;
+
+    fn next(&mut self) -> Option<LogEntry> {
+        if let Some(lines) = self.spilled.as_mut() {
+            match lines.next() {
+                Some(Ok(line)) => match serde_json::from_str(&line) {
+                    Ok(entry) => return Some(entry),
+                    Err(e) => error!("Failed to read spilled log entry: {}", e),
+                },
+                Some(Err(e)) => error!("Failed to read spill file: {}", e),
+                None => {}
+            }
+            // The spill file is done, or can't be read any further
+            self.spilled = None;
+        }
+        self.memory.next()
+    }
+}
+
+/// Keep the last `count` entries of a stream
+pub fn last_entries(entries: impl Iterator<Item = LogEntry>, count: usize) -> Vec<LogEntry> {
+    let mut last = VecDeque::with_capacity(count);
+    for entry in entries {
+        if count == 0 {
+            break;
+        }
+        if last.len() == count {
+            last.pop_front();
+        }
+        last.push_back(entry);
+    }
+    last.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_entry(pod_name: &str, message: &str) -> LogEntry {
+        LogEntry::parse(
+            "default",
+            pod_name.to_string(),
+            "main".to_string(),
+            format!("2024-05-01T10:00:00Z {}", message),
+        )
+    }
+
+    fn messages(entries: impl Iterator<Item = LogEntry>) -> Vec<String> {
+        entries.map(|e| e.message().to_string()).collect()
+    }
+
+    #[test]
+    fn test_ring_buffers() {
+        let mut buffers = StreamBuffers::new(Some(2));
+        for message in ["1", "2", "3", "4"] {
+            buffers.push(create_test_entry("a", message)).unwrap();
+        }
+        buffers.push(create_test_entry("b", "5")).unwrap();
+        assert_eq!(buffers.held(), 3);
+
+        let mut streams = buffers.into_streams().unwrap();
+        let key = ("a".into(), "main".into());
+        assert_eq!(messages(streams.remove(&key).unwrap()), ["3", "4"]);
+    }
+
+    #[test]
+    fn test_spill_to_disk() {
+        let mut buffers = StreamBuffers::with_spill_threshold(None, 3);
+        for message in ["1", "2", "3", "4", "5"] {
+            buffers.push(create_test_entry("a", message)).unwrap();
+        }
+        buffers.push(create_test_entry("b", "6")).unwrap();
+        // Four entries were spilled when the threshold was exceeded
+        assert_eq!(buffers.held(), 2);
+
+        let streams: Vec<Vec<String>> = buffers
+            .into_streams()
+            .unwrap()
+            .into_values()
+            .map(messages)
+            .collect();
+        assert_eq!(streams, [vec!["1", "2", "3", "4", "5"], vec!["6"]]);
+    }
+
+    #[test]
+    fn test_last_entries() {
+        let entries = (1..=5).map(|i| create_test_entry("a", &i.to_string()));
+        assert_eq!(messages(last_entries(entries, 2).into_iter()), ["4", "5"]);
+        let entries = (1..=5).map(|i| create_test_entry("a", &i.to_string()));
+        assert!(last_entries(entries, 0).is_empty());
+    }
+}
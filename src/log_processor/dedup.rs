@@ -0,0 +1,307 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::kubernetes::log::{LogEntry, Source};
+use crate::log_processor::stage::Stage;
+
+/// How long suppressed repeats are held before a summary is emitted in follow mode
+const REPEAT_REPORT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Last message seen from a pod and how often it was repeated since
+struct Repeat {
+    /// Last distinct entry from the pod
+    entry: LogEntry,
+    /// Number of suppressed repeats not yet reported
+    count: usize,
+    /// When the first unreported repeat was suppressed
+    since: Instant,
+}
+
+/// Identical messages from several pods waiting for the merge window to close
+struct MergeGroup {
+    /// First entry of the group
+    entry: LogEntry,
+    /// Pods that emitted the message, in arrival order
+    pods: Vec<Arc<str>>,
+    /// When the first entry of the group arrived
+    first_seen: Instant,
+}
+
+/// Deduplication stage for repeated log lines
+///
+/// Consecutive identical messages from the same pod are collapsed into a
+/// single "last message repeated N times" line. When a merge window is set,
+/// identical messages arriving from multiple pods within the window are
+/// merged into one line listing all the pods, which delays output by the
+/// window.
+pub struct DedupStage {
+    /// Whether to collapse consecutive repeats per pod
+    collapse_repeats: bool,
+    /// Window for merging identical messages across pods
+    merge_window: Option<Duration>,
+    /// Repeat state per pod
+    repeats: HashMap<Arc<str>, Repeat>,
+    /// Groups waiting for the merge window, in arrival order
+    groups: VecDeque<MergeGroup>,
+}
+
+impl DedupStage {
+    /// Create a new deduplication stage
+    pub fn new(collapse_repeats: bool, merge_window: Option<Duration>) -> Self {
+        Self {
+            collapse_repeats,
+            merge_window,
+            repeats: HashMap::new(),
+            groups: VecDeque::new(),
+        }
+    }
+
+    /// Process a log entry that arrived at the given time
+    pub fn process_at(&mut self, entry: LogEntry, now: Instant) -> Vec<LogEntry> {
+        let collapsed = if self.collapse_repeats {
+            self.collapse(entry, now)
+        } else {
+            vec![entry]
+        };
+
+        let mut output = self.drain_groups(Some(now));
+        for entry in collapsed {
+            output.extend(self.merge(entry, now));
+        }
+        output
+    }
+
+    /// Emit repeat summaries and merge groups that are due at the given time
+    pub fn tick_at(&mut self, now: Instant) -> Vec<LogEntry> {
+        let mut summaries = Vec::new();
+        for repeat in self.repeats.values_mut() {
+            if repeat.count > 0 && now.duration_since(repeat.since) >= REPEAT_REPORT_INTERVAL {
+                summaries.push(Self::summary(repeat));
+                repeat.count = 0;
+            }
+        }
+
+        let mut output = self.drain_groups(Some(now));
+        for summary in summaries {
+            output.extend(self.merge(summary, now));
+        }
+        output
+    }
+
+    /// Collapse consecutive repeats from the same pod
+    fn collapse(&mut self, entry: LogEntry, now: Instant) -> Vec<LogEntry> {
+        match self.repeats.get_mut(&entry.pod_name) {
+            Some(repeat) if repeat.entry.message() == entry.message() => {
+                if repeat.count == 0 {
+                    repeat.since = now;
+                }
+                repeat.count += 1;
+                Vec::new()
+            }
+            Some(repeat) => {
+                let mut output = Vec::with_capacity(2);
+                if repeat.count > 0 {
+                    output.push(Self::summary(repeat));
+                }
+                repeat.entry = entry.clone();
+                repeat.count = 0;
+                output.push(entry);
+                output
+            }
+            None => {
+                self.repeats.insert(
+                    entry.pod_name.clone(),
+                    Repeat {
+                        entry: entry.clone(),
+                        count: 0,
+                        since: now,
+                    },
+                );
+                vec![entry]
+            }
+        }
+    }
+
+    /// Add an entry to the merge groups, or pass it through without a window
+    fn merge(&mut self, entry: LogEntry, now: Instant) -> Vec<LogEntry> {
+        if self.merge_window.is_none() {
+            return vec![entry];
+        }
+
+        let group = self.groups.iter_mut().find(|group| {
+            group.entry.message() == entry.message() && !group.pods.contains(&entry.pod_name)
+        });
+
+        match group {
+            Some(group) => group.pods.push(entry.pod_name),
+            None => self.groups.push_back(MergeGroup {
+                pods: vec![entry.pod_name.clone()],
+                entry,
+                first_seen: now,
+            }),
+        }
+
+        Vec::new()
+    }
+
+    /// Emit merge groups whose window closed before `now`, or all groups if `now` is `None`
+    fn drain_groups(&mut self, now: Option<Instant>) -> Vec<LogEntry> {
+        let mut output = Vec::new();
+        while let Some(group) = self.groups.front() {
+            let due = match (now, self.merge_window) {
+                (Some(now), Some(window)) => now.duration_since(group.first_seen) >= window,
+                _ => true,
+            };
+            if !due {
+                break;
+            }
+
+            if let Some(group) = self.groups.pop_front() {
+                let mut entry = group.entry;
+                if group.pods.len() > 1 {
+                    // The pod name lists all pods for display
+                    entry.pod_name = group.pods.join(",").into();
+                    entry.source = Source::Merged(group.pods);
+                }
+                output.push(entry);
+            }
+        }
+        output
+    }
+
+    /// Build the summary line for suppressed repeats
+    fn summary(repeat: &Repeat) -> LogEntry {
+        let mut entry = repeat.entry.clone();
+        let times = if repeat.count == 1 { "time" } else { "times" };
+        entry.raw_line = format!("last message repeated {} {}", repeat.count, times);
+        entry.set_message(entry.raw_line.clone());
+        entry
+    }
+}
+
+impl Stage for DedupStage {
+    fn process(&mut self, entry: LogEntry) -> Vec<LogEntry> {
+        self.process_at(entry, Instant::now())
+    }
+
+    fn tick(&mut self) -> Vec<LogEntry> {
+        self.tick_at(Instant::now())
+    }
+
+    fn finish(&mut self) -> Vec<LogEntry> {
+        let now = Instant::now();
+        let mut summaries = Vec::new();
+        for repeat in self.repeats.values_mut() {
+            if repeat.count > 0 {
+                summaries.push(Self::summary(repeat));
+                repeat.count = 0;
+            }
+        }
+        summaries.sort_by(|a, b| a.pod_name.cmp(&b.pod_name));
+
+        let mut output = self.drain_groups(None);
+        for summary in summaries {
+            output.extend(self.merge(summary, now));
+        }
+        output.extend(self.drain_groups(None));
+        output
+    }
+
+    fn description(&self) -> String {
+        match (self.collapse_repeats, self.merge_window) {
+            (true, Some(window)) => format!("dedup(repeats, merge {:?})", window),
+            (false, Some(window)) => format!("dedup(merge {:?})", window),
+            _ => "dedup(repeats)".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(entries: Vec<LogEntry>) -> Vec<String> {
+        entries
+            .into_iter()
+            .map(|e| format!("[{}] {}", e.pod_name, e.message()))
+            .collect()
+    }
+
+    #[test]
+    fn test_collapse_repeats() {
+        let mut stage = DedupStage::new(true, None);
+        let now = Instant::now();
+
+        let mut output = Vec::new();
+        for message in ["start", "retry", "retry", "retry", "done"] {
+            output.extend(stage.process_at(LogEntry::for_test("pod-a", message), now));
+        }
+
+        assert_eq!(
+            lines(output),
+            vec![
+                "[pod-a] start",
+                "[pod-a] retry",
+                "[pod-a] last message repeated 2 times",
+                "[pod-a] done",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repeats_are_tracked_per_pod() {
+        let mut stage = DedupStage::new(true, None);
+        let now = Instant::now();
+
+        let mut output = Vec::new();
+        output.extend(stage.process_at(LogEntry::for_test("pod-a", "retry"), now));
+        output.extend(stage.process_at(LogEntry::for_test("pod-b", "retry"), now));
+        output.extend(stage.process_at(LogEntry::for_test("pod-a", "retry"), now));
+        assert_eq!(lines(output), vec!["[pod-a] retry", "[pod-b] retry"]);
+
+        // Pending repeats are reported after the interval and when the stream ends
+        assert!(stage.tick_at(now + Duration::from_millis(100)).is_empty());
+        assert_eq!(
+            lines(stage.tick_at(now + REPEAT_REPORT_INTERVAL)),
+            vec!["[pod-a] last message repeated 1 time"]
+        );
+
+        stage.process_at(LogEntry::for_test("pod-b", "retry"), now);
+        assert_eq!(
+            lines(stage.finish()),
+            vec!["[pod-b] last message repeated 1 time"]
+        );
+    }
+
+    #[test]
+    fn test_merge_across_pods() {
+        let mut stage = DedupStage::new(false, Some(Duration::from_secs(1)));
+        let now = Instant::now();
+
+        assert!(stage
+            .process_at(LogEntry::for_test("pod-a", "config reloaded"), now)
+            .is_empty());
+        assert!(stage
+            .process_at(LogEntry::for_test("pod-b", "config reloaded"), now)
+            .is_empty());
+        assert!(stage
+            .process_at(LogEntry::for_test("pod-b", "serving"), now)
+            .is_empty());
+
+        assert!(stage.tick_at(now + Duration::from_millis(500)).is_empty());
+
+        // A late replica outside the window starts a new group
+        let output = stage.process_at(
+            LogEntry::for_test("pod-c", "config reloaded"),
+            now + Duration::from_secs(1),
+        );
+        assert_eq!(output[0].pods(), [Arc::from("pod-a"), Arc::from("pod-b")]);
+        assert_eq!(output[1].source, Source::Container);
+        assert_eq!(
+            lines(output),
+            vec!["[pod-a,pod-b] config reloaded", "[pod-b] serving"]
+        );
+        assert_eq!(lines(stage.finish()), vec!["[pod-c] config reloaded"]);
+    }
+}
@@ -0,0 +1,236 @@
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::kubernetes::log::LogEntry;
+
+/// Keys of the fixed record columns, in output order
+const RECORD_KEYS: [&str; 6] = ["namespace", "pod", "container", "ts", "level", "msg"];
+
+/// Output format for log entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Colored text with a pod prefix
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+    /// One logfmt line per entry
+    Logfmt,
+    /// CSV with a header row
+    Csv,
+}
+
+impl OutputFormat {
+    /// Check whether the format is meant for other programs rather than people
+    pub fn is_machine_readable(&self) -> bool {
+        *self != OutputFormat::Text
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" | "jsonl" => Ok(OutputFormat::Json),
+            "logfmt" => Ok(OutputFormat::Logfmt),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(anyhow!(
+                "Invalid output format: {} (expected text, json, logfmt or csv)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Text => write!(f, "text"),
+            OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Logfmt => write!(f, "logfmt"),
+            OutputFormat::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+/// Get the values of the fixed record columns
+fn record_values(entry: &LogEntry) -> [String; 6] {
+    [
+        entry.namespace.to_string(),
+        entry.pod_name.to_string(),
+        entry.container_name.to_string(),
+        entry
+            .timestamp
+            .map(|ts| ts.to_rfc3339())
+            .unwrap_or_default(),
+        entry.level().unwrap_or_default().to_string(),
+        entry.message().to_string(),
+    ]
+}
+
+/// Encode an entry as a JSON object
+pub fn encode_json(entry: &LogEntry) -> String {
+    json!({
+        "namespace": entry.namespace,
+        "pod": entry.pod_name,
+        "container": entry.container_name,
+        "timestamp": entry.timestamp.map(|ts| ts.to_rfc3339()),
+        "level": entry.level(),
+        "message": entry.message(),
+        "fields": entry.json_fields(),
+    })
+    .to_string()
+}
+
+/// Quote a logfmt value if needed
+fn logfmt_value(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c == '"' || c == '=' || c.is_control());
+    if needs_quotes {
+        // JSON string escaping is what logfmt parsers expect
+        Value::String(value.to_string()).to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Encode an entry as a logfmt line
+///
+/// Parsed fields follow the fixed keys; fields whose key collides with a
+/// fixed key are written with a `field.` prefix.
+pub fn encode_logfmt(entry: &LogEntry) -> String {
+    let mut pairs: Vec<String> = RECORD_KEYS
+        .iter()
+        .zip(record_values(entry))
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| format!("{}={}", key, logfmt_value(&value)))
+        .collect();
+
+    for (key, value) in entry.json_fields() {
+        let value = match value {
+            Value::String(value) => value,
+            other => other.to_string(),
+        };
+        let key = if RECORD_KEYS.contains(&key.as_str()) {
+            format!("field.{}", key)
+        } else {
+            key
+        };
+        pairs.push(format!("{}={}", key, logfmt_value(&value)));
+    }
+
+    pairs.join(" ")
+}
+
+/// Quote a CSV value if needed
+fn csv_value(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Get the CSV header row
+pub fn csv_header() -> String {
+    "namespace,pod,container,timestamp,level,message,fields".to_string()
+}
+
+/// Encode an entry as a CSV row, with the parsed fields as a JSON object
+pub fn encode_csv(entry: &LogEntry) -> String {
+    let fields = entry.json_fields();
+    let fields = if fields.is_empty() {
+        String::new()
+    } else {
+        Value::Object(fields).to_string()
+    };
+
+    record_values(entry)
+        .iter()
+        .chain(std::iter::once(&fields))
+        .map(|value| csv_value(value))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Encode an entry in a machine-readable format, or `None` for text output
+pub fn encode(entry: &LogEntry, format: OutputFormat) -> Option<String> {
+    match format {
+        OutputFormat::Text => None,
+        OutputFormat::Json => Some(encode_json(entry)),
+        OutputFormat::Logfmt => Some(encode_logfmt(entry)),
+        OutputFormat::Csv => Some(encode_csv(entry)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_entry(message: &str) -> LogEntry {
+        let mut entry = LogEntry::parse(
+            "default",
+            "web-1".to_string(),
+            "main".to_string(),
+            format!("2024-05-01T10:00:00Z {}", message),
+        );
+        entry.namespace = "shop".into();
+        entry
+    }
+
+    #[test]
+    fn test_parse_output_format() {
+        assert_eq!("json".parse::<OutputFormat>().unwrap(), OutputFormat::Json);
+        assert_eq!("CSV".parse::<OutputFormat>().unwrap(), OutputFormat::Csv);
+        assert!("yaml".parse::<OutputFormat>().is_err());
+        assert!(!OutputFormat::Text.is_machine_readable());
+    }
+
+    #[test]
+    fn test_encode_json() {
+        let entry = create_test_entry(r#"{"level":"error","msg":"db down","retry":3}"#);
+        let record: Value = serde_json::from_str(&encode_json(&entry)).unwrap();
+
+        assert_eq!(record["namespace"], "shop");
+        assert_eq!(record["pod"], "web-1");
+        assert_eq!(record["container"], "main");
+        assert_eq!(record["timestamp"], "2024-05-01T10:00:00+00:00");
+        assert_eq!(record["level"], "ERROR");
+        assert_eq!(record["fields"]["retry"], 3);
+
+        let entry = LogEntry::parse(
+            "default",
+            "web-1".to_string(),
+            "main".to_string(),
+            "plain".to_string(),
+        );
+        let record: Value = serde_json::from_str(&encode_json(&entry)).unwrap();
+        assert_eq!(record["timestamp"], Value::Null);
+        assert_eq!(record["level"], Value::Null);
+        assert_eq!(record["fields"], json!({}));
+    }
+
+    #[test]
+    fn test_encode_logfmt() {
+        let entry = create_test_entry(r#"level=warn msg="slow query" took=35ms"#);
+        assert_eq!(
+            encode_logfmt(&entry),
+            r#"namespace=shop pod=web-1 container=main ts=2024-05-01T10:00:00+00:00 level=WARN msg="level=warn msg=\"slow query\" took=35ms" field.level=warn field.msg="slow query" took=35ms"#
+        );
+    }
+
+    #[test]
+    fn test_encode_csv() {
+        let entry = create_test_entry(r#"said "hi", then left"#);
+        assert_eq!(
+            encode_csv(&entry),
+            r#"shop,web-1,main,2024-05-01T10:00:00+00:00,,"said ""hi"", then left","#
+        );
+    }
+}
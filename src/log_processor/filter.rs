@@ -1,7 +1,12 @@
 use anyhow::Result;
 use regex::Regex;
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
 
-use crate::kubernetes::log::LogEntry;
+use crate::kubernetes::log::{canonical_level, LogEntry};
+use crate::log_processor::matcher::PatternMatcher;
 
 /// Trait for log filters
 pub trait Filter: Send + Sync {
@@ -12,11 +17,119 @@ pub trait Filter: Send + Sync {
     fn description(&self) -> String;
 }
 
+/// Part of a log entry that a filter matches against
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MatchScope {
+    /// Log message without the Kubernetes timestamp
+    #[default]
+    Message,
+    /// Raw log line including the timestamp
+    Raw,
+    /// Level detected from the level field or the first level keyword
+    Level,
+    /// Pod name
+    Pod,
+    /// Container name
+    Container,
+    /// A JSON or logfmt field of the message
+    Field(String),
+}
+
+impl MatchScope {
+    /// Select the text of a log entry covered by this scope
+    ///
+    /// Returns `None` when the entry has no such field.
+    pub fn select<'a>(&self, entry: &'a LogEntry) -> Option<Cow<'a, str>> {
+        match self {
+            MatchScope::Message => Some(Cow::Borrowed(entry.message())),
+            MatchScope::Raw => Some(Cow::Borrowed(entry.raw_line.as_str())),
+            MatchScope::Level => entry.level().map(Cow::Borrowed),
+            MatchScope::Pod => Some(Cow::Borrowed(&entry.pod_name)),
+            MatchScope::Container => Some(Cow::Borrowed(&entry.container_name)),
+            MatchScope::Field(name) => entry.field(name).map(Cow::Borrowed),
+        }
+    }
+
+    /// Find the spans of the message to highlight for the given matcher
+    ///
+    /// Matches are searched in the scoped text and mapped back onto the
+    /// message, so highlighting shows exactly what the filter matched. Scopes
+    /// that are not part of the message produce no highlights.
+    pub fn highlight_spans(
+        &self,
+        entry: &LogEntry,
+        matcher: &PatternMatcher,
+    ) -> Vec<(usize, usize)> {
+        match self {
+            MatchScope::Message => matcher.find_matches(entry.message()),
+            MatchScope::Raw => {
+                // The message is always the tail of the raw line
+                let offset = entry.raw_line.len().saturating_sub(entry.message().len());
+                matcher
+                    .find_matches(&entry.raw_line)
+                    .into_iter()
+                    .filter(|&(_, end)| end > offset)
+                    .map(|(start, end)| (start.max(offset) - offset, end - offset))
+                    .collect()
+            }
+            MatchScope::Level | MatchScope::Pod | MatchScope::Container => Vec::new(),
+            MatchScope::Field(name) => {
+                // Highlight inside the value where the field is written in the message
+                let Some((offset, end)) = entry.field_span(name) else {
+                    return Vec::new();
+                };
+                matcher
+                    .find_matches(&entry.message()[offset..end])
+                    .into_iter()
+                    .map(|(start, end)| (start + offset, end + offset))
+                    .collect()
+            }
+        }
+    }
+}
+
+impl FromStr for MatchScope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "message" | "msg" => Ok(MatchScope::Message),
+            "raw" => Ok(MatchScope::Raw),
+            "level" => Ok(MatchScope::Level),
+            "pod" => Ok(MatchScope::Pod),
+            "container" => Ok(MatchScope::Container),
+            _ => match s.split_once(':') {
+                Some((kind, name)) if kind.eq_ignore_ascii_case("field") && !name.is_empty() => {
+                    Ok(MatchScope::Field(name.to_string()))
+                }
+                _ => Err(anyhow::anyhow!(
+                    "Invalid match scope: {} (expected message, raw, level, pod, container or field:<name>)",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for MatchScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchScope::Message => write!(f, "message"),
+            MatchScope::Raw => write!(f, "raw"),
+            MatchScope::Level => write!(f, "level"),
+            MatchScope::Pod => write!(f, "pod"),
+            MatchScope::Container => write!(f, "container"),
+            MatchScope::Field(name) => write!(f, "field:{}", name),
+        }
+    }
+}
+
 /// Filter logs using a regular expression
 #[derive(Clone)]
 pub struct GrepFilter {
     pattern: String,
     regex: Regex,
+    scope: MatchScope,
 }
 
 impl GrepFilter {
@@ -35,8 +148,15 @@ impl GrepFilter {
         Ok(Self {
             pattern: pattern.to_string(),
             regex,
+            scope: MatchScope::default(),
         })
     }
+
+    /// Set the part of the log entry to match against
+    pub fn with_scope(mut self, scope: MatchScope) -> Self {
+        self.scope = scope;
+        self
+    }
     
     /// Get the pattern string
     pub fn pattern(&self) -> &str {
@@ -53,7 +173,9 @@ impl GrepFilter {
 
 impl Filter for GrepFilter {
     fn apply(&self, entry: &LogEntry) -> bool {
-        self.regex.is_match(&entry.raw_line)
+        self.scope
+            .select(entry)
+            .is_some_and(|text| self.regex.is_match(&text))
     }
 
     fn description(&self) -> String {
@@ -61,10 +183,83 @@ impl Filter for GrepFilter {
     }
 }
 
+/// Filter logs using a shared multi-pattern matcher
+///
+/// All patterns are evaluated in a single pass, which keeps large watchlists
+/// cheap compared to one `GrepFilter` per pattern.
+#[derive(Clone)]
+pub struct PatternFilter {
+    matcher: Arc<PatternMatcher>,
+    match_all: bool,
+    scope: MatchScope,
+}
+
+impl PatternFilter {
+    /// Create a filter that matches when any pattern matches
+    pub fn any(matcher: Arc<PatternMatcher>) -> Self {
+        Self {
+            matcher,
+            match_all: false,
+            scope: MatchScope::default(),
+        }
+    }
+
+    /// Create a filter that matches only when all patterns match
+    pub fn all(matcher: Arc<PatternMatcher>) -> Self {
+        Self {
+            matcher,
+            match_all: true,
+            scope: MatchScope::default(),
+        }
+    }
+
+    /// Set the part of the log entry to match against
+    pub fn with_scope(mut self, scope: MatchScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Get the underlying matcher
+    pub fn matcher(&self) -> &Arc<PatternMatcher> {
+        &self.matcher
+    }
+}
+
+impl Filter for PatternFilter {
+    fn apply(&self, entry: &LogEntry) -> bool {
+        let Some(text) = self.scope.select(entry) else {
+            return false;
+        };
+
+        if self.match_all {
+            self.matcher.is_match_all(&text)
+        } else {
+            self.matcher.is_match(&text)
+        }
+    }
+
+    fn description(&self) -> String {
+        let patterns: Vec<String> = self
+            .matcher
+            .patterns()
+            .iter()
+            .map(|p| format!("\"{}\"", p))
+            .collect();
+        let joiner = if self.match_all { " AND " } else { " OR " };
+        format!("grep({})", patterns.join(joiner))
+    }
+}
+
 /// Filter logs by log level
+///
+/// By default the level detected for the entry is compared, so a level word
+/// inside the message text doesn't count as the level of the line.
 pub struct LevelFilter {
     level_str: String,
+    /// Canonical name of the level, compared with the detected level
+    level: Option<&'static str>,
     regex: Regex,
+    scope: MatchScope,
 }
 
 impl LevelFilter {
@@ -95,10 +290,18 @@ impl LevelFilter {
         let regex = Regex::new(&pattern)?;
         
         Ok(Self { 
+            level: canonical_level(&upper_level),
             level_str: upper_level,
             regex,
+            scope: MatchScope::Level,
         })
     }
+
+    /// Set the part of the log entry to match against
+    pub fn with_scope(mut self, scope: MatchScope) -> Self {
+        self.scope = scope;
+        self
+    }
     
     /// Special handling for certain log lines to exclude false positives
     fn should_exclude(&self, text: &str) -> bool {
@@ -112,13 +315,21 @@ impl LevelFilter {
 
 impl Filter for LevelFilter {
     fn apply(&self, entry: &LogEntry) -> bool {
+        if self.scope == MatchScope::Level {
+            return entry.level() == self.level && !self.should_exclude(entry.message());
+        }
+
+        let Some(text) = self.scope.select(entry) else {
+            return false;
+        };
+
         // First check if this log should be excluded
-        if self.should_exclude(&entry.raw_line) {
+        if self.should_exclude(&text) {
             return false;
         }
         
         // Use regex for more precise matching
-        self.regex.is_match(&entry.raw_line)
+        self.regex.is_match(&text)
     }
 
     fn description(&self) -> String {
@@ -177,11 +388,16 @@ mod tests {
     use super::*;
 
     fn create_test_entry(message: &str) -> LogEntry {
-        LogEntry {
-            pod_name: "test-pod".to_string(),
-            raw_line: message.to_string(),
-            message: message.to_string(),
-        }
+        LogEntry::for_test("test-pod", message)
+    }
+
+    fn create_raw_entry(message: &str) -> LogEntry {
+        LogEntry::parse(
+            "default",
+            "test-pod".to_string(),
+            "main".to_string(),
+            format!("2024-05-01T10:00:00Z {}", message),
+        )
     }
 
     #[test]
@@ -243,6 +459,89 @@ mod tests {
         assert!(!or_filter.apply(&create_test_entry("This is a normal message")));
     }
 
+    #[test]
+    fn test_pattern_filter() {
+        let matcher = Arc::new(PatternMatcher::new(["important", "ERROR"]).unwrap());
+
+        let any_filter = PatternFilter::any(matcher.clone());
+        assert!(any_filter.apply(&create_test_entry("This is an important message")));
+        assert!(!any_filter.apply(&create_test_entry("This is a normal message")));
+
+        let all_filter = PatternFilter::all(matcher);
+        assert!(all_filter.apply(&create_test_entry("This is an important ERROR message")));
+        assert!(!all_filter.apply(&create_test_entry("This is an ERROR message")));
+        assert_eq!(
+            all_filter.description(),
+            "grep(\"important\" AND \"ERROR\")"
+        );
+    }
+
+    #[test]
+    fn test_match_scope() {
+        let entry = create_raw_entry(r#"{"level":"error","msg":"request timeout"}"#);
+
+        // The message without its timestamp is matched by default
+        let filter = GrepFilter::new("2024").unwrap();
+        assert!(!filter.apply(&entry));
+        assert!(filter.with_scope(MatchScope::Raw).apply(&entry));
+
+        let filter = GrepFilter::new("^test-")
+            .unwrap()
+            .with_scope(MatchScope::Pod);
+        assert!(filter.apply(&entry));
+
+        let filter = LevelFilter::new("ERROR")
+            .unwrap()
+            .with_scope("field:level".parse().unwrap());
+        assert!(filter.apply(&entry));
+        assert!(!filter.apply(&create_raw_entry("level=info msg=\"error budget ok\"")));
+
+        // Levels are compared with the detected level, not words in the message
+        let filter = LevelFilter::new("ERROR").unwrap();
+        assert!(filter.apply(&entry));
+        assert!(!filter.apply(&create_raw_entry(
+            r#"{"level":"info","msg":"set level=error"}"#
+        )));
+        assert!(LevelFilter::new("ERR").unwrap().apply(&entry));
+        assert!(!LevelFilter::new("ERROR")
+            .unwrap()
+            .with_scope(MatchScope::Message)
+            .apply(&create_raw_entry("all good")));
+
+        assert!("field:".parse::<MatchScope>().is_err());
+        assert_eq!(
+            MatchScope::Field("msg".to_string()).to_string(),
+            "field:msg"
+        );
+    }
+
+    #[test]
+    fn test_highlight_spans_follow_scope() {
+        let matcher = PatternMatcher::new(["2024", "timeout"]).unwrap();
+        let entry = create_raw_entry("level=warn msg=timeout took=2024ms");
+
+        assert_eq!(
+            MatchScope::Message.highlight_spans(&entry, &matcher),
+            vec![(15, 22), (28, 32)]
+        );
+        assert_eq!(
+            MatchScope::Raw.highlight_spans(&entry, &matcher),
+            vec![(15, 22), (28, 32)]
+        );
+        assert_eq!(
+            MatchScope::Field("msg".to_string()).highlight_spans(&entry, &matcher),
+            vec![(15, 22)]
+        );
+        assert!(MatchScope::Pod.highlight_spans(&entry, &matcher).is_empty());
+
+        // The value of the field is highlighted, not the same text elsewhere
+        let entry = create_raw_entry(r#"{"note":"timeout","msg":"a \"timeout\""}"#);
+        assert_eq!(
+            MatchScope::Field("msg".to_string()).highlight_spans(&entry, &matcher),
+            vec![(29, 36)]
+        );
+    }
+
     #[test]
     fn test_numeric_grep_filter() {
         let filter = GrepFilter::new("123").unwrap();
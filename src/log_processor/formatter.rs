@@ -1,11 +1,23 @@
 use anyhow::Result;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::io::Write;
-use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+use std::sync::Arc;
+use termcolor::{Color, ColorChoice, ColorSpec, WriteColor};
+use unicode_width::UnicodeWidthStr;
 
-use crate::kubernetes::log::LogEntry;
-use crate::log_processor::filter::GrepFilter;
+use crate::kubernetes::log::{LogEntry, Source};
+use crate::kubernetes::ShortNames;
+use crate::log_processor::encoder::{csv_header, encode, OutputFormat};
+use crate::log_processor::filter::{GrepFilter, MatchScope};
+use crate::log_processor::matcher::PatternMatcher;
+use crate::log_processor::pretty::{render_json, JsonStyle, JsonToken, StyledText};
+use crate::log_processor::template::{Key, Rendered, Template};
+use crate::log_processor::timestamp::{TimestampFormatter, TimestampMode};
+use crate::log_processor::wrap::{LineWriter, Overflow};
+use crate::output::{OutputSink, Pager, RotatingFile, SplitWriter};
 use crate::utils::color::ColorGenerator;
+use crate::utils::theme::Theme;
 
 /// Prefix format for log entries
 pub struct PrefixFormat {
@@ -21,18 +33,20 @@ impl PrefixFormat {
         }
     }
 
-    /// Format a log entry prefix
-    pub fn format(&self, entry: &LogEntry) -> String {
+    /// Format a log entry prefix, with the short name from the known pods
+    pub fn format(&self, entry: &LogEntry, short_names: &ShortNames) -> String {
+        self.format_pod(&entry.pod_name, short_names.get(&entry.pod_name))
+    }
+
+    /// Format the prefix of a pod
+    pub fn format_pod(&self, pod_name: &str, short_name: &str) -> String {
         let mut result = self.format.clone();
 
         // Replace %n with pod name
-        result = result.replace("%n", &entry.pod_name);
+        result = result.replace("%n", pod_name);
 
         // Replace %s with short pod name
-        result = result.replace(
-            "%s",
-            &entry.pod_name[..std::cmp::min(8, entry.pod_name.len())],
-        );
+        result = result.replace("%s", short_name);
 
         result
     }
@@ -47,11 +61,45 @@ pub struct LogFormatter {
     /// Color generator
     color_generator: ColorGenerator,
     /// Pod colors
-    pod_colors: HashMap<String, Color>,
+    pod_colors: HashMap<Arc<str>, Color>,
+    /// Whether colored output is written
+    color_choice: ColorChoice,
+    /// Colors for levels and highlighted matches
+    theme: Theme,
     /// Whether to highlight grep matches
     highlight: bool,
-    /// Grep filters for highlighting
-    grep_filters: Vec<GrepFilter>,
+    /// Pattern matcher for highlighting
+    matcher: Option<Arc<PatternMatcher>>,
+    /// Part of the entry the matcher was applied to when filtering
+    match_scope: MatchScope,
+    /// Output format
+    output_format: OutputFormat,
+    /// Whether the CSV header row was written
+    header_written: bool,
+    /// How JSON messages are rendered
+    json_style: JsonStyle,
+    /// Renders timestamps before the prefix, when enabled
+    timestamps: Option<TimestampFormatter>,
+    /// Output template replacing the prefix and message layout
+    template: Option<Template>,
+    /// Node of each pod, for the node placeholder
+    pod_nodes: HashMap<String, String>,
+    /// File receiving plain copies of the output
+    output_file: Option<RotatingFile>,
+    /// Directory receiving one file per container instead of the terminal output
+    output_dir: Option<SplitWriter>,
+    /// Pager receiving the terminal output instead of stdout
+    pager: Option<Pager>,
+    /// Buffered stdout, opened with the first line written to the terminal
+    sink: Option<OutputSink>,
+    /// Unique short names of the pods seen so far
+    short_names: ShortNames,
+    /// Width prefixes are padded to so messages line up, grows with wider pods
+    prefix_width: usize,
+    /// Terminal width lines are fitted to, if any
+    line_width: Option<usize>,
+    /// How lines wider than the terminal are fitted
+    overflow: Overflow,
 }
 
 impl LogFormatter {
@@ -62,11 +110,40 @@ impl LogFormatter {
             no_prefix,
             color_generator: ColorGenerator::new(),
             pod_colors: HashMap::new(),
+            color_choice: ColorChoice::Always,
+            theme: Theme::default(),
             highlight: true,
-            grep_filters: Vec::new(),
+            matcher: None,
+            match_scope: MatchScope::default(),
+            output_format: OutputFormat::default(),
+            header_written: false,
+            json_style: JsonStyle::default(),
+            timestamps: None,
+            template: None,
+            pod_nodes: HashMap::new(),
+            output_file: None,
+            output_dir: None,
+            pager: None,
+            sink: None,
+            short_names: ShortNames::default(),
+            prefix_width: 0,
+            line_width: None,
+            overflow: Overflow::Wrap,
         }
     }
     
+    /// Set whether colored output is written
+    pub fn set_color_choice(&mut self, choice: ColorChoice) {
+        self.color_choice = choice;
+    }
+
+    /// Set the color theme, pods are colored from its palette
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.color_generator = theme.color_generator();
+        self.pod_colors.clear();
+        self.theme = theme;
+    }
+
     /// Set whether to highlight grep matches
     pub fn set_highlight(&mut self, highlight: bool) {
         self.highlight = highlight;
@@ -74,116 +151,501 @@ impl LogFormatter {
     
     /// Add a grep filter for highlighting
     pub fn add_grep_filter(&mut self, filter: GrepFilter) {
-        self.grep_filters.push(filter);
+        let mut patterns = self
+            .matcher
+            .as_ref()
+            .map(|m| m.patterns().to_vec())
+            .unwrap_or_default();
+        patterns.push(filter.pattern().to_string());
+
+        // Patterns already compiled once by the grep filter, so this cannot fail
+        if let Ok(matcher) = PatternMatcher::new(patterns) {
+            self.matcher = Some(Arc::new(matcher));
+        }
+    }
+
+    /// Set the pattern matcher used for highlighting
+    ///
+    /// The same matcher can be shared with a `PatternFilter` so patterns are
+    /// compiled only once for both filtering and highlighting.
+    pub fn set_matcher(&mut self, matcher: Arc<PatternMatcher>) {
+        self.matcher = Some(matcher);
+    }
+
+    /// Set the scope used to locate highlights, matching the grep filter scope
+    pub fn set_match_scope(&mut self, scope: MatchScope) {
+        self.match_scope = scope;
+    }
+
+    /// Set the output format
+    pub fn set_output_format(&mut self, format: OutputFormat) {
+        self.output_format = format;
+    }
+
+    /// Set how JSON messages are rendered
+    pub fn set_json_style(&mut self, style: JsonStyle) {
+        self.json_style = style;
+    }
+
+    /// Show the Kubernetes timestamp of each line before the prefix
+    pub fn set_timestamps(&mut self, mode: TimestampMode) {
+        self.timestamps = Some(TimestampFormatter::new(mode));
+    }
+
+    /// Set the output template
+    pub fn set_template(&mut self, template: Template) {
+        self.template = Some(template);
+    }
+
+    /// Set the node of each pod, used by the node placeholder of templates
+    pub fn set_pod_nodes(&mut self, pod_nodes: HashMap<String, String>) {
+        self.pod_nodes = pod_nodes;
+    }
+
+    /// Write every entry to a file as well, without colors
+    pub fn set_output_file(&mut self, file: RotatingFile) {
+        self.output_file = Some(file);
+    }
+
+    /// Write each container to its own file in a directory instead of the terminal
+    pub fn set_output_dir(&mut self, writer: SplitWriter) {
+        self.output_dir = Some(writer);
+    }
+
+    /// Get the directory writer, if output goes to a directory
+    pub fn output_dir(&self) -> Option<&SplitWriter> {
+        self.output_dir.as_ref()
+    }
+
+    /// Show the terminal output in a pager
+    pub fn set_pager(&mut self, pager: Pager) {
+        self.pager = Some(pager);
+    }
+
+    /// Wait for the user to quit the pager, if output goes to one
+    pub fn close_pager(&mut self) -> Result<()> {
+        match self.pager.take() {
+            Some(pager) => pager.wait(),
+            None => Ok(()),
+        }
+    }
+
+    /// Write out lines buffered for the terminal, e.g. before waiting for more
+    pub fn flush_terminal(&mut self) -> Result<()> {
+        if let Some(sink) = self.sink.as_mut() {
+            sink.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Flush lines buffered for the terminal, the output file and directory
+    pub fn flush(&mut self) -> Result<()> {
+        self.flush_terminal()?;
+        if let Some(file) = self.output_file.as_mut() {
+            file.flush()?;
+        }
+        if let Some(writer) = self.output_dir.as_mut() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Add pods for short names, and pad prefixes to the widest prefix among them
+    pub fn add_pods<'a>(&mut self, pod_names: impl IntoIterator<Item = &'a str> + Clone) {
+        self.short_names.extend(pod_names.clone());
+        for pod_name in pod_names {
+            let prefix = self.prefix(pod_name, self.short_names.get(pod_name));
+            self.prefix_width = self.prefix_width.max(prefix.width());
+        }
+    }
+
+    /// Get the short name of an entry's pod, or of each pod of a merged duplicate
+    fn short_name<'a>(&'a self, entry: &'a LogEntry) -> Cow<'a, str> {
+        match &entry.source {
+            Source::Merged(pods) => {
+                let names: Vec<&str> = pods.iter().map(|pod| self.short_names.get(pod)).collect();
+                Cow::Owned(names.join(","))
+            }
+            Source::Container | Source::Note => {
+                Cow::Borrowed(self.short_names.get(&entry.pod_name))
+            }
+        }
+    }
+
+    /// Fit colored lines into the terminal width by wrapping or truncating them
+    pub fn set_line_width(&mut self, width: usize, overflow: Overflow) {
+        self.line_width = Some(width);
+        self.overflow = overflow;
     }
 
     /// Format a log entry
     pub fn format(&mut self, entry: &LogEntry) -> Result<String> {
-        if self.no_prefix {
-            return Ok(entry.message.clone());
+        let timestamp = self.format_timestamp(entry);
+        Ok(self.format_plain(entry, timestamp.as_deref()))
+    }
+
+    /// Format a log entry without colors, with an already formatted timestamp
+    fn format_plain(&self, entry: &LogEntry, timestamp: Option<&str>) -> String {
+        if let Some(record) = encode(entry, self.output_format) {
+            return record;
         }
 
-        let prefix = if let Some(format) = &self.prefix_format {
-            let prefix_format = PrefixFormat::new(format);
-            prefix_format.format(entry)
-        } else {
-            format!("[{}]", entry.pod_name)
+        if let Some(template) = &self.template {
+            let node = self.pod_nodes.get(&*entry.pod_name).map(String::as_str);
+            let short_pod = self.short_name(entry);
+            return template.render(entry, node, Some(&short_pod));
+        }
+
+        let mut message = match render_json(entry.message(), self.json_style) {
+            Some(view) => view.text,
+            None => entry.message().to_string(),
         };
 
-        Ok(format!("{} {}", prefix, entry.message))
+        if !self.no_prefix {
+            message = format!("{} {}", self.entry_prefix(entry), message);
+        }
+
+        if let Some(timestamp) = timestamp {
+            message = format!("{} {}", timestamp, message);
+        }
+
+        message
+    }
+
+    /// Get the prefix of a pod
+    fn prefix(&self, pod_name: &str, short_name: &str) -> String {
+        match &self.prefix_format {
+            Some(format) => PrefixFormat::new(format).format_pod(pod_name, short_name),
+            None => format!("[{}]", pod_name),
+        }
+    }
+
+    /// Get the prefix of an entry
+    fn entry_prefix(&self, entry: &LogEntry) -> String {
+        self.prefix(&entry.pod_name, &self.short_name(entry))
+    }
+
+    /// Format the timestamp of an entry if timestamps are enabled
+    fn format_timestamp(&mut self, entry: &LogEntry) -> Option<String> {
+        self.timestamps.as_mut()?.format(entry.timestamp)
     }
 
     /// Format a log entry with color
     pub fn format_colored(&mut self, entry: &LogEntry) -> Result<()> {
-        let mut stdout = StandardStream::stdout(ColorChoice::Always);
+        let timestamp = self.format_timestamp(entry);
+
+        // The output file gets plain lines while the terminal keeps its colors
+        if self.output_file.is_some() || self.output_dir.is_some() {
+            let line = self.format_plain(entry, timestamp.as_deref());
+            if let Some(file) = self.output_file.as_mut() {
+                file.write_line(&line)?;
+            }
+            if let Some(writer) = self.output_dir.as_mut() {
+                writer.write_line(entry, &line)?;
+                return Ok(());
+            }
+        }
+
+        // Machine-readable formats are written without colors
+        if let Some(record) = encode(entry, self.output_format) {
+            let header = self.output_format == OutputFormat::Csv && !self.header_written;
+            self.header_written = true;
+            return match self.pager.as_mut() {
+                Some(pager) => Self::write_record(pager, header, &record),
+                None => {
+                    let sink = self
+                        .sink
+                        .get_or_insert_with(|| OutputSink::stdout(self.color_choice));
+                    Self::write_record(sink, header, &record)
+                }
+            };
+        }
+
+        if let Some(mut pager) = self.pager.take() {
+            let result = self.write_colored(&mut pager, entry, timestamp.as_deref());
+            self.pager = Some(pager);
+            return result;
+        }
+        let mut sink = self
+            .sink
+            .take()
+            .unwrap_or_else(|| OutputSink::stdout(self.color_choice));
+        let result = self.write_colored(&mut sink, entry, timestamp.as_deref());
+        self.sink = Some(sink);
+        result
+    }
+
+    /// Write a log entry with colors to any writer, e.g. to render it elsewhere
+    pub fn write_entry<W: WriteColor>(&mut self, out: &mut W, entry: &LogEntry) -> Result<()> {
+        let timestamp = self.format_timestamp(entry);
+        self.write_colored(out, entry, timestamp.as_deref())
+    }
+
+    /// Write a machine-readable record, preceded by the CSV header if needed
+    fn write_record<W: Write>(out: &mut W, header: bool, record: &str) -> Result<()> {
+        if header {
+            writeln!(out, "{}", csv_header())?;
+        }
+        writeln!(out, "{}", record)?;
+        Ok(())
+    }
+
+    /// Write a log entry with colors, fitted to the terminal width if one is set
+    fn write_colored<W: WriteColor>(
+        &mut self,
+        out: &mut W,
+        entry: &LogEntry,
+        timestamp: Option<&str>,
+    ) -> Result<()> {
+        let prefix = (!self.no_prefix && self.template.is_none()).then(|| self.entry_prefix(entry));
+        // Notes and merged duplicates don't widen the column of the pods
+        let widens = entry.source == Source::Container;
+        if let Some(prefix) = prefix.as_ref().filter(|_| widens) {
+            self.prefix_width = self.prefix_width.max(prefix.width());
+        }
+
+        let Some(width) = self.line_width else {
+            return self.write_line(out, entry, timestamp, prefix.as_deref());
+        };
+        let indent = self.message_column(entry, timestamp, prefix.is_some());
+        let mut lines = LineWriter::new(out, width, self.overflow, indent);
+        self.write_line(&mut lines, entry, timestamp, prefix.as_deref())?;
+        lines.finish()?;
+        Ok(())
+    }
+
+    /// Get the column the message starts at, where continuation lines are indented to
+    fn message_column(&self, entry: &LogEntry, timestamp: Option<&str>, prefix: bool) -> usize {
+        if let Some(template) = &self.template {
+            let node = self.pod_nodes.get(&*entry.pod_name).map(String::as_str);
+            let mut column = 0;
+            for part in template.render_parts(entry, node, Some(&self.short_name(entry))) {
+                if matches!(part.key, Some(Key::Message)) {
+                    return column + part.text[..part.offset].width();
+                }
+                column += part.text.width();
+            }
+            return 0;
+        }
 
-        // Get or generate color for pod
-        let color = self
+        let mut column = 0;
+        if let Some(timestamp) = timestamp {
+            column += timestamp.width() + 1;
+        }
+        if prefix {
+            column += self.prefix_width + 1;
+        }
+        column
+    }
+
+    /// Write a log entry with pod, level and highlight colors
+    fn write_line<W: WriteColor>(
+        &mut self,
+        out: &mut W,
+        entry: &LogEntry,
+        timestamp: Option<&str>,
+        prefix: Option<&str>,
+    ) -> Result<()> {
+        // Pods get the same color in every run
+        let color = *self
             .pod_colors
             .entry(entry.pod_name.clone())
-            .or_insert_with(|| self.color_generator.next_color());
+            .or_insert_with(|| self.color_generator.color_for_string(&entry.pod_name));
+        let level_style = self.theme.level_style(entry.level());
 
-        // Format prefix
-        if !self.no_prefix {
-            let prefix = if let Some(format) = &self.prefix_format {
-                let prefix_format = PrefixFormat::new(format);
-                prefix_format.format(entry)
-            } else {
-                format!("[{}]", entry.pod_name)
-            };
+        // If highlighting is enabled and we have patterns, highlight matches
+        let matcher = self
+            .matcher
+            .as_ref()
+            .filter(|m| self.highlight && !m.is_empty());
+        let spans = matcher
+            .map(|matcher| self.match_scope.highlight_spans(entry, matcher))
+            .unwrap_or_default();
+
+        if let Some(template) = &self.template {
+            let node = self.pod_nodes.get(&*entry.pod_name).map(String::as_str);
+            for part in template.render_parts(entry, node, Some(&self.short_name(entry))) {
+                self.write_template_part(out, &part, color, level_style, &spans)?;
+            }
+            writeln!(out)?;
+            return Ok(());
+        }
+
+        if let Some(timestamp) = timestamp {
+            Self::write_styled(out, timestamp, Some(&self.theme.timestamp))?;
+            write!(out, " ")?;
+        }
 
+        if let Some(prefix) = prefix {
             // Write colored prefix
-            stdout.set_color(ColorSpec::new().set_fg(Some(*color)))?;
-            write!(stdout, "{}", prefix)?;
-            stdout.reset()?;
+            out.set_color(ColorSpec::new().set_fg(Some(color)))?;
+            write!(out, "{}", prefix)?;
+            out.reset()?;
 
-            // Add space after prefix
-            write!(stdout, " ")?;
+            // Pad to the prefix column and add space after prefix
+            let padding = self.prefix_width.saturating_sub(prefix.width());
+            write!(out, "{:padding$} ", "")?;
         }
 
-        // If highlighting is enabled and we have grep filters, highlight matches
-        if self.highlight && !self.grep_filters.is_empty() {
-            self.write_highlighted_message(&mut stdout, &entry.message)?;
+        if let Some(view) = render_json(entry.message(), self.json_style) {
+            let spans = matcher
+                .map(|matcher| self.json_highlight_spans(&view, matcher))
+                .unwrap_or_default();
+            self.write_json(out, &view, level_style, &spans)?;
         } else {
-            // Write message without highlighting
-            writeln!(stdout, "{}", entry.message)?;
+            self.write_highlighted_message(out, &spans, entry.message(), level_style)?;
         }
+        writeln!(out)?;
 
         Ok(())
     }
-    
-    /// Write a message with highlighted grep matches
-    fn write_highlighted_message(&self, stdout: &mut StandardStream, message: &str) -> Result<()> {
-        // Collect all matches from all filters
-        let mut matches = Vec::new();
-        for filter in &self.grep_filters {
-            matches.extend(filter.find_matches(message));
-        }
-        
-        // Sort matches by start position
-        matches.sort_by_key(|&(start, _)| start);
-        
-        // Merge overlapping matches
-        let mut merged_matches = Vec::new();
-        for (start, end) in matches {
-            if let Some((_, last_end)) = merged_matches.last_mut() {
-                if start <= *last_end {
-                    // Overlapping match, extend the previous one
-                    *last_end = std::cmp::max(*last_end, end);
-                } else {
-                    // Non-overlapping match, add it
-                    merged_matches.push((start, end));
-                }
-            } else {
-                // First match
-                merged_matches.push((start, end));
+
+    /// Locate highlights in rendered JSON, within the field value for field scopes
+    fn json_highlight_spans(
+        &self,
+        view: &StyledText,
+        matcher: &PatternMatcher,
+    ) -> Vec<(usize, usize)> {
+        match &self.match_scope {
+            MatchScope::Level | MatchScope::Pod | MatchScope::Container => Vec::new(),
+            MatchScope::Field(name) => {
+                let Some((start, end)) = view.field_range(name) else {
+                    return Vec::new();
+                };
+                matcher
+                    .find_matches(&view.text[start..end])
+                    .into_iter()
+                    .map(|(s, e)| (s + start, e + start))
+                    .collect()
+            }
+            MatchScope::Message | MatchScope::Raw => matcher.find_matches(&view.text),
+        }
+    }
+
+    /// Write rendered JSON with syntax colors and highlighted spans
+    fn write_json<W: WriteColor>(
+        &self,
+        out: &mut W,
+        view: &StyledText,
+        level_style: Option<&ColorSpec>,
+        spans: &[(usize, usize)],
+    ) -> Result<()> {
+        let mut pos = 0;
+        for &(start, end, token) in &view.runs {
+            // Whitespace between syntax elements
+            self.write_segment(out, &view.text, pos, start, None, spans)?;
+            let style = match token {
+                JsonToken::Message => level_style,
+                token => self.theme.json_style(token),
+            };
+            self.write_segment(out, &view.text, start, end, style, spans)?;
+            pos = end;
+        }
+        self.write_segment(out, &view.text, pos, view.text.len(), None, spans)
+    }
+
+    /// Write a rendered template part, coloring pod and level parts and highlighting the message
+    fn write_template_part<W: WriteColor>(
+        &self,
+        out: &mut W,
+        part: &Rendered,
+        pod_color: Color,
+        level_style: Option<&ColorSpec>,
+        spans: &[(usize, usize)],
+    ) -> Result<()> {
+        match part.key {
+            Some(Key::Pod | Key::ShortPod) => {
+                out.set_color(ColorSpec::new().set_fg(Some(pod_color)))?;
+                write!(out, "{}", part.text)?;
+                out.reset()?;
+            }
+            Some(Key::Level) => Self::write_styled(out, &part.text, level_style)?,
+            Some(Key::Message) => {
+                // Spans refer to the message, so clip them to the truncated value
+                let value_end = part.offset + part.len;
+                let spans: Vec<(usize, usize)> = spans
+                    .iter()
+                    .filter(|&&(start, _)| start < part.len)
+                    .map(|&(start, end)| (start, end.min(part.len)))
+                    .collect();
+                write!(out, "{}", &part.text[..part.offset])?;
+                self.write_highlighted_message(
+                    out,
+                    &spans,
+                    &part.text[part.offset..value_end],
+                    level_style,
+                )?;
+                write!(out, "{}", &part.text[value_end..])?;
             }
+            _ => write!(out, "{}", part.text)?,
         }
-        
-        // Write message with highlighted matches
-        let mut last_end = 0;
-        for (start, end) in merged_matches {
+        Ok(())
+    }
+
+    /// Write text in a style, or unstyled without one
+    fn write_styled<W: WriteColor>(
+        out: &mut W,
+        text: &str,
+        style: Option<&ColorSpec>,
+    ) -> Result<()> {
+        match style {
+            Some(style) => {
+                out.set_color(style)?;
+                write!(out, "{}", text)?;
+                out.reset()?;
+            }
+            None => write!(out, "{}", text)?,
+        }
+        Ok(())
+    }
+    
+    /// Write a message in the level style with highlighted spans,
+    /// which must be sorted and non-overlapping
+    fn write_highlighted_message<W: WriteColor>(
+        &self,
+        out: &mut W,
+        spans: &[(usize, usize)],
+        message: &str,
+        level_style: Option<&ColorSpec>,
+    ) -> Result<()> {
+        self.write_segment(out, message, 0, message.len(), level_style, spans)
+    }
+
+    /// Write a range of a text in a style, with the parts inside spans highlighted
+    fn write_segment<W: WriteColor>(
+        &self,
+        out: &mut W,
+        text: &str,
+        start: usize,
+        end: usize,
+        style: Option<&ColorSpec>,
+        spans: &[(usize, usize)],
+    ) -> Result<()> {
+        let mut last_end = start;
+        for &(span_start, span_end) in spans {
+            let (span_start, span_end) = (span_start.max(start), span_end.min(end));
+            if span_start >= span_end {
+                continue;
+            }
+
             // Write non-highlighted text before match
-            if start > last_end {
-                write!(stdout, "{}", &message[last_end..start])?;
+            if span_start > last_end {
+                Self::write_styled(out, &text[last_end..span_start], style)?;
             }
-            
+
             // Write highlighted match
-            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Yellow)).set_intense(true))?;
-            write!(stdout, "{}", &message[start..end])?;
-            stdout.reset()?;
-            
-            last_end = end;
+            Self::write_styled(
+                out,
+                &text[span_start..span_end],
+                Some(&self.theme.highlight),
+            )?;
+            last_end = span_end;
         }
-        
+
         // Write remaining non-highlighted text
-        if last_end < message.len() {
-            write!(stdout, "{}", &message[last_end..])?;
+        if last_end < end {
+            Self::write_styled(out, &text[last_end..end], style)?;
         }
-        
-        // Add newline at the end
-        writeln!(stdout)?;
-        
         Ok(())
     }
 }
@@ -193,28 +655,39 @@ mod tests {
     use super::*;
 
     fn create_test_entry() -> LogEntry {
-        LogEntry {
-            pod_name: "test-pod".to_string(),
-            raw_line: "Hello, world!".to_string(),
-            message: "Hello, world!".to_string(),
-        }
+        LogEntry::for_test("test-pod", "Hello, world!")
     }
 
     #[test]
     fn test_prefix_format() {
         let entry = create_test_entry();
+        let short_names = ShortNames::new(["test-pod"]);
 
         let format = PrefixFormat::new("[%n]");
-        assert_eq!(format.format(&entry), "[test-pod]");
+        assert_eq!(format.format(&entry, &short_names), "[test-pod]");
 
         let format = PrefixFormat::new("[%s]");
-        assert_eq!(format.format(&entry), "[test-pod]");
+        assert_eq!(format.format(&entry, &short_names), "[test-pod]");
 
         let format = PrefixFormat::new("[%t]");
-        assert_eq!(format.format(&entry), "[%t]");
+        assert_eq!(format.format(&entry, &short_names), "[%t]");
 
         let format = PrefixFormat::new("[%t %n]");
-        assert_eq!(format.format(&entry), "[%t test-pod]");
+        assert_eq!(format.format(&entry, &short_names), "[%t test-pod]");
+
+        // Replicas are told apart by what differs in their names
+        let short_names = ShortNames::new([
+            "nginx-deployment-abc12-xyz34",
+            "nginx-deployment-abc12-qrs56",
+        ]);
+        let format = PrefixFormat::new("[%s]");
+        assert_eq!(
+            format.format_pod(
+                "nginx-deployment-abc12-xyz34",
+                short_names.get("nginx-deployment-abc12-xyz34")
+            ),
+            "[xyz34]"
+        );
     }
 
     #[test]
@@ -227,4 +700,257 @@ mod tests {
             "[test-pod] Hello, world!"
         );
     }
+
+    #[test]
+    fn test_formatter_output_format() {
+        let entry = create_test_entry();
+        let mut formatter = LogFormatter::new(None, false);
+        formatter.set_output_format(OutputFormat::Logfmt);
+
+        assert_eq!(
+            formatter.format(&entry).unwrap(),
+            r#"namespace=default pod=test-pod container=main msg="Hello, world!""#
+        );
+    }
+
+    #[test]
+    fn test_formatter_template() {
+        let entry = create_test_entry();
+        let mut formatter = LogFormatter::new(None, false);
+        formatter.set_template(Template::parse("{ns}/{pod:<10}{node:>8} {message:.5}").unwrap());
+        formatter.set_pod_nodes(HashMap::from([(
+            "test-pod".to_string(),
+            "node-a".to_string(),
+        )]));
+
+        assert_eq!(
+            formatter.format(&entry).unwrap(),
+            "default/test-pod    node-a Hello"
+        );
+    }
+
+    #[test]
+    fn test_formatter_colors() {
+        let entry = LogEntry::for_test("test-pod", "ERROR disk full");
+        let mut formatter = LogFormatter::new(None, false);
+        formatter.set_matcher(Arc::new(
+            PatternMatcher::new(vec!["disk".to_string()]).unwrap(),
+        ));
+
+        let mut buffer = termcolor::Buffer::ansi();
+        formatter.write_colored(&mut buffer, &entry, None).unwrap();
+        let output = String::from_utf8(buffer.into_inner()).unwrap();
+        // Red message with a bold intense yellow match
+        assert!(output.contains("\x1b[31mERROR \x1b[0m"), "{:?}", output);
+        assert!(
+            output.contains("\x1b[1m\x1b[38;5;11mdisk\x1b[0m"),
+            "{:?}",
+            output
+        );
+
+        let mut buffer = termcolor::Buffer::no_color();
+        formatter.write_colored(&mut buffer, &entry, None).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer.into_inner()).unwrap(),
+            "[test-pod] ERROR disk full\n"
+        );
+    }
+
+    #[test]
+    fn test_formatter_aligned_prefixes() {
+        let mut formatter = LogFormatter::new(None, false);
+        formatter.add_pods(["web-1", "worker-2"]);
+        let write = |formatter: &mut LogFormatter, entry: &LogEntry| {
+            let mut buffer = termcolor::Buffer::no_color();
+            formatter.write_colored(&mut buffer, entry, None).unwrap();
+            String::from_utf8(buffer.into_inner()).unwrap()
+        };
+
+        let mut entry = create_test_entry();
+        entry.pod_name = "web-1".into();
+        assert_eq!(write(&mut formatter, &entry), "[web-1]    Hello, world!\n");
+
+        // A wider pod that joins later widens the column
+        entry.pod_name = "scheduler-3".into();
+        assert_eq!(
+            write(&mut formatter, &entry),
+            "[scheduler-3] Hello, world!\n"
+        );
+        entry.pod_name = "web-1".into();
+        assert_eq!(
+            write(&mut formatter, &entry),
+            "[web-1]       Hello, world!\n"
+        );
+
+        // Plain output is not padded
+        assert_eq!(formatter.format(&entry).unwrap(), "[web-1] Hello, world!");
+    }
+
+    #[test]
+    fn test_formatter_synthetic_pods() {
+        let mut formatter = LogFormatter::new(Some("[%s]".to_string()), false);
+        formatter.add_pods(["web-abc12-xyz34", "web-abc12-qrs56"]);
+        let write = |formatter: &mut LogFormatter, entry: &LogEntry| {
+            let mut buffer = termcolor::Buffer::no_color();
+            formatter.write_colored(&mut buffer, entry, None).unwrap();
+            String::from_utf8(buffer.into_inner()).unwrap()
+        };
+
+        // Notes and merged duplicates keep the short names and the column as they are
+        let note =
+            LogEntry::new("", "klogrs", "", "throttled".to_string()).with_source(Source::Note);
+        assert_eq!(write(&mut formatter, &note), "[klogrs] throttled\n");
+        let pods = vec!["web-abc12-xyz34".into(), "web-abc12-qrs56".into()];
+        let mut entry = LogEntry::for_test("web-abc12-xyz34,web-abc12-qrs56", "started")
+            .with_source(Source::Merged(pods));
+        assert_eq!(write(&mut formatter, &entry), "[xyz34,qrs56] started\n");
+
+        entry = LogEntry::for_test("web-abc12-xyz34", "started");
+        assert_eq!(write(&mut formatter, &entry), "[xyz34] started\n");
+    }
+
+    #[test]
+    fn test_formatter_line_width() {
+        let entry = LogEntry::for_test(
+            "test-pod",
+            "connection to the database was refused after three attempts",
+        );
+        let mut formatter = LogFormatter::new(None, false);
+
+        formatter.set_line_width(40, Overflow::Wrap);
+        let mut buffer = termcolor::Buffer::no_color();
+        formatter
+            .write_colored(&mut buffer, &entry, Some("10:00:05"))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(buffer.into_inner()).unwrap(),
+            "10:00:05 [test-pod] connection to the\n                    database was refused\n                    after three attempts\n"
+        );
+
+        formatter.set_line_width(40, Overflow::Truncate);
+        let mut buffer = termcolor::Buffer::no_color();
+        formatter
+            .write_colored(&mut buffer, &entry, Some("10:00:05"))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(buffer.into_inner()).unwrap(),
+            "10:00:05 [test-pod] connection to the d…\n"
+        );
+    }
+
+    #[test]
+    fn test_formatter_json_style() {
+        let entry = LogEntry::for_test(
+            "test-pod",
+            r#"{"msg":"card declined","user":"bob","code":402}"#,
+        );
+        let mut formatter = LogFormatter::new(None, false);
+        formatter.set_json_style(JsonStyle::Compact);
+
+        assert_eq!(
+            formatter.format(&entry).unwrap(),
+            "[test-pod] card declined user=bob code=402"
+        );
+
+        // Grep matches inside field values are still highlighted
+        formatter.set_matcher(Arc::new(
+            PatternMatcher::new(vec!["bob".to_string()]).unwrap(),
+        ));
+        let mut buffer = termcolor::Buffer::ansi();
+        formatter.write_colored(&mut buffer, &entry, None).unwrap();
+        let output = String::from_utf8(buffer.into_inner()).unwrap();
+        assert!(output.contains("\x1b[34muser\x1b[0m"), "{:?}", output);
+        assert!(
+            output.contains("\x1b[1m\x1b[38;5;11mbob\x1b[0m"),
+            "{:?}",
+            output
+        );
+
+        // With a field scope, only that field is highlighted
+        formatter.set_match_scope(MatchScope::Field("msg".to_string()));
+        let mut buffer = termcolor::Buffer::ansi();
+        formatter.write_colored(&mut buffer, &entry, None).unwrap();
+        let output = String::from_utf8(buffer.into_inner()).unwrap();
+        assert!(!output.contains("\x1b[38;5;11m"), "{:?}", output);
+    }
+
+    #[test]
+    fn test_formatter_timestamps() {
+        let mut entry = LogEntry::parse(
+            "default",
+            "test-pod".to_string(),
+            "main".to_string(),
+            "2024-05-01T10:00:05.5Z Hello, world!".to_string(),
+        );
+        let mut formatter = LogFormatter::new(None, false);
+        formatter.set_timestamps("Europe/Berlin %H:%M:%S%.3f".parse().unwrap());
+        assert_eq!(
+            formatter.format(&entry).unwrap(),
+            "12:00:05.500 [test-pod] Hello, world!"
+        );
+
+        formatter.set_timestamps(TimestampMode::Relative);
+        formatter.format(&entry).unwrap();
+        entry.timestamp = entry
+            .timestamp
+            .map(|ts| ts + chrono::TimeDelta::milliseconds(1500));
+        assert_eq!(
+            formatter.format(&entry).unwrap(),
+            "+1.5s [test-pod] Hello, world!"
+        );
+
+        // Lines without a timestamp are shown without one
+        assert_eq!(
+            formatter.format(&create_test_entry()).unwrap(),
+            "[test-pod] Hello, world!"
+        );
+    }
+
+    #[test]
+    fn test_formatter_output_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture.log");
+        let entry = LogEntry::parse(
+            "default",
+            "test-pod".to_string(),
+            "main".to_string(),
+            "2024-05-01T10:00:05Z ERROR disk full".to_string(),
+        );
+
+        let mut formatter = LogFormatter::new(None, false);
+        formatter.set_color_choice(ColorChoice::Never);
+        formatter.set_timestamps("%H:%M:%S".parse().unwrap());
+        formatter.set_output_file(RotatingFile::open(&path, Default::default()).unwrap());
+        formatter.format_colored(&entry).unwrap();
+        formatter.flush().unwrap();
+
+        // Plain text, with the timestamp formatted once for both outputs
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "10:00:05 [test-pod] ERROR disk full\n"
+        );
+    }
+
+    #[test]
+    fn test_formatter_output_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut entry = LogEntry::parse(
+            "default",
+            "test-pod".to_string(),
+            "main".to_string(),
+            "2024-05-01T10:00:05Z ERROR disk full".to_string(),
+        );
+        entry.namespace = "default".into();
+
+        let mut formatter = LogFormatter::new(None, false);
+        formatter.set_output_dir(SplitWriter::create(dir.path()).unwrap());
+        formatter.format_colored(&entry).unwrap();
+        formatter.flush().unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("default/test-pod/main.log")).unwrap(),
+            "[test-pod] ERROR disk full\n"
+        );
+        assert_eq!(formatter.output_dir().unwrap().line_count(), 1);
+    }
 }
@@ -0,0 +1,167 @@
+use anyhow::{Context, Result};
+use regex::{Regex, RegexSet, RegexSetBuilder};
+
+/// Size limit for the compiled pattern set, large enough for watchlists of
+/// several hundred error signatures
+const SET_SIZE_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Multi-pattern matcher that compiles all patterns into a single `RegexSet`
+///
+/// The set is used to decide whether a line matches and which patterns hit,
+/// so only the patterns that actually matched are run again to locate the
+/// spans used for highlighting.
+#[derive(Clone, Debug)]
+pub struct PatternMatcher {
+    /// Original pattern strings
+    patterns: Vec<String>,
+    /// Compiled set of all patterns
+    set: RegexSet,
+    /// Individual regexes, indexed like the set
+    regexes: Vec<Regex>,
+}
+
+impl PatternMatcher {
+    /// Create a new matcher from a list of patterns
+    ///
+    /// Like `GrepFilter`, patterns that are not valid regular expressions are
+    /// matched literally.
+    pub fn new<I, S>(patterns: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let patterns: Vec<String> = patterns
+            .into_iter()
+            .map(|p| p.as_ref().to_string())
+            .collect();
+
+        let mut regexes = Vec::with_capacity(patterns.len());
+        for pattern in &patterns {
+            let regex = match Regex::new(pattern) {
+                Ok(re) => re,
+                Err(_) => Regex::new(&regex::escape(pattern))
+                    .with_context(|| format!("Invalid pattern: {}", pattern))?,
+            };
+            regexes.push(regex);
+        }
+
+        let set = RegexSetBuilder::new(regexes.iter().map(|re| re.as_str()))
+            .size_limit(SET_SIZE_LIMIT)
+            .build()
+            .context("Failed to compile pattern set")?;
+
+        Ok(Self {
+            patterns,
+            set,
+            regexes,
+        })
+    }
+
+    /// Get the number of patterns
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Check whether the matcher has no patterns
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Get the pattern strings
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// Check whether any pattern matches the text
+    pub fn is_match(&self, text: &str) -> bool {
+        self.set.is_match(text)
+    }
+
+    /// Check whether every pattern matches the text
+    pub fn is_match_all(&self, text: &str) -> bool {
+        self.set.matches(text).matched_all()
+    }
+
+    /// Get the indices of the patterns that match the text
+    pub fn matching_patterns(&self, text: &str) -> Vec<usize> {
+        self.set.matches(text).into_iter().collect()
+    }
+
+    /// Find all match spans in the text, sorted and with overlaps merged
+    pub fn find_matches(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut matches = Vec::new();
+        for index in self.set.matches(text).iter() {
+            matches.extend(
+                self.regexes[index]
+                    .find_iter(text)
+                    .filter(|m| m.start() < m.end())
+                    .map(|m| (m.start(), m.end())),
+            );
+        }
+
+        merge_spans(matches)
+    }
+}
+
+/// Sort spans by start position and merge the overlapping ones
+pub fn merge_spans(mut spans: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    spans.sort_by_key(|&(start, _)| start);
+
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(spans.len());
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end => {
+                *last_end = std::cmp::max(*last_end, end);
+            }
+            _ => merged.push((start, end)),
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_patterns() {
+        let matcher = PatternMatcher::new(["timeout", "refused", "ERROR"]).unwrap();
+
+        assert_eq!(
+            matcher.matching_patterns("ERROR: connection refused"),
+            vec![1, 2]
+        );
+        assert!(matcher.is_match("request timeout"));
+        assert!(!matcher.is_match("all good"));
+        assert!(matcher.is_match_all("ERROR: timeout, connection refused"));
+        assert!(!matcher.is_match_all("ERROR: timeout"));
+    }
+
+    #[test]
+    fn test_invalid_regex_is_literal() {
+        let matcher = PatternMatcher::new(["[", "a(b"]).unwrap();
+
+        assert_eq!(matcher.matching_patterns("x [ y"), vec![0]);
+        assert_eq!(matcher.matching_patterns("a(b"), vec![1]);
+    }
+
+    #[test]
+    fn test_find_matches_merges_overlaps() {
+        let matcher = PatternMatcher::new(["conn", "connection", "refused"]).unwrap();
+
+        assert_eq!(
+            matcher.find_matches("connection refused, conn"),
+            vec![(0, 10), (11, 18), (20, 24)]
+        );
+    }
+
+    #[test]
+    fn test_empty_matcher() {
+        let matcher = PatternMatcher::new(Vec::<String>::new()).unwrap();
+
+        assert!(matcher.is_empty());
+        assert!(!matcher.is_match("anything"));
+        assert!(matcher.find_matches("anything").is_empty());
+    }
+}
@@ -0,0 +1,144 @@
+use chrono::{DateTime, Utc};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::kubernetes::LogEntry;
+
+/// Stream being merged, with the timestamp of its next entry
+struct Source<I> {
+    entries: I,
+    /// Next entry and the timestamp it is ordered by
+    head: Option<(Option<DateTime<Utc>>, LogEntry)>,
+    /// Timestamp of the previous entry with one
+    previous: Option<DateTime<Utc>>,
+}
+
+impl<I: Iterator<Item = LogEntry>> Source<I> {
+    /// Advance to the next entry
+    ///
+    /// Lines without a timestamp, such as continuation lines of a stack trace,
+    /// take the timestamp of the line before them so they stay in place.
+    fn advance(&mut self) {
+        self.head = self.entries.next().map(|entry| {
+            self.previous = entry.timestamp.or(self.previous);
+            (self.previous, entry)
+        });
+    }
+}
+
+/// Iterator merging streams that are each in timestamp order
+///
+/// This is a k-way merge: the next entry is always the earliest head among the
+/// streams, so only one entry per stream is held at a time. Entries with equal
+/// timestamps keep the order of their streams, so the result is deterministic.
+pub struct MergeIter<I> {
+    sources: Vec<Source<I>>,
+    /// Timestamps of the stream heads, earliest first
+    heads: BinaryHeap<Reverse<(Option<DateTime<Utc>>, usize)>>,
+}
+
+impl<I: Iterator<Item = LogEntry>> Iterator for MergeIter<I> {
+    type Item = LogEntry;
+
+    fn next(&mut self) -> Option<LogEntry> {
+        let Reverse((_, index)) = self.heads.pop()?;
+        let source = &mut self.sources[index];
+        let (_, entry) = source.head.take().expect("stream has a head");
+        source.advance();
+        if let Some((timestamp, _)) = &source.head {
+            self.heads.push(Reverse((*timestamp, index)));
+        }
+        Some(entry)
+    }
+}
+
+/// Merge streams that are each in timestamp order into one stream in timestamp order
+pub fn merge_streams<S>(streams: impl IntoIterator<Item = S>) -> MergeIter<S::IntoIter>
+where
+    S: IntoIterator<Item = LogEntry>,
+{
+    let mut sources = Vec::new();
+    let mut heads = BinaryHeap::new();
+    for (index, stream) in streams.into_iter().enumerate() {
+        let mut source = Source {
+            entries: stream.into_iter(),
+            head: None,
+            previous: None,
+        };
+        source.advance();
+        if let Some((timestamp, _)) = &source.head {
+            heads.push(Reverse((*timestamp, index)));
+        }
+        sources.push(source);
+    }
+    MergeIter { sources, heads }
+}
+
+/// Merge buffered streams that are each in timestamp order into one vector
+pub fn merge_by_timestamp(streams: Vec<Vec<LogEntry>>) -> Vec<LogEntry> {
+    merge_streams(streams).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(pod: &str, lines: &[&str]) -> Vec<LogEntry> {
+        lines
+            .iter()
+            .map(|line| {
+                LogEntry::parse(
+                    "default",
+                    pod.to_string(),
+                    "main".to_string(),
+                    line.to_string(),
+                )
+            })
+            .collect()
+    }
+
+    fn messages(entries: &[LogEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.message()).collect()
+    }
+
+    #[test]
+    fn test_merge_by_timestamp() {
+        let merged = merge_by_timestamp(vec![
+            stream(
+                "a",
+                &[
+                    "2024-05-01T10:00:01Z a1",
+                    "2024-05-01T10:00:04Z a2",
+                    "2024-05-01T10:00:05Z a3",
+                ],
+            ),
+            stream("b", &["2024-05-01T10:00:02Z b1", "2024-05-01T10:00:03Z b2"]),
+            Vec::new(),
+            stream("c", &["2024-05-01T10:00:00Z c1", "2024-05-01T10:00:04Z c2"]),
+        ]);
+        assert_eq!(
+            messages(&merged),
+            ["c1", "a1", "b1", "b2", "a2", "c2", "a3"]
+        );
+    }
+
+    #[test]
+    fn test_merge_lines_without_timestamp() {
+        let merged = merge_by_timestamp(vec![
+            stream(
+                "a",
+                &[
+                    "2024-05-01T10:00:01Z panic",
+                    "at main.rs:10",
+                    "2024-05-01T10:00:05Z recovered",
+                ],
+            ),
+            stream("b", &["2024-05-01T10:00:02Z b1"]),
+        ]);
+        // The continuation line stays right after the line it belongs to
+        assert_eq!(
+            messages(&merged),
+            ["panic", "main.rs:10", "b1", "recovered"]
+        );
+    }
+}
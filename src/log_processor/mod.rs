@@ -1,5 +1,41 @@
+pub mod alert;
+pub mod buffer;
+pub mod dedup;
+pub mod encoder;
 pub mod filter;
 pub mod formatter;
+pub mod matcher;
+pub mod merge;
+pub mod pipe;
+pub mod pretty;
+pub mod redact;
+pub mod reorder;
+pub mod stage;
+pub mod stats;
+pub mod summary;
+pub mod template;
+pub mod throttle;
+pub mod timestamp;
+pub mod wait;
+pub mod wrap;
 
-pub use filter::{Filter, GrepFilter};
+pub use alert::{AlertEngine, AlertStage};
+pub use buffer::StreamBuffers;
+pub use dedup::DedupStage;
+pub use encoder::OutputFormat;
+pub use filter::{Filter, GrepFilter, PatternFilter};
 pub use formatter::{LogFormatter, PrefixFormat};
+pub use matcher::PatternMatcher;
+pub use merge::{merge_by_timestamp, merge_streams};
+pub use pipe::CommandPipe;
+pub use pretty::JsonStyle;
+pub use redact::Redactor;
+pub use reorder::ReorderStage;
+pub use stage::{Pipeline, Stage};
+pub use stats::StatsCollector;
+pub use summary::TemplateMiner;
+pub use template::Template;
+pub use throttle::ThrottleStage;
+pub use timestamp::{TimestampFormatter, TimestampMode};
+pub use wait::{WaitOutcome, WaitTracker};
+pub use wrap::Overflow;
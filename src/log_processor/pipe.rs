@@ -1,6 +1,7 @@
-use anyhow::{Context, Result};
-use std::io::{BufRead, BufReader, Write};
+use anyhow::{anyhow, Context, Result};
+use std::io::{self, Write};
 use std::process::{Command, Stdio};
+use std::thread;
 use tokio::sync::mpsc;
 use tokio::task;
 use tracing::{debug, error, warn};
@@ -22,6 +23,11 @@ impl CommandPipe {
 
     /// Process a log entry through the command pipe
     pub fn process(&self, entry: &LogEntry) -> Result<String> {
+        self.run(&entry.raw_line)
+    }
+
+    /// Run the command with the given input on stdin and return its output
+    pub fn run(&self, input: &str) -> Result<String> {
         // Create a command with shell
         #[cfg(target_os = "windows")]
         let mut cmd = Command::new("cmd");
@@ -43,56 +49,41 @@ impl CommandPipe {
             .spawn()
             .context(format!("Failed to start command: {}", self.command))?;
 
-        // Write the log message to stdin
-        if let Some(mut stdin) = child.stdin.take() {
-            stdin
-                .write_all(entry.raw_line.as_bytes())
+        // Write the input on its own thread while the output is read, so a
+        // command writing output before reading its input can't fill both pipes
+        let writer = child.stdin.take().map(|mut stdin| {
+            let input = input.to_string();
+            thread::spawn(move || {
+                // Dropping stdin at the end signals EOF
+                match stdin.write_all(input.as_bytes()) {
+                    // Commands that don't read their input may exit before it is written
+                    Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+                    result => result,
+                }
+            })
+        });
+
+        // Read stdout and stderr until the command finishes
+        let result = child
+            .wait_with_output()
+            .context("Failed to wait for command")?;
+        if let Some(writer) = writer {
+            writer
+                .join()
+                .map_err(|_| anyhow!("Command stdin writer panicked"))?
                 .context("Failed to write to command stdin")?;
-            // Close stdin to signal EOF
-            drop(stdin);
         }
 
-        // Read stdout
-        let stdout = child
-            .stdout
-            .take()
-            .context("Failed to open command stdout")?;
-        let reader = BufReader::new(stdout);
-        let mut output = String::new();
-
-        for line in reader.lines() {
-            match line {
-                Ok(line) => {
-                    output.push_str(&line);
-                    output.push('\n');
-                }
-                Err(e) => {
-                    warn!("Error reading command output: {}", e);
-                }
-            }
+        if !result.status.success() {
+            warn!(
+                "Command failed with status {}: {}",
+                result.status,
+                String::from_utf8_lossy(&result.stderr)
+            );
+            return Err(anyhow!("Command failed with status {}", result.status));
         }
 
-        // Wait for the command to finish
-        let status = child.wait().context("Failed to wait for command")?;
-
-        if !status.success() {
-            // Read stderr if command failed
-            if let Some(stderr) = child.stderr.take() {
-                let reader = BufReader::new(stderr);
-                let mut error_output = String::new();
-
-                for line in reader.lines() {
-                    if let Ok(line) = line {
-                        error_output.push_str(&line);
-                        error_output.push('\n');
-                    }
-                }
-
-                warn!("Command failed with status {}: {}", status, error_output);
-            }
-
-            return Err(anyhow::anyhow!("Command failed with status {}", status));
-        }
+        let mut output = String::from_utf8_lossy(&result.stdout).replace("\r\n", "\n");
 
         // Trim trailing newline
         if output.ends_with('\n') {
@@ -109,19 +100,16 @@ impl CommandPipe {
         output_tx: mpsc::Sender<Result<LogEntry>>,
     ) {
         // Spawn a blocking task for command execution
-        let _ = task::spawn_blocking(move || {
+        task::spawn_blocking(move || {
             while let Some(entry_result) = input_rx.blocking_recv() {
                 match entry_result {
                     Ok(entry) => {
                         match self.process(&entry) {
                             Ok(processed_message) => {
                                 // Create a new log entry with processed message
-                                let processed_entry = LogEntry {
-                                    pod_name: entry.pod_name,
-                                    raw_line: processed_message.clone(),
-                                    timestamp: entry.timestamp,
-                                    message: processed_message,
-                                };
+                                let mut processed_entry = entry;
+                                processed_entry.raw_line = processed_message.clone();
+                                processed_entry.set_message(processed_message);
 
                                 if let Err(e) = output_tx.blocking_send(Ok(processed_entry)) {
                                     error!("Failed to send processed log entry: {}", e);
@@ -159,12 +147,7 @@ mod tests {
     #[test]
     fn test_command_pipe_echo() {
         let pipe = CommandPipe::new("echo test");
-        let entry = LogEntry {
-            pod_name: "test-pod".to_string(),
-            raw_line: "Hello, world!".to_string(),
-            timestamp: None,
-            message: "Hello, world!".to_string(),
-        };
+        let entry = LogEntry::for_test("test-pod", "Hello, world!");
 
         let result = pipe.process(&entry).unwrap();
         assert_eq!(result, "test");
@@ -173,14 +156,20 @@ mod tests {
     #[test]
     fn test_command_pipe_cat() {
         let pipe = CommandPipe::new("cat");
-        let entry = LogEntry {
-            pod_name: "test-pod".to_string(),
-            raw_line: "Hello, world!".to_string(),
-            timestamp: None,
-            message: "Hello, world!".to_string(),
-        };
+        let entry = LogEntry::for_test("test-pod", "Hello, world!");
 
         let result = pipe.process(&entry).unwrap();
         assert_eq!(result, "Hello, world!");
     }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_command_pipe_output_before_input() {
+        // More output than a pipe buffer holds is written before reading the input
+        let pipe = CommandPipe::new("head -c 200000 /dev/zero | tr '\\0' x; wc -c");
+        let output = pipe.run(&"y".repeat(200_000)).unwrap();
+
+        assert!(output.starts_with(&"x".repeat(200_000)));
+        assert!(output.ends_with("200000"));
+    }
 }
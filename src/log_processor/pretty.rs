@@ -0,0 +1,312 @@
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// Keys holding the message of a JSON log line, in order of preference
+const MESSAGE_KEYS: [&str; 2] = ["msg", "message"];
+
+/// Indentation of one level in pretty output
+const INDENT: &str = "  ";
+
+/// How JSON log lines are rendered in text output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JsonStyle {
+    /// The line as it was logged
+    #[default]
+    Raw,
+    /// An indented object
+    Pretty,
+    /// The message followed by `key=value` pairs
+    Compact,
+}
+
+impl FromStr for JsonStyle {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "raw" => Ok(JsonStyle::Raw),
+            "pretty" => Ok(JsonStyle::Pretty),
+            "compact" => Ok(JsonStyle::Compact),
+            _ => Err(anyhow!(
+                "Invalid JSON style: {} (expected raw, pretty or compact)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for JsonStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonStyle::Raw => write!(f, "raw"),
+            JsonStyle::Pretty => write!(f, "pretty"),
+            JsonStyle::Compact => write!(f, "compact"),
+        }
+    }
+}
+
+/// Syntax element of rendered JSON, used to pick its color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JsonToken {
+    /// Braces, brackets, colons, commas and `=`
+    Punctuation,
+    /// Object keys
+    Key,
+    /// String values
+    String,
+    /// Number values
+    Number,
+    /// `true`, `false` and `null`
+    Literal,
+    /// The message in compact output
+    Message,
+}
+
+impl FromStr for JsonToken {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "punctuation" => Ok(JsonToken::Punctuation),
+            "key" => Ok(JsonToken::Key),
+            "string" => Ok(JsonToken::String),
+            "number" => Ok(JsonToken::Number),
+            "literal" => Ok(JsonToken::Literal),
+            "message" => Ok(JsonToken::Message),
+            _ => Err(anyhow!(
+                "Invalid JSON token: {} (expected punctuation, key, string, number, literal or message)",
+                s
+            )),
+        }
+    }
+}
+
+/// Text with the byte ranges of its syntax elements
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StyledText {
+    /// Rendered text
+    pub text: String,
+    /// Byte ranges of syntax elements; text outside them is whitespace
+    pub runs: Vec<(usize, usize, JsonToken)>,
+    /// Byte ranges of the values of top-level fields
+    fields: Vec<(String, usize, usize)>,
+}
+
+impl StyledText {
+    /// Append a syntax element
+    fn push(&mut self, text: &str, token: JsonToken) {
+        let start = self.text.len();
+        self.text.push_str(text);
+        self.runs.push((start, self.text.len(), token));
+    }
+
+    /// Append whitespace
+    fn push_plain(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    /// Get the byte range of the value of a top-level field
+    pub fn field_range(&self, name: &str) -> Option<(usize, usize)> {
+        self.fields
+            .iter()
+            .find(|(key, _, _)| key == name)
+            .map(|&(_, start, end)| (start, end))
+    }
+}
+
+/// Render a JSON log line in the given style
+///
+/// Returns `None` for the raw style and for messages that are not a JSON object.
+pub fn render_json(message: &str, style: JsonStyle) -> Option<StyledText> {
+    let trimmed = message.trim();
+    if style == JsonStyle::Raw || !trimmed.starts_with('{') {
+        return None;
+    }
+    let Ok(Value::Object(map)) = serde_json::from_str::<Value>(trimmed) else {
+        return None;
+    };
+
+    let mut output = StyledText::default();
+    match style {
+        JsonStyle::Raw => return None,
+        JsonStyle::Pretty => write_pretty_object(&mut output, &map, 0),
+        JsonStyle::Compact => write_compact(&mut output, &map),
+    }
+    Some(output)
+}
+
+/// Write a scalar or compact nested value
+fn write_scalar(output: &mut StyledText, value: &Value) {
+    let token = match value {
+        Value::String(_) => JsonToken::String,
+        Value::Number(_) => JsonToken::Number,
+        Value::Bool(_) | Value::Null => JsonToken::Literal,
+        // Nested values in compact output
+        Value::Array(_) | Value::Object(_) => JsonToken::String,
+    };
+    output.push(&value.to_string(), token);
+}
+
+/// Write an object as indented JSON
+fn write_pretty_object(output: &mut StyledText, map: &Map<String, Value>, depth: usize) {
+    if map.is_empty() {
+        output.push("{}", JsonToken::Punctuation);
+        return;
+    }
+
+    output.push("{", JsonToken::Punctuation);
+    for (i, (key, value)) in map.iter().enumerate() {
+        if i > 0 {
+            output.push(",", JsonToken::Punctuation);
+        }
+        output.push_plain("\n");
+        output.push_plain(&INDENT.repeat(depth + 1));
+        output.push(&Value::String(key.clone()).to_string(), JsonToken::Key);
+        output.push(":", JsonToken::Punctuation);
+        output.push_plain(" ");
+
+        let start = output.text.len();
+        write_pretty_value(output, value, depth + 1);
+        if depth == 0 {
+            output.fields.push((key.clone(), start, output.text.len()));
+        }
+    }
+    output.push_plain("\n");
+    output.push_plain(&INDENT.repeat(depth));
+    output.push("}", JsonToken::Punctuation);
+}
+
+/// Write a value as indented JSON
+fn write_pretty_value(output: &mut StyledText, value: &Value, depth: usize) {
+    match value {
+        Value::Object(map) => write_pretty_object(output, map, depth),
+        Value::Array(items) if !items.is_empty() => {
+            output.push("[", JsonToken::Punctuation);
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    output.push(",", JsonToken::Punctuation);
+                }
+                output.push_plain("\n");
+                output.push_plain(&INDENT.repeat(depth + 1));
+                write_pretty_value(output, item, depth + 1);
+            }
+            output.push_plain("\n");
+            output.push_plain(&INDENT.repeat(depth));
+            output.push("]", JsonToken::Punctuation);
+        }
+        Value::Array(_) => output.push("[]", JsonToken::Punctuation),
+        scalar => write_scalar(output, scalar),
+    }
+}
+
+/// Check whether a compact value needs quotes
+fn needs_quotes(value: &str) -> bool {
+    value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c == '"' || c == '=' || c.is_control())
+}
+
+/// Write the message followed by the other fields as `key=value` pairs
+fn write_compact(output: &mut StyledText, map: &Map<String, Value>) {
+    let message_key = MESSAGE_KEYS
+        .iter()
+        .find(|key| matches!(map.get(**key), Some(Value::String(_))));
+
+    if let Some(&key) = message_key {
+        if let Some(Value::String(message)) = map.get(key) {
+            let start = output.text.len();
+            output.push(message, JsonToken::Message);
+            output
+                .fields
+                .push((key.to_string(), start, output.text.len()));
+        }
+    }
+
+    for (key, value) in map {
+        if message_key.is_some_and(|k| *k == key) {
+            continue;
+        }
+        if !output.text.is_empty() {
+            output.push_plain(" ");
+        }
+        output.push(key, JsonToken::Key);
+        output.push("=", JsonToken::Punctuation);
+
+        let start = output.text.len();
+        match value {
+            Value::String(s) if needs_quotes(s) => {
+                output.push(&value.to_string(), JsonToken::String)
+            }
+            Value::String(s) => output.push(s, JsonToken::String),
+            other => write_scalar(output, other),
+        }
+        output.fields.push((key.clone(), start, output.text.len()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE: &str = r#"{"level":"error","msg":"payment failed","order":{"id":42,"items":["a"]},"retry":true,"user":"bob smith"}"#;
+
+    #[test]
+    fn test_parse_json_style() {
+        assert_eq!("pretty".parse::<JsonStyle>().unwrap(), JsonStyle::Pretty);
+        assert!("yaml".parse::<JsonStyle>().is_err());
+        assert_eq!("key".parse::<JsonToken>().unwrap(), JsonToken::Key);
+    }
+
+    #[test]
+    fn test_render_pretty() {
+        let output = render_json(LINE, JsonStyle::Pretty).unwrap();
+        assert_eq!(
+            output.text,
+            r#"{
+  "level": "error",
+  "msg": "payment failed",
+  "order": {
+    "id": 42,
+    "items": [
+      "a"
+    ]
+  },
+  "retry": true,
+  "user": "bob smith"
+}"#
+        );
+
+        let (start, end) = output.field_range("retry").unwrap();
+        assert_eq!(&output.text[start..end], "true");
+        assert!(output.runs.contains(&(start, end, JsonToken::Literal)));
+        assert!(output.field_range("id").is_none());
+    }
+
+    #[test]
+    fn test_render_compact() {
+        let output = render_json(LINE, JsonStyle::Compact).unwrap();
+        assert_eq!(
+            output.text,
+            r#"payment failed level=error order={"id":42,"items":["a"]} retry=true user="bob smith""#
+        );
+        assert_eq!(output.runs[0], (0, 14, JsonToken::Message));
+
+        let (start, end) = output.field_range("user").unwrap();
+        assert_eq!(&output.text[start..end], r#""bob smith""#);
+
+        // Without a message field, the fields come first
+        let output = render_json(r#"{"a":1}"#, JsonStyle::Compact).unwrap();
+        assert_eq!(output.text, "a=1");
+    }
+
+    #[test]
+    fn test_render_non_json() {
+        assert!(render_json("plain text", JsonStyle::Pretty).is_none());
+        assert!(render_json("{broken", JsonStyle::Pretty).is_none());
+        assert!(render_json("[1, 2]", JsonStyle::Compact).is_none());
+        assert!(render_json(LINE, JsonStyle::Raw).is_none());
+    }
+}
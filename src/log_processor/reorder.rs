@@ -0,0 +1,279 @@
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::kubernetes::log::LogEntry;
+use crate::log_processor::stage::Stage;
+
+/// Maximum number of held entries, the earliest are emitted early beyond it
+const MAX_HELD: usize = 10_000;
+
+/// Latest timestamp of a container stream and when it last sent a line
+struct StreamClock {
+    latest: DateTime<Utc>,
+    last_seen: Instant,
+}
+
+/// Reorder stage emitting follow mode lines in timestamp order
+///
+/// Lines are held for up to the latency budget. A line is emitted as soon as
+/// every container that logged within the budget has reached its timestamp
+/// (the watermark), so output is only delayed while a slower container may
+/// still send earlier lines. Known containers that haven't logged yet hold
+/// every line until they report. Containers that stay silent for longer than
+/// the budget are left out of the watermark so they don't stall the output.
+pub struct ReorderStage {
+    /// How long a line may be held
+    latency: Duration,
+    /// Held entries by timestamp and arrival sequence, with their arrival time
+    held: BTreeMap<(DateTime<Utc>, u64), (Instant, LogEntry)>,
+    /// Clock of each container stream, by pod and container
+    streams: HashMap<(Arc<str>, Arc<str>), StreamClock>,
+    /// Sequence number keeping entries with equal timestamps in arrival order
+    sequence: u64,
+}
+
+impl ReorderStage {
+    /// Create a new reorder stage with the given latency budget
+    pub fn new(latency: Duration) -> Self {
+        Self {
+            latency,
+            held: BTreeMap::new(),
+            streams: HashMap::new(),
+            sequence: 0,
+        }
+    }
+
+    /// Register the containers that are expected to log, by pod and container
+    ///
+    /// Until a registered container sends its first line, lines of the others
+    /// are held for the whole budget instead of being ordered without it.
+    pub fn with_containers<I>(mut self, containers: I) -> Self
+    where
+        I: IntoIterator<Item = (Arc<str>, Arc<str>)>,
+    {
+        let now = Instant::now();
+        for key in containers {
+            self.streams.insert(
+                key,
+                StreamClock {
+                    latest: DateTime::<Utc>::MIN_UTC,
+                    last_seen: now,
+                },
+            );
+        }
+        self
+    }
+
+    /// Process a log entry that arrived at the given time
+    pub fn process_at(&mut self, entry: LogEntry, now: Instant) -> Vec<LogEntry> {
+        let key = (entry.pod_name.clone(), entry.container_name.clone());
+
+        // Lines without a timestamp follow the previous line of their container,
+        // and can't be ordered at all before their container sent one
+        let timestamp = match (entry.timestamp, self.streams.get(&key)) {
+            (Some(timestamp), _) => timestamp,
+            (None, Some(clock)) => clock.latest,
+            (None, None) => return vec![entry],
+        };
+
+        let clock = self.streams.entry(key).or_insert(StreamClock {
+            latest: timestamp,
+            last_seen: now,
+        });
+        clock.latest = clock.latest.max(timestamp);
+        clock.last_seen = now;
+
+        self.sequence += 1;
+        self.held.insert((timestamp, self.sequence), (now, entry));
+
+        let mut output = self.tick_at(now);
+        while self.held.len() > MAX_HELD {
+            if let Some((_, (_, entry))) = self.held.pop_first() {
+                output.push(entry);
+            }
+        }
+        output
+    }
+
+    /// Emit entries that reached the watermark or were held for the whole budget
+    pub fn tick_at(&mut self, now: Instant) -> Vec<LogEntry> {
+        let watermark = self.watermark(now);
+        let latency = self.latency;
+        self.drain(|timestamp, arrived| {
+            watermark.is_none_or(|watermark| timestamp <= watermark)
+                || now.duration_since(arrived) >= latency
+        })
+    }
+
+    /// Get the earliest latest timestamp among containers that logged within the budget
+    ///
+    /// Returns `None` when no container is active, in which case nothing has to wait.
+    fn watermark(&self, now: Instant) -> Option<DateTime<Utc>> {
+        self.streams
+            .values()
+            .filter(|clock| now.duration_since(clock.last_seen) < self.latency)
+            .map(|clock| clock.latest)
+            .min()
+    }
+
+    /// Emit held entries in timestamp order while the condition holds for the earliest one
+    fn drain(&mut self, due: impl Fn(DateTime<Utc>, Instant) -> bool) -> Vec<LogEntry> {
+        let mut output = Vec::new();
+        while let Some(entry) = self.held.first_entry() {
+            let ((timestamp, _), (arrived, _)) = (entry.key(), entry.get());
+            if !due(*timestamp, *arrived) {
+                break;
+            }
+            output.push(entry.remove().1);
+        }
+        output
+    }
+}
+
+impl Stage for ReorderStage {
+    fn process(&mut self, entry: LogEntry) -> Vec<LogEntry> {
+        self.process_at(entry, Instant::now())
+    }
+
+    fn tick(&mut self) -> Vec<LogEntry> {
+        self.tick_at(Instant::now())
+    }
+
+    fn finish(&mut self) -> Vec<LogEntry> {
+        self.drain(|_, _| true)
+    }
+
+    fn description(&self) -> String {
+        format!("reorder({:?})", self.latency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_entry(pod_name: &str, line: &str) -> LogEntry {
+        LogEntry::parse(
+            "default",
+            pod_name.to_string(),
+            "main".to_string(),
+            line.to_string(),
+        )
+    }
+
+    fn lines(entries: Vec<LogEntry>) -> Vec<String> {
+        entries
+            .into_iter()
+            .map(|e| format!("[{}] {}", e.pod_name, e.message()))
+            .collect()
+    }
+
+    fn create_stage(latency: Duration, pods: &[&str]) -> ReorderStage {
+        ReorderStage::new(latency).with_containers(
+            pods.iter()
+                .map(|pod| (Arc::from(*pod), Arc::from("main")))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn test_reorder_by_watermark() {
+        let mut stage = create_stage(Duration::from_secs(1), &["a", "b"]);
+        let now = Instant::now();
+
+        // b hasn't logged yet, so a1 waits for it
+        assert!(stage
+            .process_at(create_test_entry("a", "2024-05-01T10:00:02Z a1"), now)
+            .is_empty());
+
+        // b is behind a, so its line is earlier than anything a may still send
+        assert_eq!(
+            lines(stage.process_at(create_test_entry("b", "2024-05-01T10:00:01Z b1"), now)),
+            ["[b] b1"]
+        );
+
+        // a is ahead of b, so a2 waits for b to catch up
+        assert!(stage
+            .process_at(create_test_entry("a", "2024-05-01T10:00:05Z a2"), now)
+            .is_empty());
+        assert_eq!(
+            lines(stage.process_at(
+                create_test_entry("b", "2024-05-01T10:00:03Z b2"),
+                now + Duration::from_millis(100)
+            )),
+            ["[a] a1", "[b] b2"]
+        );
+        assert_eq!(
+            lines(stage.process_at(
+                create_test_entry("b", "2024-05-01T10:00:06Z b3"),
+                now + Duration::from_millis(200)
+            )),
+            ["[a] a2"]
+        );
+        assert_eq!(lines(stage.finish()), ["[b] b3"]);
+    }
+
+    #[test]
+    fn test_silent_pod_does_not_stall() {
+        let mut stage = ReorderStage::new(Duration::from_millis(500));
+        let now = Instant::now();
+        let later = now + Duration::from_millis(300);
+
+        stage.process_at(create_test_entry("a", "2024-05-01T10:00:01Z a1"), now);
+        stage.process_at(create_test_entry("b", "2024-05-01T10:00:00Z b1"), now);
+        assert!(stage
+            .process_at(create_test_entry("a", "2024-05-01T10:00:02Z a2"), later)
+            .is_empty());
+        // Lines without a timestamp stay behind the previous line of their pod
+        assert!(stage
+            .process_at(create_test_entry("a", "continued"), later)
+            .is_empty());
+
+        assert!(stage.tick_at(now + Duration::from_millis(400)).is_empty());
+        // b has been silent for the whole budget and no longer holds a back
+        assert_eq!(
+            lines(stage.tick_at(now + Duration::from_millis(500))),
+            ["[a] a2", "[a] continued"]
+        );
+        assert!(stage.finish().is_empty());
+    }
+
+    #[test]
+    fn test_known_pod_that_never_logs() {
+        let mut stage = create_stage(Duration::from_millis(500), &["a", "b"]);
+        let now = Instant::now();
+
+        assert!(stage
+            .process_at(create_test_entry("a", "2024-05-01T10:00:01Z a1"), now)
+            .is_empty());
+        // A line of an unknown container without a timestamp passes through alone
+        assert_eq!(
+            lines(stage.process_at(LogEntry::for_test("c", "no timestamp"), now)),
+            ["[c] no timestamp"]
+        );
+
+        // b stays silent for the whole budget and no longer holds a back
+        assert_eq!(
+            lines(stage.tick_at(now + Duration::from_millis(500))),
+            ["[a] a1"]
+        );
+    }
+
+    #[test]
+    fn test_late_line_is_emitted_immediately() {
+        let mut stage = ReorderStage::new(Duration::from_millis(500));
+        let now = Instant::now();
+
+        stage.process_at(create_test_entry("a", "2024-05-01T10:00:05Z a1"), now);
+        // Older than what was already emitted, but nothing can be gained by holding it
+        assert_eq!(
+            lines(stage.process_at(
+                create_test_entry("a", "2024-05-01T10:00:01Z late"),
+                now + Duration::from_millis(10)
+            )),
+            ["[a] late"]
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use crate::kubernetes::log::LogEntry;
+
+/// Trait for processing stages that run between the log aggregator and the formatter
+///
+/// Unlike a `Filter`, a stage may hold entries back, rewrite them or emit
+/// additional entries such as summaries.
+pub trait Stage: Send {
+    /// Process a log entry, returning the entries to pass downstream
+    fn process(&mut self, entry: LogEntry) -> Vec<LogEntry>;
+
+    /// Emit entries that became due since the last call, called periodically
+    fn tick(&mut self) -> Vec<LogEntry> {
+        Vec::new()
+    }
+
+    /// Emit all remaining entries when the stream ends
+    fn finish(&mut self) -> Vec<LogEntry> {
+        Vec::new()
+    }
+
+    /// Get the stage description
+    fn description(&self) -> String;
+}
+
+/// Chain of processing stages, where the output of each stage feeds the next
+#[derive(Default)]
+pub struct Pipeline {
+    stages: Vec<Box<dyn Stage>>,
+}
+
+impl Pipeline {
+    /// Create an empty pipeline
+    pub fn new() -> Self {
+        Self { stages: Vec::new() }
+    }
+
+    /// Append a stage to the pipeline
+    pub fn push(&mut self, stage: Box<dyn Stage>) {
+        self.stages.push(stage);
+    }
+
+    /// Check whether the pipeline has no stages
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Get the pipeline description
+    pub fn description(&self) -> String {
+        let descriptions: Vec<String> = self.stages.iter().map(|s| s.description()).collect();
+        descriptions.join(" -> ")
+    }
+
+    /// Run a log entry through all stages
+    pub fn process(&mut self, entry: LogEntry) -> Vec<LogEntry> {
+        self.run_from(0, vec![entry])
+    }
+
+    /// Collect the entries that became due in any stage
+    pub fn tick(&mut self) -> Vec<LogEntry> {
+        let mut output = Vec::new();
+        for index in 0..self.stages.len() {
+            let due = self.stages[index].tick();
+            output.extend(self.run_from(index + 1, due));
+        }
+        output
+    }
+
+    /// Flush all stages in order at the end of the stream
+    pub fn finish(&mut self) -> Vec<LogEntry> {
+        let mut output = Vec::new();
+        for index in 0..self.stages.len() {
+            let remaining = self.stages[index].finish();
+            output.extend(self.run_from(index + 1, remaining));
+        }
+        output
+    }
+
+    /// Run entries through the stages starting at the given index
+    fn run_from(&mut self, start: usize, mut entries: Vec<LogEntry>) -> Vec<LogEntry> {
+        for stage in &mut self.stages[start..] {
+            if entries.is_empty() {
+                break;
+            }
+            entries = entries
+                .into_iter()
+                .flat_map(|entry| stage.process(entry))
+                .collect();
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stage that duplicates every entry
+    struct Duplicate;
+
+    impl Stage for Duplicate {
+        fn process(&mut self, entry: LogEntry) -> Vec<LogEntry> {
+            vec![entry.clone(), entry]
+        }
+
+        fn description(&self) -> String {
+            "duplicate".to_string()
+        }
+    }
+
+    /// Stage that holds entries until the stream ends
+    #[derive(Default)]
+    struct Hold(Vec<LogEntry>);
+
+    impl Stage for Hold {
+        fn process(&mut self, entry: LogEntry) -> Vec<LogEntry> {
+            self.0.push(entry);
+            Vec::new()
+        }
+
+        fn finish(&mut self) -> Vec<LogEntry> {
+            std::mem::take(&mut self.0)
+        }
+
+        fn description(&self) -> String {
+            "hold".to_string()
+        }
+    }
+
+    #[test]
+    fn test_pipeline_chains_stages() {
+        let mut pipeline = Pipeline::new();
+        assert!(pipeline.is_empty());
+        assert_eq!(
+            pipeline.process(LogEntry::for_test("test-pod", "a")).len(),
+            1
+        );
+
+        pipeline.push(Box::new(Hold::default()));
+        pipeline.push(Box::new(Duplicate));
+        assert_eq!(pipeline.description(), "hold -> duplicate");
+
+        assert!(pipeline
+            .process(LogEntry::for_test("test-pod", "a"))
+            .is_empty());
+        assert!(pipeline
+            .process(LogEntry::for_test("test-pod", "b"))
+            .is_empty());
+        assert!(pipeline.tick().is_empty());
+
+        // Held entries flow through the following stages when flushed
+        let flushed: Vec<String> = pipeline
+            .finish()
+            .into_iter()
+            .map(|e| e.message().to_string())
+            .collect();
+        assert_eq!(flushed, vec!["a", "a", "b", "b"]);
+    }
+}
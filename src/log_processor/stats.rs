@@ -0,0 +1,179 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::kubernetes::log::LogEntry;
+
+/// Level columns shown in the statistics table
+const LEVELS: [&str; 6] = ["TRACE", "DEBUG", "INFO", "WARN", "ERROR", "FATAL"];
+
+/// Line counters for a single pod
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PodStats {
+    /// Total number of lines
+    pub total: u64,
+    /// Number of lines per detected level
+    pub levels: BTreeMap<&'static str, u64>,
+}
+
+impl PodStats {
+    /// Count a line with the given level
+    fn record(&mut self, level: Option<&'static str>) {
+        self.total += 1;
+        if let Some(level) = level {
+            *self.levels.entry(level).or_insert(0) += 1;
+        }
+    }
+
+    /// Get the number of lines with the given level
+    pub fn level_count(&self, level: &str) -> u64 {
+        self.levels.get(level).copied().unwrap_or(0)
+    }
+
+    /// Get the fraction of ERROR and FATAL lines
+    pub fn error_ratio(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        (self.level_count("ERROR") + self.level_count("FATAL")) as f64 / self.total as f64
+    }
+
+    /// Add the counts of another pod
+    fn merge(&mut self, other: &PodStats) {
+        self.total += other.total;
+        for (level, count) in &other.levels {
+            *self.levels.entry(level).or_insert(0) += count;
+        }
+    }
+}
+
+/// Collector of per-pod and per-level line counts
+#[derive(Debug, Default)]
+pub struct StatsCollector {
+    /// Counters per pod
+    pods: BTreeMap<Arc<str>, PodStats>,
+}
+
+impl StatsCollector {
+    /// Create a new statistics collector
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a log entry
+    pub fn record(&mut self, entry: &LogEntry) {
+        let level = entry.level();
+        match self.pods.get_mut(&*entry.pod_name) {
+            Some(stats) => stats.record(level),
+            None => {
+                let mut stats = PodStats::default();
+                stats.record(level);
+                self.pods.insert(entry.pod_name.clone(), stats);
+            }
+        }
+    }
+
+    /// Get the counters of a pod
+    pub fn pod(&self, pod_name: &str) -> Option<&PodStats> {
+        self.pods.get(pod_name)
+    }
+
+    /// Get the counters summed over all pods
+    pub fn total(&self) -> PodStats {
+        let mut total = PodStats::default();
+        for stats in self.pods.values() {
+            total.merge(stats);
+        }
+        total
+    }
+
+    /// Render the statistics table, with rates averaged over the elapsed time
+    pub fn render(&self, elapsed: Duration) -> String {
+        let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+        let width = self
+            .pods
+            .keys()
+            .map(|pod| pod.len())
+            .max()
+            .unwrap_or(0)
+            .max("POD".len());
+
+        let mut output = String::new();
+        let _ = write!(
+            output,
+            "{:<width$}  {:>8}  {:>8}  {:>6}",
+            "POD", "LINES", "AVG/S", "ERR%"
+        );
+        for level in LEVELS {
+            let _ = write!(output, "  {:>7}", level);
+        }
+        output.push('\n');
+
+        let total = self.total();
+        let rows = self.pods.iter().map(|(pod, stats)| (&**pod, stats));
+        for (pod, stats) in rows.chain(std::iter::once(("TOTAL", &total))) {
+            let _ = write!(
+                output,
+                "{:<width$}  {:>8}  {:>8.1}  {:>5.1}%",
+                pod,
+                stats.total,
+                stats.total as f64 / seconds,
+                stats.error_ratio() * 100.0
+            );
+            for level in LEVELS {
+                let _ = write!(output, "  {:>7}", stats.level_count(level));
+            }
+            output.push('\n');
+        }
+
+        let _ = write!(output, "elapsed {:.1}s", elapsed.as_secs_f64());
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut stats = StatsCollector::new();
+        stats.record(&LogEntry::for_test("web-1", "[INFO] started"));
+        stats.record(&LogEntry::for_test("web-1", "[ERROR] failed"));
+        stats.record(&LogEntry::for_test("web-1", "no level"));
+        stats.record(&LogEntry::for_test("web-2", "FATAL: out of memory"));
+
+        let web1 = stats.pod("web-1").unwrap();
+        assert_eq!(web1.total, 3);
+        assert_eq!(web1.level_count("INFO"), 1);
+        assert_eq!(web1.level_count("ERROR"), 1);
+        assert!((web1.error_ratio() - 1.0 / 3.0).abs() < 1e-9);
+
+        let total = stats.total();
+        assert_eq!(total.total, 4);
+        assert_eq!(total.error_ratio(), 0.5);
+    }
+
+    #[test]
+    fn test_render() {
+        let mut stats = StatsCollector::new();
+        for _ in 0..4 {
+            stats.record(&LogEntry::for_test("web-1", "[WARN] slow"));
+        }
+        stats.record(&LogEntry::for_test("web-2", "[ERROR] boom"));
+
+        let table = stats.render(Duration::from_secs(2));
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("POD       LINES     AVG/S    ERR%"));
+        assert_eq!(
+            lines[1],
+            "web-1         4       2.0    0.0%        0        0        0        4        0        0"
+        );
+        assert!(lines[2].starts_with("web-2         1       0.5  100.0%"));
+        assert!(lines[3].starts_with("TOTAL         5       2.5   20.0%"));
+        assert_eq!(lines[4], "elapsed 2.0s");
+    }
+}
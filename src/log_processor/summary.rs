@@ -0,0 +1,387 @@
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+use std::sync::{Arc, OnceLock};
+
+use crate::kubernetes::log::LogEntry;
+
+/// Token that matches any value in a template
+const WILDCARD: &str = "<*>";
+
+/// Minimum fraction of equal tokens for a message to join an existing template
+const DEFAULT_SIMILARITY: f64 = 0.5;
+
+/// Maximum number of pods listed per template in the summary table
+const MAX_LISTED_PODS: usize = 3;
+
+/// Masks for variable tokens, checked in order
+fn masks() -> &'static [(Regex, &'static str)] {
+    static MASKS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    MASKS.get_or_init(|| {
+        [
+            (r"^\d{1,3}(\.\d{1,3}){3}(:\d+)?$", "<IP>"),
+            (
+                r"^(?i)[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$",
+                "<UUID>",
+            ),
+            (r"^(?i)(0x)?[0-9a-f]*\d[0-9a-f]*$", "<NUM>"),
+            (r"^[-+]?\d+(\.\d+)?([a-zA-Zµ%]{1,3})?$", "<NUM>"),
+            (r"^(?i)[0-9a-f]{12,}$", "<ID>"),
+            (r"^[\w.-]*\d[\w.-]*$", "<ID>"),
+        ]
+        .into_iter()
+        .map(|(pattern, mask)| (Regex::new(pattern).expect("valid mask regex"), mask))
+        .collect()
+    })
+}
+
+/// Replace a variable token with its mask, keeping surrounding punctuation
+fn mask_token(token: &str) -> String {
+    // Mask only the value of key=value tokens
+    if let Some((key, value)) = token.split_once('=') {
+        if !key.is_empty() && !value.is_empty() {
+            return format!("{}={}", key, mask_token(value));
+        }
+    }
+
+    let start = token.find(|c: char| c.is_alphanumeric()).unwrap_or(0);
+    let end = token
+        .rfind(|c: char| c.is_alphanumeric())
+        .map_or(token.len(), |i| {
+            i + token[i..].chars().next().map_or(1, char::len_utf8)
+        });
+    if start >= end {
+        return token.to_string();
+    }
+
+    let core = &token[start..end];
+    match masks().iter().find(|(regex, _)| regex.is_match(core)) {
+        Some((_, mask)) => format!("{}{}{}", &token[..start], mask, &token[end..]),
+        None => token.to_string(),
+    }
+}
+
+/// Split a message into masked tokens
+fn tokenize(message: &str) -> Vec<String> {
+    message.split_whitespace().map(mask_token).collect()
+}
+
+/// A group of messages sharing the same template
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    /// Template tokens, with varying positions replaced by `<*>`
+    tokens: Vec<String>,
+    /// Number of messages in the cluster
+    pub count: usize,
+    /// Timestamp of the earliest message
+    pub first_seen: Option<DateTime<Utc>>,
+    /// Timestamp of the latest message
+    pub last_seen: Option<DateTime<Utc>>,
+    /// Message counts per detected level
+    pub levels: BTreeMap<&'static str, usize>,
+    /// Pods that emitted messages of this cluster
+    pub pods: BTreeSet<Arc<str>>,
+}
+
+impl Cluster {
+    /// Get the template string
+    pub fn template(&self) -> String {
+        self.tokens.join(" ")
+    }
+
+    /// Get the most frequent level of the cluster
+    pub fn level(&self) -> Option<&'static str> {
+        self.levels
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(level, _)| *level)
+    }
+
+    /// Fraction of positions where the tokens equal the template
+    fn similarity(&self, tokens: &[String]) -> f64 {
+        if tokens.is_empty() {
+            return 1.0;
+        }
+        let equal = self
+            .tokens
+            .iter()
+            .zip(tokens)
+            .filter(|(a, b)| a == b)
+            .count();
+        equal as f64 / tokens.len() as f64
+    }
+
+    /// Add a message to the cluster, generalizing positions that differ
+    fn add(&mut self, tokens: &[String], entry: &LogEntry) {
+        for (template, token) in self.tokens.iter_mut().zip(tokens) {
+            if template != token {
+                *template = WILDCARD.to_string();
+            }
+        }
+
+        self.count += 1;
+        if let Some(ts) = entry.timestamp {
+            self.first_seen = Some(self.first_seen.map_or(ts, |first| first.min(ts)));
+            self.last_seen = Some(self.last_seen.map_or(ts, |last| last.max(ts)));
+        }
+        if let Some(level) = entry.level() {
+            *self.levels.entry(level).or_insert(0) += 1;
+        }
+        if !self.pods.contains(&*entry.pod_name) {
+            self.pods.insert(entry.pod_name.clone());
+        }
+    }
+}
+
+/// Drain-style log template miner
+///
+/// Messages are tokenized on whitespace and variable tokens such as
+/// numbers, IDs and IP addresses are masked. Messages with the same number
+/// of tokens and the same first token are compared against the existing
+/// templates of their group and join the most similar one if enough tokens
+/// match, otherwise they start a new template.
+pub struct TemplateMiner {
+    /// Minimum similarity to join an existing cluster
+    similarity: f64,
+    /// All clusters in creation order
+    clusters: Vec<Cluster>,
+    /// Cluster indices grouped by token count and first token
+    groups: HashMap<(usize, String), Vec<usize>>,
+}
+
+impl TemplateMiner {
+    /// Create a new template miner with the default similarity threshold
+    pub fn new() -> Self {
+        Self::with_similarity(DEFAULT_SIMILARITY)
+    }
+
+    /// Create a new template miner with a custom similarity threshold
+    pub fn with_similarity(similarity: f64) -> Self {
+        Self {
+            similarity,
+            clusters: Vec::new(),
+            groups: HashMap::new(),
+        }
+    }
+
+    /// Add a log entry to its template cluster
+    pub fn add(&mut self, entry: &LogEntry) {
+        let tokens = tokenize(entry.message());
+        let key = (tokens.len(), tokens.first().cloned().unwrap_or_default());
+        let candidates = self.groups.entry(key).or_default();
+
+        let best = candidates
+            .iter()
+            .map(|&index| (index, self.clusters[index].similarity(&tokens)))
+            .filter(|&(_, similarity)| similarity >= self.similarity)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index);
+
+        let index = match best {
+            Some(index) => index,
+            None => {
+                self.clusters.push(Cluster {
+                    tokens: tokens.clone(),
+                    count: 0,
+                    first_seen: None,
+                    last_seen: None,
+                    levels: BTreeMap::new(),
+                    pods: BTreeSet::new(),
+                });
+                candidates.push(self.clusters.len() - 1);
+                self.clusters.len() - 1
+            }
+        };
+
+        self.clusters[index].add(&tokens, entry);
+    }
+
+    /// Get the total number of messages added
+    pub fn total(&self) -> usize {
+        self.clusters.iter().map(|c| c.count).sum()
+    }
+
+    /// Get the clusters sorted by descending count
+    pub fn clusters(&self) -> Vec<&Cluster> {
+        let mut clusters: Vec<&Cluster> = self.clusters.iter().collect();
+        clusters.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tokens.cmp(&b.tokens)));
+        clusters
+    }
+
+    /// Render the summary table
+    pub fn render(&self) -> String {
+        // Times alone are ambiguous when the lines span more than one day
+        let first = self.clusters.iter().filter_map(|c| c.first_seen).min();
+        let last = self.clusters.iter().filter_map(|c| c.last_seen).max();
+        let (time_format, width) = match (first, last) {
+            (Some(first), Some(last)) if first.date_naive() != last.date_naive() => {
+                ("%Y-%m-%dT%H:%M:%SZ", 20)
+            }
+            _ => ("%H:%M:%S", 8),
+        };
+
+        let mut output = String::new();
+        let _ = writeln!(
+            output,
+            "{:>7}  {:<5}  {:<width$}  {:<width$}  {:<24}  TEMPLATE",
+            "COUNT", "LEVEL", "FIRST", "LAST", "PODS"
+        );
+
+        for cluster in self.clusters() {
+            let time = |ts: Option<DateTime<Utc>>| {
+                ts.map_or("-".to_string(), |ts| ts.format(time_format).to_string())
+            };
+
+            let mut pods: Vec<&str> = cluster
+                .pods
+                .iter()
+                .take(MAX_LISTED_PODS)
+                .map(|pod| &**pod)
+                .collect();
+            let more = cluster.pods.len().saturating_sub(MAX_LISTED_PODS);
+            let more_label = format!("+{}", more);
+            if more > 0 {
+                pods.push(&more_label);
+            }
+
+            let _ = writeln!(
+                output,
+                "{:>7}  {:<5}  {:<width$}  {:<width$}  {:<24}  {}",
+                cluster.count,
+                cluster.level().unwrap_or("-"),
+                time(cluster.first_seen),
+                time(cluster.last_seen),
+                pods.join(","),
+                cluster.template()
+            );
+        }
+
+        let _ = writeln!(
+            output,
+            "{} lines in {} templates",
+            self.total(),
+            self.clusters.len()
+        );
+        output
+    }
+}
+
+impl Default for TemplateMiner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_entry(pod_name: &str, timestamp: &str, message: &str) -> LogEntry {
+        LogEntry::parse(
+            "default",
+            pod_name.to_string(),
+            "main".to_string(),
+            format!("{} {}", timestamp, message),
+        )
+    }
+
+    #[test]
+    fn test_mask_tokens() {
+        assert_eq!(
+            tokenize("GET /api/users/42 from 10.0.0.12:8080 took 35ms"),
+            vec!["GET", "/api/users/42", "from", "<IP>", "took", "<NUM>"]
+        );
+        assert_eq!(
+            tokenize("request 3f2b8c1e-1d2a-4b7e-9c3d-0a1b2c3d4e5f failed (code=500)"),
+            vec!["request", "<UUID>", "failed", "(code=<NUM>)"]
+        );
+        assert_eq!(
+            tokenize("pod web-7d9f8-abc12 ready"),
+            vec!["pod", "<ID>", "ready"]
+        );
+    }
+
+    #[test]
+    fn test_clusters() {
+        let mut miner = TemplateMiner::new();
+        miner.add(&create_test_entry(
+            "web-1",
+            "2024-05-01T10:00:05Z",
+            "ERROR connection to db-1 refused",
+        ));
+        miner.add(&create_test_entry(
+            "web-2",
+            "2024-05-01T10:00:01Z",
+            "ERROR connection to cache refused",
+        ));
+        miner.add(&create_test_entry(
+            "web-1",
+            "2024-05-01T10:00:09Z",
+            "ERROR connection to db-2 refused",
+        ));
+        miner.add(&create_test_entry(
+            "web-1",
+            "2024-05-01T10:00:02Z",
+            "INFO served 120 requests",
+        ));
+
+        let clusters = miner.clusters();
+        assert_eq!(clusters.len(), 2);
+
+        let errors = clusters[0];
+        assert_eq!(errors.count, 3);
+        assert_eq!(errors.template(), "ERROR connection to <*> refused");
+        assert_eq!(errors.level(), Some("ERROR"));
+        assert_eq!(
+            errors
+                .first_seen
+                .map(|ts| ts.format("%H:%M:%S").to_string()),
+            Some("10:00:01".to_string())
+        );
+        assert_eq!(
+            errors.last_seen.map(|ts| ts.format("%H:%M:%S").to_string()),
+            Some("10:00:09".to_string())
+        );
+        assert_eq!(errors.pods.len(), 2);
+
+        assert_eq!(clusters[1].template(), "INFO served <NUM> requests");
+        assert_eq!(miner.total(), 4);
+    }
+
+    #[test]
+    fn test_render() {
+        let mut miner = TemplateMiner::new();
+        for pod in ["web-1", "web-2", "web-3", "web-4", "web-5"] {
+            miner.add(&create_test_entry(
+                pod,
+                "2024-05-01T10:00:00Z",
+                "WARN slow query 12ms",
+            ));
+        }
+
+        let output = miner.render();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("  COUNT  LEVEL"));
+        assert!(lines[1].contains("web-1,web-2,web-3,+2"));
+        assert!(lines[1].ends_with("WARN slow query <NUM>"));
+        assert_eq!(lines[2], "5 lines in 1 templates");
+    }
+
+    #[test]
+    fn test_render_dates_across_midnight() {
+        let mut miner = TemplateMiner::new();
+        for timestamp in ["2024-05-01T23:59:58Z", "2024-05-02T00:00:03Z"] {
+            miner.add(&create_test_entry(
+                "web-1",
+                timestamp,
+                "WARN slow query 12ms",
+            ));
+        }
+
+        let output = miner.render();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("  COUNT  LEVEL  FIRST                 LAST "));
+        assert!(lines[1].contains("2024-05-01T23:59:58Z  2024-05-02T00:00:03Z  web-1"));
+    }
+}
@@ -0,0 +1,383 @@
+use anyhow::{anyhow, Result};
+use std::borrow::Cow;
+
+use crate::kubernetes::log::LogEntry;
+use crate::log_processor::timestamp::validate_strftime;
+
+/// Value a placeholder refers to
+#[derive(Debug, Clone, PartialEq)]
+pub enum Key {
+    /// Kubernetes timestamp, optionally with a strftime format
+    Timestamp(Option<String>),
+    Namespace,
+    Pod,
+    ShortPod,
+    Container,
+    Node,
+    Level,
+    Message,
+    /// Field parsed from a JSON or logfmt message
+    Field(String),
+}
+
+/// Alignment of a padded value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// A placeholder with its padding and truncation modifiers
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placeholder {
+    pub key: Key,
+    /// Alignment within the width
+    pub align: Align,
+    /// Minimum width, padded with spaces
+    pub width: Option<usize>,
+    /// Maximum width, longer values are truncated
+    pub precision: Option<usize>,
+}
+
+/// Part of a template
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// A rendered part of a template
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rendered<'a> {
+    /// The placeholder that was rendered, or `None` for literal text
+    pub key: Option<&'a Key>,
+    /// Rendered text including padding
+    pub text: Cow<'a, str>,
+    /// Number of padding bytes before the value
+    pub offset: usize,
+    /// Length in bytes of the value after truncation
+    pub len: usize,
+}
+
+/// Output template such as `{timestamp:%H:%M:%S} {pod:<20} {level:5} {message}`
+///
+/// Placeholders are written in braces, with optional modifiers after a
+/// colon: `<`, `>` or `^` for alignment, a minimum width and `.N` for a
+/// maximum width. For the timestamp, modifiers containing `%` are a
+/// strftime format instead. `{{` and `}}` are literal braces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    /// Parse a template string
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' => {
+                    if chars.as_str().starts_with('{') {
+                        chars.next();
+                        literal.push('{');
+                        continue;
+                    }
+
+                    let rest = chars.as_str();
+                    let end = rest
+                        .find('}')
+                        .ok_or_else(|| anyhow!("Unclosed placeholder in template: {}", template))?;
+                    let placeholder = Self::parse_placeholder(&rest[..end])?;
+                    chars = rest[end + 1..].chars();
+
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder(placeholder));
+                }
+                '}' => {
+                    if chars.as_str().starts_with('}') {
+                        chars.next();
+                    }
+                    literal.push('}');
+                }
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Parse the content of a placeholder, e.g. `pod:<20.20`
+    fn parse_placeholder(content: &str) -> Result<Placeholder> {
+        let (name, spec) = match content.split_once(':') {
+            Some((name, spec)) => (name.trim(), Some(spec)),
+            None => (content.trim(), None),
+        };
+
+        let mut key = match name {
+            "timestamp" | "ts" => Key::Timestamp(None),
+            "namespace" | "ns" => Key::Namespace,
+            "pod" => Key::Pod,
+            "short_pod" => Key::ShortPod,
+            "container" => Key::Container,
+            "node" => Key::Node,
+            "level" => Key::Level,
+            "message" | "msg" => Key::Message,
+            _ => match name.strip_prefix("field.") {
+                Some(field) if !field.is_empty() => Key::Field(field.to_string()),
+                _ => return Err(anyhow!("Unknown template placeholder: {{{}}}", name)),
+            },
+        };
+
+        let mut placeholder = Placeholder {
+            key: Key::Message,
+            align: Align::Left,
+            width: None,
+            precision: None,
+        };
+
+        if let Some(spec) = spec {
+            if matches!(key, Key::Timestamp(_)) && spec.contains('%') {
+                validate_strftime(spec)?;
+                key = Key::Timestamp(Some(spec.to_string()));
+            } else {
+                Self::parse_spec(spec, &mut placeholder).map_err(|_| {
+                    anyhow!("Invalid modifiers in template placeholder: {{{}}}", content)
+                })?;
+            }
+        }
+
+        placeholder.key = key;
+        Ok(placeholder)
+    }
+
+    /// Parse padding and truncation modifiers such as `<20.20`
+    fn parse_spec(spec: &str, placeholder: &mut Placeholder) -> Result<()> {
+        let mut spec = spec.trim();
+        if let Some(c) = spec.chars().next() {
+            let align = match c {
+                '<' => Some(Align::Left),
+                '>' => Some(Align::Right),
+                '^' => Some(Align::Center),
+                _ => None,
+            };
+            if let Some(align) = align {
+                placeholder.align = align;
+                spec = &spec[1..];
+            }
+        }
+
+        let (width, precision) = match spec.split_once('.') {
+            Some((width, precision)) => (width, Some(precision)),
+            None => (spec, None),
+        };
+        if !width.is_empty() {
+            placeholder.width = Some(width.parse()?);
+        }
+        if let Some(precision) = precision {
+            placeholder.precision = Some(precision.parse()?);
+        }
+        Ok(())
+    }
+
+    /// Get the segments of the template
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Get the raw value of a placeholder key
+    fn value<'a>(
+        key: &Key,
+        entry: &'a LogEntry,
+        node: Option<&'a str>,
+        short_pod: Option<&'a str>,
+    ) -> Cow<'a, str> {
+        match key {
+            Key::Timestamp(format) => match (entry.timestamp, format) {
+                (Some(ts), Some(format)) => Cow::Owned(ts.format(format).to_string()),
+                (Some(ts), None) => Cow::Owned(ts.to_rfc3339()),
+                (None, _) => Cow::Borrowed(""),
+            },
+            Key::Namespace => Cow::Borrowed(&entry.namespace),
+            Key::Pod => Cow::Borrowed(&entry.pod_name),
+            Key::ShortPod => Cow::Borrowed(short_pod.unwrap_or(&entry.pod_name)),
+            Key::Container => Cow::Borrowed(&entry.container_name),
+            Key::Node => Cow::Borrowed(node.unwrap_or_default()),
+            Key::Level => Cow::Borrowed(entry.level().unwrap_or_default()),
+            Key::Message => Cow::Borrowed(entry.message()),
+            Key::Field(name) => Cow::Borrowed(entry.field(name).unwrap_or_default()),
+        }
+    }
+
+    /// Render a placeholder with padding and truncation
+    fn render_placeholder<'a>(
+        placeholder: &'a Placeholder,
+        entry: &LogEntry,
+        node: Option<&str>,
+        short_pod: Option<&str>,
+    ) -> Rendered<'a> {
+        let value = Self::value(&placeholder.key, entry, node, short_pod);
+
+        let value = match placeholder.precision {
+            Some(precision) => match value.char_indices().nth(precision) {
+                Some((end, _)) => &value[..end],
+                None => &value[..],
+            },
+            None => &value[..],
+        };
+
+        let padding = placeholder
+            .width
+            .map_or(0, |width| width.saturating_sub(value.chars().count()));
+        let before = match placeholder.align {
+            Align::Left => 0,
+            Align::Right => padding,
+            Align::Center => padding / 2,
+        };
+
+        let mut text = String::with_capacity(value.len() + padding);
+        text.extend(std::iter::repeat_n(' ', before));
+        text.push_str(value);
+        text.extend(std::iter::repeat_n(' ', padding - before));
+
+        Rendered {
+            key: Some(&placeholder.key),
+            text: Cow::Owned(text),
+            offset: before,
+            len: value.len(),
+        }
+    }
+
+    /// Render the template into parts, so each part can be colored separately
+    ///
+    /// The node and short name of the pod are looked up by the caller; the
+    /// short name falls back to the full pod name.
+    pub fn render_parts<'a>(
+        &'a self,
+        entry: &LogEntry,
+        node: Option<&str>,
+        short_pod: Option<&str>,
+    ) -> Vec<Rendered<'a>> {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(text) => Rendered {
+                    key: None,
+                    text: Cow::Borrowed(text),
+                    offset: 0,
+                    len: text.len(),
+                },
+                Segment::Placeholder(placeholder) => {
+                    Self::render_placeholder(placeholder, entry, node, short_pod)
+                }
+            })
+            .collect()
+    }
+
+    /// Render the template for an entry
+    pub fn render(&self, entry: &LogEntry, node: Option<&str>, short_pod: Option<&str>) -> String {
+        self.render_parts(entry, node, short_pod)
+            .into_iter()
+            .map(|part| part.text)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_entry(message: &str) -> LogEntry {
+        let mut entry = LogEntry::parse(
+            "default",
+            "checkout-7d9f8c6b5-x2k4q".to_string(),
+            "api".to_string(),
+            format!("2024-05-01T10:00:05.123Z {}", message),
+        );
+        entry.namespace = "shop".into();
+        entry
+    }
+
+    #[test]
+    fn test_parse() {
+        let template = Template::parse("{{{pod:>10.4}}} {field.user}").unwrap();
+        assert_eq!(
+            template.segments(),
+            &[
+                Segment::Literal("{".to_string()),
+                Segment::Placeholder(Placeholder {
+                    key: Key::Pod,
+                    align: Align::Right,
+                    width: Some(10),
+                    precision: Some(4),
+                }),
+                Segment::Literal("} ".to_string()),
+                Segment::Placeholder(Placeholder {
+                    key: Key::Field("user".to_string()),
+                    align: Align::Left,
+                    width: None,
+                    precision: None,
+                }),
+            ]
+        );
+
+        assert!(Template::parse("{pod").is_err());
+        assert!(Template::parse("{hostname}").is_err());
+        assert!(Template::parse("{pod:<x}").is_err());
+        assert!(Template::parse("{field.}").is_err());
+        assert!(Template::parse("{ts:%Q}").is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let entry = create_test_entry(r#"level=warn msg="slow query" user=42"#);
+
+        let template =
+            Template::parse("{ts:%H:%M:%S%.3f} {ns}/{short_pod}/{container}@{node} {level:<5}|")
+                .unwrap();
+        assert_eq!(
+            template.render(&entry, Some("node-a"), Some("x2k4q")),
+            "10:00:05.123 shop/x2k4q/api@node-a WARN |"
+        );
+        // Without a short name the full pod name is used
+        assert_eq!(
+            template.render(&entry, None, None),
+            "10:00:05.123 shop/checkout-7d9f8c6b5-x2k4q/api@ WARN |"
+        );
+
+        let template =
+            Template::parse("[{pod:^12.10}] {field.user:>4} {field.missing}{msg:.9}").unwrap();
+        assert_eq!(
+            template.render(&entry, None, None),
+            "[ checkout-7 ]   42 level=war"
+        );
+
+        let template = Template::parse("{timestamp}").unwrap();
+        assert_eq!(
+            template.render(&entry, None, None),
+            "2024-05-01T10:00:05.123+00:00"
+        );
+    }
+
+    #[test]
+    fn test_render_parts() {
+        let entry = create_test_entry("hello");
+        let template = Template::parse("{pod:.3} {message:>8}").unwrap();
+        let parts = template.render_parts(&entry, None, None);
+
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0].key, Some(&Key::Pod));
+        assert_eq!(parts[0].text, "che");
+        assert_eq!(parts[1].key, None);
+        assert_eq!(parts[2].text, "   hello");
+        assert_eq!((parts[2].offset, parts[2].len), (3, 5));
+    }
+}
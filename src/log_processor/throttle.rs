@@ -0,0 +1,406 @@
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::kubernetes::log::{LogEntry, Source};
+use crate::log_processor::stage::Stage;
+use crate::utils::hash::fnv1a;
+
+/// How often a note about suppressed lines is emitted
+const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Pod name used for notes emitted by the throttle stage
+pub const NOTE_SOURCE: &str = "klogrs";
+
+/// Token bucket allowing a sustained rate with bursts of up to one second
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    /// Tokens added per second
+    rate: f64,
+    /// Maximum number of tokens
+    capacity: f64,
+    /// Currently available tokens
+    tokens: f64,
+    /// Last time tokens were added
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Create a full bucket for the given rate in lines per second
+    pub fn new(rate: f64, now: Instant) -> Self {
+        let capacity = rate.max(1.0);
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+            last_refill: now,
+        }
+    }
+
+    /// Check whether a token is available, without taking it
+    pub fn has_token(&mut self, now: Instant) -> bool {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last_refill = now;
+        self.tokens >= 1.0
+    }
+
+    /// Take a token if one is available
+    pub fn try_take(&mut self, now: Instant) -> bool {
+        let available = self.has_token(now);
+        if available {
+            self.tokens -= 1.0;
+        }
+        available
+    }
+}
+
+/// Deterministic sampling strategy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sampling {
+    /// Keep every Nth line of each pod
+    EveryNth(u64),
+    /// Keep lines whose message hash falls into one of N buckets, so the same
+    /// message is always either kept or dropped
+    Hash(u64),
+}
+
+impl FromStr for Sampling {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (hash, rate) = match s.split_once(':') {
+            Some((kind, rate)) if kind.eq_ignore_ascii_case("hash") => (true, rate),
+            Some(_) => return Err(anyhow!("Invalid sampling: {} (expected N or hash:N)", s)),
+            None => (false, s),
+        };
+
+        let rate: u64 = rate
+            .trim()
+            .parse()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| anyhow!("Invalid sampling rate: {}", s))?;
+
+        Ok(if hash {
+            Sampling::Hash(rate)
+        } else {
+            Sampling::EveryNth(rate)
+        })
+    }
+}
+
+impl fmt::Display for Sampling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sampling::EveryNth(n) => write!(f, "1/{}", n),
+            Sampling::Hash(n) => write!(f, "hash 1/{}", n),
+        }
+    }
+}
+
+/// Counts of suppressed lines since the last report
+#[derive(Debug, Default)]
+struct Suppressed {
+    sampled: u64,
+    rate_limited: u64,
+    per_pod: BTreeMap<Arc<str>, u64>,
+}
+
+impl Suppressed {
+    fn total(&self) -> u64 {
+        self.sampled + self.rate_limited
+    }
+}
+
+/// Sampling and rate limiting stage for noisy streams
+///
+/// Lines are first sampled, then checked against the per-pod and global
+/// token buckets. Dropped lines are counted and summarized in a periodic
+/// note so it is visible that output was reduced.
+pub struct ThrottleStage {
+    /// Sampling strategy
+    sampling: Option<Sampling>,
+    /// Per-pod rate limit in lines per second
+    pod_rate: Option<f64>,
+    /// Token buckets per pod
+    pod_buckets: HashMap<Arc<str>, TokenBucket>,
+    /// Global token bucket
+    global_bucket: Option<TokenBucket>,
+    /// Line counters per pod for 1-in-N sampling
+    counters: HashMap<Arc<str>, u64>,
+    /// Suppressed lines since the last report
+    suppressed: Suppressed,
+    /// When the last report was emitted
+    last_report: Instant,
+}
+
+impl ThrottleStage {
+    /// Create a new throttle stage
+    pub fn new(
+        sampling: Option<Sampling>,
+        pod_rate: Option<f64>,
+        global_rate: Option<f64>,
+    ) -> Self {
+        let now = Instant::now();
+        Self {
+            sampling,
+            pod_rate,
+            pod_buckets: HashMap::new(),
+            global_bucket: global_rate.map(|rate| TokenBucket::new(rate, now)),
+            counters: HashMap::new(),
+            suppressed: Suppressed::default(),
+            last_report: now,
+        }
+    }
+
+    /// Process a log entry that arrived at the given time
+    pub fn process_at(&mut self, entry: LogEntry, now: Instant) -> Vec<LogEntry> {
+        if !self.sample(&entry) {
+            self.suppressed.sampled += 1;
+            *self.suppressed.per_pod.entry(entry.pod_name).or_insert(0) += 1;
+            return self.tick_at(now);
+        }
+
+        if !self.take_tokens(&entry.pod_name, now) {
+            self.suppressed.rate_limited += 1;
+            *self.suppressed.per_pod.entry(entry.pod_name).or_insert(0) += 1;
+            return self.tick_at(now);
+        }
+
+        let mut output = self.tick_at(now);
+        output.push(entry);
+        output
+    }
+
+    /// Emit a note about suppressed lines if the report interval has passed
+    pub fn tick_at(&mut self, now: Instant) -> Vec<LogEntry> {
+        if now.saturating_duration_since(self.last_report) < REPORT_INTERVAL {
+            return Vec::new();
+        }
+        self.report(now).into_iter().collect()
+    }
+
+    /// Check whether the entry is kept by sampling
+    fn sample(&mut self, entry: &LogEntry) -> bool {
+        match self.sampling {
+            None => true,
+            Some(Sampling::EveryNth(n)) => {
+                let counter = self.counters.entry(entry.pod_name.clone()).or_insert(0);
+                let keep = counter.is_multiple_of(n);
+                *counter += 1;
+                keep
+            }
+            Some(Sampling::Hash(n)) => fnv1a(entry.message()).is_multiple_of(n),
+        }
+    }
+
+    /// Take a token from the pod bucket and from the global bucket
+    ///
+    /// Tokens are only taken when both buckets have one, so a line dropped by
+    /// the global limit doesn't count against its pod's rate.
+    fn take_tokens(&mut self, pod_name: &Arc<str>, now: Instant) -> bool {
+        let pod_bucket = self.pod_rate.map(|rate| {
+            self.pod_buckets
+                .entry(pod_name.clone())
+                .or_insert_with(|| TokenBucket::new(rate, now))
+        });
+        let mut buckets: Vec<&mut TokenBucket> = pod_bucket
+            .into_iter()
+            .chain(self.global_bucket.as_mut())
+            .collect();
+        if !buckets.iter_mut().all(|bucket| bucket.has_token(now)) {
+            return false;
+        }
+        for bucket in buckets {
+            bucket.try_take(now);
+        }
+        true
+    }
+
+    /// Build the note for lines suppressed since the last report and reset the counts
+    fn report(&mut self, now: Instant) -> Option<LogEntry> {
+        let elapsed = now.saturating_duration_since(self.last_report);
+        self.last_report = now;
+
+        let suppressed = std::mem::take(&mut self.suppressed);
+        if suppressed.total() == 0 {
+            return None;
+        }
+
+        let pods: Vec<String> = suppressed
+            .per_pod
+            .iter()
+            .map(|(pod, count)| format!("{}: {}", pod, count))
+            .collect();
+        let message = format!(
+            "suppressed {} lines in the last {:.0}s (sampled: {}, rate limited: {}; {})",
+            suppressed.total(),
+            elapsed.as_secs_f64(),
+            suppressed.sampled,
+            suppressed.rate_limited,
+            pods.join(", ")
+        );
+
+        Some(LogEntry::new("", NOTE_SOURCE, "", message).with_source(Source::Note))
+    }
+}
+
+impl Stage for ThrottleStage {
+    fn process(&mut self, entry: LogEntry) -> Vec<LogEntry> {
+        self.process_at(entry, Instant::now())
+    }
+
+    fn tick(&mut self) -> Vec<LogEntry> {
+        self.tick_at(Instant::now())
+    }
+
+    fn finish(&mut self) -> Vec<LogEntry> {
+        self.report(Instant::now()).into_iter().collect()
+    }
+
+    fn description(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(sampling) = self.sampling {
+            parts.push(format!("sample {}", sampling));
+        }
+        if let Some(rate) = self.pod_rate {
+            parts.push(format!("{}/s per pod", rate));
+        }
+        if let Some(bucket) = &self.global_bucket {
+            parts.push(format!("{}/s global", bucket.rate));
+        }
+        format!("throttle({})", parts.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, now);
+
+        assert!(bucket.try_take(now));
+        assert!(bucket.try_take(now));
+        assert!(!bucket.try_take(now));
+        assert!(bucket.try_take(now + Duration::from_millis(500)));
+        assert!(!bucket.try_take(now + Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn test_parse_sampling() {
+        assert_eq!("10".parse::<Sampling>().unwrap(), Sampling::EveryNth(10));
+        assert_eq!("hash:4".parse::<Sampling>().unwrap(), Sampling::Hash(4));
+        assert!("0".parse::<Sampling>().is_err());
+        assert!("every:3".parse::<Sampling>().is_err());
+    }
+
+    #[test]
+    fn test_every_nth_sampling_is_per_pod() {
+        let mut stage = ThrottleStage::new(Some(Sampling::EveryNth(3)), None, None);
+        let now = Instant::now();
+
+        let mut kept = Vec::new();
+        for i in 0..6 {
+            for pod in ["pod-a", "pod-b"] {
+                kept.extend(stage.process_at(LogEntry::for_test(pod, &format!("line {}", i)), now));
+            }
+        }
+
+        let lines: Vec<String> = kept
+            .into_iter()
+            .map(|e| format!("{} {}", e.pod_name, e.message()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "pod-a line 0",
+                "pod-b line 0",
+                "pod-a line 3",
+                "pod-b line 3"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hash_sampling_is_consistent() {
+        let mut stage = ThrottleStage::new(Some(Sampling::Hash(2)), None, None);
+        let now = Instant::now();
+
+        for i in 0..20 {
+            let message = format!("message {}", i);
+            let first = stage
+                .process_at(LogEntry::for_test("pod-a", &message), now)
+                .len();
+            let second = stage
+                .process_at(LogEntry::for_test("pod-b", &message), now)
+                .len();
+            assert_eq!(first, second);
+        }
+    }
+
+    #[test]
+    fn test_global_limit_keeps_pod_tokens() {
+        let mut stage = ThrottleStage::new(None, Some(1.0), Some(2.0));
+        let now = Instant::now();
+
+        for pod in ["pod-a", "pod-b"] {
+            assert_eq!(
+                stage.process_at(LogEntry::for_test(pod, "line"), now).len(),
+                1
+            );
+        }
+        // Dropped by the global limit without using up the token of pod-c
+        assert!(stage
+            .process_at(LogEntry::for_test("pod-c", "line"), now)
+            .is_empty());
+
+        let later = now + Duration::from_millis(500);
+        assert_eq!(
+            stage
+                .process_at(LogEntry::for_test("pod-c", "line"), later)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_rate_limits_and_report() {
+        let mut stage = ThrottleStage::new(None, Some(2.0), Some(3.0));
+        let now = Instant::now();
+
+        let mut kept = 0;
+        for _ in 0..5 {
+            kept += stage
+                .process_at(LogEntry::for_test("pod-a", "noisy"), now)
+                .len();
+        }
+        for _ in 0..5 {
+            kept += stage
+                .process_at(LogEntry::for_test("pod-b", "noisy"), now)
+                .len();
+        }
+
+        // Two from pod-a, then the global bucket only has one token left
+        assert_eq!(kept, 3);
+
+        let notes = stage.tick_at(now + REPORT_INTERVAL);
+        assert_eq!(notes.len(), 1);
+        assert_eq!(&*notes[0].pod_name, NOTE_SOURCE);
+        assert_eq!(notes[0].source, Source::Note);
+        assert!(notes[0].message().starts_with(
+            "suppressed 7 lines in the last 5s (sampled: 0, rate limited: 7; pod-a: 3, pod-b: 4)"
+        ));
+
+        // Nothing suppressed since the last report
+        assert!(stage.finish().is_empty());
+    }
+}
@@ -0,0 +1,263 @@
+use anyhow::{anyhow, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use chrono_tz::Tz;
+use std::str::FromStr;
+
+/// Format of absolute timestamps in UTC
+const UTC_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
+
+/// Format of absolute timestamps in other zones
+const ZONED_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f%:z";
+
+/// Check that a strftime format has no invalid specifiers
+///
+/// Formatting with an invalid specifier panics, so formats given by users
+/// have to be checked up front.
+pub fn validate_strftime(format: &str) -> Result<()> {
+    if StrftimeItems::new(format).any(|item| item == Item::Error) {
+        return Err(anyhow!("Invalid timestamp format: {}", format));
+    }
+    Ok(())
+}
+
+/// Time zone absolute timestamps are shown in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Zone {
+    Utc,
+    Local,
+    /// A named IANA zone such as `Europe/Berlin`
+    Named(Tz),
+}
+
+impl FromStr for Zone {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "utc" => Ok(Zone::Utc),
+            "local" => Ok(Zone::Local),
+            _ => s
+                .parse::<Tz>()
+                .map(Zone::Named)
+                .map_err(|_| anyhow!("Unknown time zone: {}", s)),
+        }
+    }
+}
+
+/// How timestamps are shown
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimestampMode {
+    /// Time in a zone, with an optional strftime format
+    Absolute { zone: Zone, format: Option<String> },
+    /// Time since the previous line, e.g. `+3.2s`
+    Relative,
+    /// Time since the first line shown
+    Elapsed,
+}
+
+impl FromStr for TimestampMode {
+    type Err = anyhow::Error;
+
+    /// Parse `utc`, `local`, an IANA zone, `relative`, `elapsed`, a strftime
+    /// format (in UTC) or a zone followed by a space and a strftime format
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "relative" => return Ok(TimestampMode::Relative),
+            "elapsed" => return Ok(TimestampMode::Elapsed),
+            _ => {}
+        }
+
+        if !s.contains('%') {
+            return Ok(TimestampMode::Absolute {
+                zone: s.parse().map_err(|_| {
+                    anyhow!(
+                        "Invalid timestamps: {} (expected utc, local, a time zone, relative, elapsed or a strftime format)",
+                        s
+                    )
+                })?,
+                format: None,
+            });
+        }
+
+        // A leading zone is only recognized when the rest is a format
+        let (zone, format) = match s.split_once(' ') {
+            Some((zone, format)) if !zone.contains('%') => (zone.parse()?, format),
+            _ => (Zone::Utc, s),
+        };
+        validate_strftime(format)?;
+        Ok(TimestampMode::Absolute {
+            zone,
+            format: Some(format.to_string()),
+        })
+    }
+}
+
+/// Format an offset as `+3.2s`, `-4m05s` or `+2h03m`
+pub fn format_offset(delta: TimeDelta) -> String {
+    let sign = if delta < TimeDelta::zero() { '-' } else { '+' };
+    let millis = delta.num_milliseconds().unsigned_abs();
+    let seconds = millis / 1000;
+
+    if seconds < 60 {
+        format!("{}{:.1}s", sign, millis as f64 / 1000.0)
+    } else if seconds < 3600 {
+        format!("{}{}m{:02}s", sign, seconds / 60, seconds % 60)
+    } else {
+        format!("{}{}h{:02}m", sign, seconds / 3600, seconds % 3600 / 60)
+    }
+}
+
+/// Renders the Kubernetes timestamps of log entries
+pub struct TimestampFormatter {
+    mode: TimestampMode,
+    /// Timestamp of the first line, which elapsed offsets are measured from
+    start: Option<DateTime<Utc>>,
+    /// Timestamp of the previous line
+    previous: Option<DateTime<Utc>>,
+}
+
+impl TimestampFormatter {
+    /// Create a formatter for the given mode
+    pub fn new(mode: TimestampMode) -> Self {
+        Self {
+            mode,
+            start: None,
+            previous: None,
+        }
+    }
+
+    /// Format a timestamp, or return `None` for lines without one
+    pub fn format(&mut self, timestamp: Option<DateTime<Utc>>) -> Option<String> {
+        let timestamp = timestamp?;
+        let formatted = match &self.mode {
+            TimestampMode::Absolute { zone, format } => {
+                let format = format.as_deref();
+                match zone {
+                    Zone::Utc => timestamp.format(format.unwrap_or(UTC_FORMAT)).to_string(),
+                    Zone::Local => timestamp
+                        .with_timezone(&Local)
+                        .format(format.unwrap_or(ZONED_FORMAT))
+                        .to_string(),
+                    Zone::Named(tz) => timestamp
+                        .with_timezone(tz)
+                        .format(format.unwrap_or(ZONED_FORMAT))
+                        .to_string(),
+                }
+            }
+            TimestampMode::Relative => {
+                format_offset(timestamp - self.previous.unwrap_or(timestamp))
+            }
+            TimestampMode::Elapsed => {
+                // Measured from the logs rather than the clock, so dumps of old
+                // logs start at zero instead of hours in the past
+                let start = *self.start.get_or_insert(timestamp);
+                format_offset(timestamp - start)
+            }
+        };
+        self.previous = Some(timestamp);
+        Some(formatted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> Option<DateTime<Utc>> {
+        Some(DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc))
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(
+            "UTC".parse::<TimestampMode>().unwrap(),
+            TimestampMode::Absolute {
+                zone: Zone::Utc,
+                format: None
+            }
+        );
+        assert_eq!(
+            "Europe/Berlin %H:%M".parse::<TimestampMode>().unwrap(),
+            TimestampMode::Absolute {
+                zone: Zone::Named(chrono_tz::Europe::Berlin),
+                format: Some("%H:%M".to_string())
+            }
+        );
+        assert_eq!(
+            "%d %b %H:%M".parse::<TimestampMode>().unwrap(),
+            TimestampMode::Absolute {
+                zone: Zone::Utc,
+                format: Some("%d %b %H:%M".to_string())
+            }
+        );
+        assert_eq!(
+            "relative".parse::<TimestampMode>().unwrap(),
+            TimestampMode::Relative
+        );
+        assert!("Mars/Olympus".parse::<TimestampMode>().is_err());
+        assert!("%Q".parse::<TimestampMode>().is_err());
+        assert!("Mars/Olympus %H".parse::<TimestampMode>().is_err());
+    }
+
+    #[test]
+    fn test_format_offset() {
+        assert_eq!(format_offset(TimeDelta::milliseconds(3210)), "+3.2s");
+        assert_eq!(format_offset(TimeDelta::seconds(-245)), "-4m05s");
+        assert_eq!(format_offset(TimeDelta::seconds(7380)), "+2h03m");
+        assert_eq!(format_offset(TimeDelta::zero()), "+0.0s");
+    }
+
+    #[test]
+    fn test_absolute() {
+        let mut formatter = TimestampFormatter::new("utc".parse().unwrap());
+        assert_eq!(
+            formatter.format(at("2024-05-01T10:00:05.123456Z")).unwrap(),
+            "2024-05-01T10:00:05.123Z"
+        );
+        assert_eq!(formatter.format(None), None);
+
+        let mut formatter = TimestampFormatter::new("America/New_York".parse().unwrap());
+        assert_eq!(
+            formatter.format(at("2024-05-01T10:00:05Z")).unwrap(),
+            "2024-05-01T06:00:05.000-04:00"
+        );
+
+        let mut formatter = TimestampFormatter::new("Asia/Tokyo %H:%M:%S".parse().unwrap());
+        assert_eq!(
+            formatter.format(at("2024-05-01T10:00:05Z")).unwrap(),
+            "19:00:05"
+        );
+    }
+
+    #[test]
+    fn test_relative_and_elapsed() {
+        let mut formatter = TimestampFormatter::new(TimestampMode::Relative);
+        assert_eq!(
+            formatter.format(at("2024-05-01T10:00:01Z")).unwrap(),
+            "+0.0s"
+        );
+        // Lines without a timestamp don't reset the previous time
+        assert_eq!(formatter.format(None), None);
+        assert_eq!(
+            formatter.format(at("2024-05-01T10:00:04.200Z")).unwrap(),
+            "+3.2s"
+        );
+
+        // Elapsed time starts at the first line, however long ago it was logged
+        let mut formatter = TimestampFormatter::new(TimestampMode::Elapsed);
+        assert_eq!(formatter.format(None), None);
+        assert_eq!(
+            formatter.format(at("2024-05-01T10:00:00Z")).unwrap(),
+            "+0.0s"
+        );
+        assert_eq!(
+            formatter.format(at("2024-05-01T10:01:30Z")).unwrap(),
+            "+1m30s"
+        );
+        assert_eq!(
+            formatter.format(at("2024-05-01T09:59:58Z")).unwrap(),
+            "-2.0s"
+        );
+    }
+}
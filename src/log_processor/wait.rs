@@ -0,0 +1,224 @@
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::kubernetes::log::LogEntry;
+
+/// How a wait ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitOutcome {
+    /// Every pod logged the wait pattern, or no failure was seen
+    Satisfied,
+    /// A pod logged the failure pattern
+    Failed,
+    /// The timeout expired before every pod logged the wait pattern
+    TimedOut,
+    /// The log streams ended before every pod logged the wait pattern
+    StreamEnded,
+}
+
+impl WaitOutcome {
+    /// Get the process exit code for the outcome
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            WaitOutcome::Satisfied => 0,
+            WaitOutcome::Failed => 2,
+            WaitOutcome::TimedOut => 3,
+            WaitOutcome::StreamEnded => 4,
+        }
+    }
+}
+
+/// Tracks which pods logged the wait pattern
+///
+/// The wait succeeds once every pod logged a line matching the wait
+/// pattern and fails as soon as any pod logs a line matching the failure
+/// pattern. Without a wait pattern, reaching the timeout or the end of the
+/// streams without a failure counts as success.
+pub struct WaitTracker {
+    /// Pattern every pod has to log
+    wait_for: Option<Regex>,
+    /// Pattern that fails the wait immediately
+    fail_on: Option<Regex>,
+    /// Maximum time to wait
+    timeout: Option<Duration>,
+    /// Pods that have not logged the wait pattern yet
+    pending: BTreeSet<String>,
+    /// Number of pods being waited for
+    total: usize,
+    /// Lines that decided the outcome so far
+    deciding: Vec<LogEntry>,
+}
+
+impl WaitTracker {
+    /// Create a tracker for the given pods
+    pub fn new<I>(wait_for: Option<Regex>, fail_on: Option<Regex>, pods: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let pending: BTreeSet<String> = pods.into_iter().collect();
+        Self {
+            wait_for,
+            fail_on,
+            timeout: None,
+            total: pending.len(),
+            pending,
+            deciding: Vec::new(),
+        }
+    }
+
+    /// Set the maximum time to wait
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Get the maximum time to wait
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Get the pods that have not logged the wait pattern yet
+    pub fn pending_pods(&self) -> impl Iterator<Item = &str> {
+        self.pending.iter().map(String::as_str)
+    }
+
+    /// Get the lines that decided the outcome
+    pub fn deciding_lines(&self) -> &[LogEntry] {
+        &self.deciding
+    }
+
+    /// Check a log entry and return the outcome once it is decided
+    pub fn observe(&mut self, entry: &LogEntry) -> Option<WaitOutcome> {
+        if let Some(fail_on) = &self.fail_on {
+            if fail_on.is_match(entry.message()) {
+                self.deciding = vec![entry.clone()];
+                return Some(WaitOutcome::Failed);
+            }
+        }
+
+        let wait_for = self.wait_for.as_ref()?;
+        if wait_for.is_match(entry.message()) && self.pending.remove(&*entry.pod_name) {
+            self.deciding.push(entry.clone());
+            if self.pending.is_empty() {
+                return Some(WaitOutcome::Satisfied);
+            }
+        }
+        None
+    }
+
+    /// Get the outcome when the streams ended or the timeout expired without a decision
+    pub fn finish(&self, timed_out: bool) -> WaitOutcome {
+        match (self.wait_for.is_some(), timed_out) {
+            (false, _) => WaitOutcome::Satisfied,
+            (true, true) => WaitOutcome::TimedOut,
+            (true, false) => WaitOutcome::StreamEnded,
+        }
+    }
+
+    /// Describe the outcome together with the lines that decided it
+    pub fn report(&self, outcome: WaitOutcome) -> String {
+        let matched = self.total - self.pending.len();
+        let mut output = match outcome {
+            WaitOutcome::Satisfied if self.wait_for.is_some() => {
+                format!("wait: all {} pods logged the wait pattern", self.total)
+            }
+            WaitOutcome::Satisfied => "wait: no pod logged the failure pattern".to_string(),
+            WaitOutcome::Failed => "wait: failure pattern matched".to_string(),
+            WaitOutcome::TimedOut => format!(
+                "wait: timed out after {:?}, {} of {} pods logged the wait pattern",
+                self.timeout.unwrap_or_default(),
+                matched,
+                self.total
+            ),
+            WaitOutcome::StreamEnded => format!(
+                "wait: log streams ended, {} of {} pods logged the wait pattern",
+                matched, self.total
+            ),
+        };
+
+        if matches!(outcome, WaitOutcome::TimedOut | WaitOutcome::StreamEnded) {
+            let pending: Vec<&str> = self.pending_pods().collect();
+            let _ = write!(output, "; still waiting for: {}", pending.join(", "));
+        }
+        for entry in &self.deciding {
+            let _ = write!(output, "\n  [{}] {}", entry.pod_name, entry.message());
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_tracker(wait_for: Option<&str>, fail_on: Option<&str>) -> WaitTracker {
+        WaitTracker::new(
+            wait_for.map(|p| Regex::new(p).unwrap()),
+            fail_on.map(|p| Regex::new(p).unwrap()),
+            vec!["web-1".to_string(), "web-2".to_string()],
+        )
+    }
+
+    #[test]
+    fn test_every_pod_has_to_match() {
+        let mut tracker = create_tracker(Some("Server started"), Some("FATAL"));
+
+        assert_eq!(
+            tracker.observe(&LogEntry::for_test("web-1", "booting")),
+            None
+        );
+        assert_eq!(
+            tracker.observe(&LogEntry::for_test("web-1", "Server started on :80")),
+            None
+        );
+        assert_eq!(
+            tracker.observe(&LogEntry::for_test("web-1", "Server started on :80")),
+            None
+        );
+        assert_eq!(
+            tracker.observe(&LogEntry::for_test("web-2", "Server started on :80")),
+            Some(WaitOutcome::Satisfied)
+        );
+
+        assert_eq!(tracker.deciding_lines().len(), 2);
+        let report = tracker.report(WaitOutcome::Satisfied);
+        assert!(report.starts_with("wait: all 2 pods logged the wait pattern"));
+        assert!(report.ends_with("[web-2] Server started on :80"));
+    }
+
+    #[test]
+    fn test_failure_pattern() {
+        let mut tracker = create_tracker(Some("Server started"), Some("FATAL"));
+
+        tracker.observe(&LogEntry::for_test("web-1", "Server started"));
+        assert_eq!(
+            tracker.observe(&LogEntry::for_test("web-2", "FATAL: bad config")),
+            Some(WaitOutcome::Failed)
+        );
+        assert_eq!(WaitOutcome::Failed.exit_code(), 2);
+        assert_eq!(
+            tracker.report(WaitOutcome::Failed),
+            "wait: failure pattern matched\n  [web-2] FATAL: bad config"
+        );
+    }
+
+    #[test]
+    fn test_finish() {
+        let mut tracker = create_tracker(Some("Server started"), None)
+            .with_timeout(Some(Duration::from_secs(120)));
+        tracker.observe(&LogEntry::for_test("web-1", "Server started"));
+
+        assert_eq!(tracker.finish(true), WaitOutcome::TimedOut);
+        assert_eq!(tracker.finish(false), WaitOutcome::StreamEnded);
+        assert_eq!(tracker.finish(true).exit_code(), 3);
+        assert!(tracker
+            .report(WaitOutcome::TimedOut)
+            .starts_with("wait: timed out after 120s, 1 of 2 pods logged the wait pattern; still waiting for: web-2"));
+
+        // Without a wait pattern, not failing is a success
+        let tracker = create_tracker(None, Some("FATAL"));
+        assert_eq!(tracker.finish(true), WaitOutcome::Satisfied);
+    }
+}
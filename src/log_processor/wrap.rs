@@ -0,0 +1,345 @@
+use std::io::{self, Write};
+use termcolor::{ColorSpec, WriteColor};
+use unicode_width::UnicodeWidthChar;
+
+/// Marker written where a truncated line was cut
+pub const TRUNCATION_MARKER: &str = "…";
+
+/// Narrowest room left for the message before the hanging indent is dropped
+const MIN_MESSAGE_WIDTH: usize = 20;
+
+/// Width of a tab stop
+const TAB_WIDTH: usize = 8;
+
+/// What happens to lines wider than the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Wrap at word boundaries, indenting continuation lines under the message
+    Wrap,
+    /// Cut at the terminal width with a marker
+    Truncate,
+}
+
+/// Writer fitting lines to a terminal width
+///
+/// Text is collected until the end of each line so words can be kept whole,
+/// then written with its colors. Continuation lines, both from wrapping and
+/// from messages spanning several lines, are indented by `indent` columns so
+/// they line up under the message instead of the prefix.
+pub struct LineWriter<'a, W: WriteColor> {
+    out: &'a mut W,
+    width: usize,
+    overflow: Overflow,
+    indent: usize,
+    /// Characters of the current line with the index of their color
+    row: Vec<(char, usize)>,
+    /// Colors used on the current line, the first is no color
+    specs: Vec<ColorSpec>,
+    /// Index of the color of text written now
+    current: usize,
+    /// Whether the current line continues a message spanning several lines
+    continued: bool,
+}
+
+impl<'a, W: WriteColor> LineWriter<'a, W> {
+    /// Create a writer fitting lines into `width` columns
+    pub fn new(out: &'a mut W, width: usize, overflow: Overflow, indent: usize) -> Self {
+        let indent = if indent + MIN_MESSAGE_WIDTH > width {
+            0
+        } else {
+            indent
+        };
+        Self {
+            out,
+            width,
+            overflow,
+            indent,
+            row: Vec::new(),
+            specs: vec![ColorSpec::new()],
+            current: 0,
+            continued: false,
+        }
+    }
+
+    /// Write the rest of an unterminated line
+    pub fn finish(mut self) -> io::Result<()> {
+        if !self.row.is_empty() {
+            self.write_row()?;
+        }
+        Ok(())
+    }
+
+    /// Write the collected line, wrapped or truncated, without the newline
+    fn write_row(&mut self) -> io::Result<()> {
+        let row = std::mem::take(&mut self.row);
+        let (first, rest) = if self.continued {
+            self.write_indent()?;
+            (self.width - self.indent, self.width - self.indent)
+        } else {
+            (self.width, self.width - self.indent)
+        };
+        let widths = char_widths(&row, if self.continued { self.indent } else { 0 });
+
+        let mut color = 0;
+        match self.overflow {
+            Overflow::Wrap => {
+                let breaks = wrap_points(&row, &widths, first, rest);
+                // Spaces where the line is cut are left out
+                let mut skip = vec![false; row.len()];
+                for &point in &breaks {
+                    for i in (0..point).rev().take_while(|&i| row[i].0 == ' ') {
+                        skip[i] = true;
+                    }
+                }
+
+                let mut breaks = breaks.iter().peekable();
+                let mut column = 0;
+                let mut available = first;
+                for (i, (&(c, spec), &width)) in row.iter().zip(&widths).enumerate() {
+                    if breaks.next_if_eq(&&i).is_some() {
+                        if color != 0 {
+                            self.out.reset()?;
+                            color = 0;
+                        }
+                        writeln!(self.out)?;
+                        self.write_indent()?;
+                        column = 0;
+                        available = rest;
+                    }
+                    if skip[i] || (c == ' ' && column + width > available) {
+                        continue;
+                    }
+                    color = self.write_char(c, width, spec, color)?;
+                    column += width;
+                }
+            }
+            Overflow::Truncate => {
+                let total: usize = widths.iter().sum();
+                let mut column = 0;
+                for (&(c, spec), &width) in row.iter().zip(&widths) {
+                    if total > first && column + width >= first {
+                        if color != 0 {
+                            self.out.reset()?;
+                            color = 0;
+                        }
+                        write!(self.out, "{}", TRUNCATION_MARKER)?;
+                        break;
+                    }
+                    color = self.write_char(c, width, spec, color)?;
+                    column += width;
+                }
+            }
+        }
+        if color != 0 {
+            self.out.reset()?;
+        }
+        Ok(())
+    }
+
+    /// Write a character in a color, switching colors only when they change
+    fn write_char(
+        &mut self,
+        c: char,
+        width: usize,
+        spec: usize,
+        color: usize,
+    ) -> io::Result<usize> {
+        if spec != color {
+            if spec == 0 {
+                self.out.reset()?;
+            } else {
+                self.out.set_color(&self.specs[spec])?;
+            }
+        }
+        if c == '\t' {
+            write!(self.out, "{:width$}", "")?;
+        } else {
+            write!(self.out, "{}", c)?;
+        }
+        Ok(spec)
+    }
+
+    /// Indent a continuation line
+    fn write_indent(&mut self) -> io::Result<()> {
+        write!(self.out, "{:width$}", "", width = self.indent)
+    }
+}
+
+/// Get the display width of each character, expanding tabs from the given column
+fn char_widths(row: &[(char, usize)], start: usize) -> Vec<usize> {
+    let mut column = start;
+    row.iter()
+        .map(|&(c, _)| {
+            let width = match c {
+                '\t' => TAB_WIDTH - column % TAB_WIDTH,
+                c => c.width().unwrap_or(0),
+            };
+            column += width;
+            width
+        })
+        .collect()
+}
+
+/// Find where wrapped lines start, preferring to break after spaces
+///
+/// The first line has `first` columns and the following ones `rest` columns.
+fn wrap_points(row: &[(char, usize)], widths: &[usize], first: usize, rest: usize) -> Vec<usize> {
+    let mut breaks = Vec::new();
+    let mut line_start = 0;
+    let mut column = 0;
+    let mut available = first;
+    let mut last_space = None;
+
+    for (i, (&(c, _), &width)) in row.iter().zip(widths).enumerate() {
+        if c != ' ' && column + width > available && i > line_start {
+            let point = match last_space {
+                Some(point) if point > line_start => point,
+                _ => i,
+            };
+            breaks.push(point);
+            line_start = point;
+            available = rest;
+            column = widths[point..i].iter().sum();
+            last_space = None;
+        }
+        column += width;
+        if c == ' ' {
+            last_space = Some(i + 1);
+        }
+    }
+    breaks
+}
+
+impl<W: WriteColor> Write for LineWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let spec = self.current;
+        for c in String::from_utf8_lossy(buf).chars() {
+            match c {
+                '\n' => {
+                    self.write_row()?;
+                    writeln!(self.out)?;
+                    self.continued = true;
+                }
+                '\r' => {}
+                c => self.row.push((c, spec)),
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+impl<W: WriteColor> WriteColor for LineWriter<'_, W> {
+    fn supports_color(&self) -> bool {
+        self.out.supports_color()
+    }
+
+    fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
+        if spec.is_none() {
+            self.current = 0;
+        } else {
+            self.specs.push(spec.clone());
+            self.current = self.specs.len() - 1;
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        self.current = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use termcolor::{Buffer, Color};
+
+    fn fit(text: &str, width: usize, overflow: Overflow, indent: usize) -> String {
+        let mut buffer = Buffer::no_color();
+        let mut writer = LineWriter::new(&mut buffer, width, overflow, indent);
+        write!(writer, "{}", text).unwrap();
+        writer.finish().unwrap();
+        String::from_utf8(buffer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn test_wrap_with_hanging_indent() {
+        let line = "[web-1] connection to the database was refused after three attempts\n";
+        assert_eq!(
+            fit(line, 30, Overflow::Wrap, 8),
+            "[web-1] connection to the\n        database was refused\n        after three attempts\n"
+        );
+        // Lines that fit are left alone
+        assert_eq!(fit("[web-1] ok\n", 30, Overflow::Wrap, 8), "[web-1] ok\n");
+    }
+
+    #[test]
+    fn test_wrap_long_words_and_wide_characters() {
+        // Words longer than a line are cut
+        assert_eq!(
+            fit(
+                "abcdefghijklmnopqrstuvwxyz0123456789",
+                20,
+                Overflow::Wrap,
+                0
+            ),
+            "abcdefghijklmnopqrst\nuvwxyz0123456789"
+        );
+        // CJK characters take two columns and are not split across lines
+        assert_eq!(
+            fit("日本語のログ行です", 7, Overflow::Wrap, 0),
+            "日本語\nのログ\n行です"
+        );
+    }
+
+    #[test]
+    fn test_continuation_lines_are_indented() {
+        let line = "[web-1] {\n  \"msg\": \"started\"\n}\n";
+        assert_eq!(
+            fit(line, 40, Overflow::Wrap, 8),
+            "[web-1] {\n          \"msg\": \"started\"\n        }\n"
+        );
+        // Without room for the message, continuation lines are not indented
+        assert_eq!(
+            fit("[web-1] a b c\nd\n", 24, Overflow::Wrap, 8),
+            "[web-1] a b c\nd\n"
+        );
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(
+            fit("[web-1] connection refused\n", 20, Overflow::Truncate, 8),
+            "[web-1] connection …\n"
+        );
+        assert_eq!(
+            fit("[web-1] exactly 20 c", 20, Overflow::Truncate, 8),
+            "[web-1] exactly 20 c"
+        );
+        assert_eq!(fit("日本語のログ", 6, Overflow::Truncate, 0), "日本…");
+    }
+
+    #[test]
+    fn test_colors_survive_wrapping() {
+        let mut red = ColorSpec::new();
+        red.set_fg(Some(Color::Red));
+
+        let mut buffer = Buffer::ansi();
+        let mut writer = LineWriter::new(&mut buffer, 10, Overflow::Wrap, 0);
+        write!(writer, "ok ").unwrap();
+        writer.set_color(&red).unwrap();
+        write!(writer, "error ").unwrap();
+        writer.reset().unwrap();
+        write!(writer, "again").unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(
+            String::from_utf8(buffer.into_inner()).unwrap(),
+            "ok \x1b[0m\x1b[31merror\x1b[0m\nagain"
+        );
+    }
+}
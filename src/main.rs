@@ -1,33 +1,95 @@
 use anyhow::{Result, anyhow};
+use indicatif::{ProgressBar, ProgressStyle};
 use klogrs::{
-    cli::{parse_args, Args},
-    kubernetes::{KubeClient, LogAggregator, LogEntry, PodInfo},
+    cli::{parse_args, Args, Command},
+    config::Config,
+    kubernetes::{KubeClient, LogAggregator, LogEntry, PodInfo, PodStatus},
     log_processor::{
-        filter::{AndFilter, Filter, GrepFilter, LevelFilter, OrFilter},
-        LogFormatter,
+        alert::AlertRule,
+        buffer::{last_entries, BufferedStream},
+        encoder::{csv_header, OutputFormat},
+        filter::{AndFilter, Filter, LevelFilter, OrFilter, PatternFilter},
+        merge_streams, AlertEngine, AlertStage, DedupStage, LogFormatter, Overflow, PatternMatcher,
+        Pipeline, Redactor, ReorderStage, StatsCollector, StreamBuffers, TemplateMiner,
+        ThrottleStage, WaitOutcome, WaitTracker,
     },
+    output::{Pager, RotatingFile, RotationPolicy, SplitWriter},
+    tui,
+    utils::{theme::DEFAULT_THEME, Theme},
 };
-use std::collections::HashMap;
+use ratatui::crossterm::terminal;
+use regex::Regex;
+use std::collections::BTreeMap;
 use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::time::{interval, sleep, Duration, Instant};
 use tracing::{debug, error, info};
 use tracing_subscriber::fmt::format::FmtSpan;
 
+/// How often processing stages get a chance to emit held entries in follow mode
+const STAGE_TICK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Capacity of the channel between the log aggregator and the redaction task
+const REDACT_CHANNEL_SIZE: usize = 100;
+
+/// How often the statistics table is refreshed in follow mode
+const STATS_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the pods in the TUI sidebar are refreshed
+const POD_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Capacity of the channel that merges the streams of pods attached while waiting
+const WAIT_CHANNEL_SIZE: usize = 100;
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging
+    // Parse command-line arguments
+    let args = parse_args(env::args_os())?;
+
+    // Initialize logging, quiet by default in the TUI where it would garble the screen
+    let default_filter = match args.command {
+        Some(Command::Tui) => "off",
+        None => "warn",
+    };
     tracing_subscriber::fmt()
-        .with_env_filter(env::var("RUST_LOG").unwrap_or_else(|_| "warn".to_string()))
+        .with_env_filter(env::var("RUST_LOG").unwrap_or_else(|_| default_filter.to_string()))
         .with_span_events(FmtSpan::CLOSE)
+        // Log lines go to stdout, which the output sink holds locked
+        .with_writer(io::stderr)
         .init();
 
-    // Parse command-line arguments
-    let args = parse_args(env::args_os())?;
-
     // Run the application
     run(args).await
 }
 
 async fn run(args: Args) -> Result<()> {
+    // Load the configuration before connecting so mistakes are reported early
+    let config = Config::load(args.config.as_deref())?;
+    let alert_rules = config
+        .alerts
+        .iter()
+        .map(AlertRule::from_config)
+        .collect::<Result<Vec<_>>>()?;
+    let theme = Theme::load(
+        args.theme
+            .as_deref()
+            .or(config.theme.as_deref())
+            .unwrap_or(DEFAULT_THEME),
+        &config,
+    )?;
+
+    // Redact sensitive values as soon as the lines leave the aggregator
+    let redactor = if args.redact {
+        let redactor = Redactor::new().with_rules(&config.redact)?;
+        info!("Redacting: {}", redactor.detector_names().join(", "));
+        Some(Arc::new(redactor))
+    } else {
+        None
+    };
+
     // Create Kubernetes client
     let client = KubeClient::new().await?;
 
@@ -36,10 +98,22 @@ async fn run(args: Args) -> Result<()> {
         .get_pods_for_deployment(&args.namespace, &args.deployment)
         .await?;
 
+    // Pods that are still starting can be waited for, but not read yet
+    let waiting = args.wait_for.is_some() || args.fail_on.is_some();
+    let starting = StartingPods {
+        namespace: args.namespace.clone(),
+        deployment: args.deployment.clone(),
+        pods: pods
+            .iter()
+            .filter(|pod| waiting && pod.status == PodStatus::Pending)
+            .cloned()
+            .collect(),
+    };
+
     // Filter out terminated pods
     pods.retain(|pod| pod.can_get_logs());
 
-    if pods.is_empty() {
+    if pods.is_empty() && starting.pods.is_empty() {
         return Err(anyhow::anyhow!(
             "No active pods found for deployment {}",
             args.deployment
@@ -52,62 +126,153 @@ async fn run(args: Args) -> Result<()> {
         args.deployment
     );
 
+    // Lines of all pods are read through the log source
+    let source = LogSource {
+        client,
+        pods,
+        redactor,
+    };
+
     // Create log formatter
     let mut formatter = LogFormatter::new(None, false);
     
     // Set highlight option
     formatter.set_highlight(!args.no_highlight);
+
+    // Set output format
+    formatter.set_output_format(args.output);
+    formatter.set_json_style(args.json_style);
+    if let Some(mode) = args.timestamps.clone() {
+        formatter.set_timestamps(mode);
+    }
+
+    // Set colors
+    formatter.set_theme(theme);
+    formatter.set_color_choice(args.color.color_choice());
+
+    // Set output template, with the nodes of the pods for the node placeholder
+    if let Some(template) = args.template.clone() {
+        formatter.set_template(template);
+        formatter.set_pod_nodes(
+            source
+                .pods
+                .iter()
+                .filter_map(|pod| Some((pod.name.clone(), pod.node_name.clone()?)))
+                .collect(),
+        );
+    }
+
+    // Line up messages of all pods and give them unique short names
+    formatter.add_pods(source.pods.iter().map(|pod| pod.name.as_str()));
+
+    // Fit lines to the terminal, leaving lines piped elsewhere and the TUI untouched
+    if !args.no_wrap
+        && !args.output.is_machine_readable()
+        && args.command.is_none()
+        && io::stdout().is_terminal()
+    {
+        if let Ok((width, _)) = terminal::size() {
+            let overflow = if args.truncate {
+                Overflow::Truncate
+            } else {
+                Overflow::Wrap
+            };
+            formatter.set_line_width(width as usize, overflow);
+        }
+    }
+
+    // Copy the output to a rotated file
+    if let Some(path) = &args.output_file {
+        let policy = RotationPolicy {
+            max_size: args.rotate_size,
+            interval: args.rotate_interval,
+            compression: args.compress,
+            keep: args.keep,
+        };
+        let mut file = RotatingFile::open(path, policy)?;
+        if args.output == OutputFormat::Csv {
+            file = file.with_header(csv_header())?;
+        }
+        formatter.set_output_file(file);
+    }
+
+    // Split the output into one file per container
+    if let Some(dir) = &args.output_dir {
+        let mut writer = SplitWriter::create(dir)?;
+        if args.output == OutputFormat::Csv {
+            writer = writer.with_header(csv_header());
+        }
+        formatter.set_output_dir(writer);
+    }
     
     // Create filters
     let mut filters: Vec<Box<dyn Filter>> = Vec::new();
     let mut combined_filters: Vec<Box<dyn Filter>> = Vec::new();
 
     // Handle grep filter
+    let mut patterns: Vec<String> = Vec::new();
+
+    // Check if it contains the & separator
+    let is_and_operation = args.grep.as_deref().is_some_and(|grep| grep.contains('&'));
+
     if let Some(grep) = &args.grep {
-        let mut grep_filters: Vec<Box<dyn Filter>> = Vec::new();
-        
-        // Check if it contains the & separator
-        let is_and_operation = grep.contains('&');
-        
         // Select the split mode according to the separator
-        let patterns: Vec<&str> = if is_and_operation {
+        let split: Vec<&str> = if is_and_operation {
             grep.split('&').collect()
         } else {
             // Use comma as the separator by default to maintain backward compatibility
             grep.split(',').collect()
         };
 
-        for pattern in patterns {
-            let trimmed_pattern = pattern.trim();
-            if !trimmed_pattern.is_empty() {
-                match GrepFilter::new(trimmed_pattern) {
-                    Ok(filter) => {
-                        // Add grep filter to formatter for highlighting if highlighting is enabled
-                        if !args.no_highlight {
-                            formatter.add_grep_filter(filter.clone());
-                        }
-                        
-                        grep_filters.push(Box::new(filter));
-                    }
-                    Err(e) => {
-                        // Only record detailed errors, and do not repeat the error messages that users will see in the logs
-                        debug!("Failed to create grep filter for pattern '{}': {}", trimmed_pattern, e);
-                        return Err(anyhow!("Invalid grep pattern: {}", trimmed_pattern));
-                    }
-                }
-            }
-        }
-        
-        if !grep_filters.is_empty() {
-            // Determine whether to use AND or OR logic according to the separator
-            if is_and_operation {
-                info!("Filtering logs with ALL of the patterns: {}", grep);
-                combined_filters.push(Box::new(AndFilter::new(grep_filters)));
-            } else {
-                info!("Filtering logs with ANY of the patterns: {}", grep);
-                combined_filters.push(Box::new(OrFilter::new(grep_filters)));
+        patterns.extend(
+            split
+                .into_iter()
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(str::to_string),
+        );
+    }
+
+    // Handle grep pattern file, one pattern per line
+    if let Some(path) = &args.grep_file {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read grep file {}: {}", path.display(), e))?;
+
+        patterns.extend(
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|p| !p.is_empty() && !p.starts_with('#'))
+                .map(str::to_string),
+        );
+    }
+
+    if !patterns.is_empty() {
+        // Compile all patterns into a single matcher shared by filtering and highlighting
+        let matcher = match PatternMatcher::new(&patterns) {
+            Ok(matcher) => Arc::new(matcher),
+            Err(e) => {
+                // Only record detailed errors, and do not repeat the error messages that users will see in the logs
+                debug!("Failed to create grep matcher: {}", e);
+                return Err(anyhow!("Invalid grep pattern: {}", e));
             }
+        };
+
+        // Add matcher to formatter for highlighting if highlighting is enabled
+        if !args.no_highlight {
+            formatter.set_matcher(matcher.clone());
+            formatter.set_match_scope(args.grep_scope.clone());
         }
+
+        // Determine whether to use AND or OR logic according to the separator
+        let filter = if is_and_operation {
+            info!("Filtering logs with ALL of the {} patterns", matcher.len());
+            PatternFilter::all(matcher)
+        } else {
+            info!("Filtering logs with ANY of the {} patterns", matcher.len());
+            PatternFilter::any(matcher)
+        };
+        combined_filters.push(Box::new(filter.with_scope(args.grep_scope.clone())));
     }
 
     // Handle level filter
@@ -123,7 +288,7 @@ async fn run(args: Args) -> Result<()> {
                 match LevelFilter::new(trimmed_level) {
                     Ok(filter) => {
                         info!("Adding level filter for: '{}'", trimmed_level);
-                        level_filters.push(Box::new(filter));
+                        level_filters.push(Box::new(filter.with_scope(args.level_scope.clone())));
                     },
                     Err(e) => {
                         // Only record detailed errors, and do not repeat the error messages that users will see in the logs
@@ -150,78 +315,224 @@ async fn run(args: Args) -> Result<()> {
         filters.extend(combined_filters);
     }
 
-    // Handle tail parameter
-    if let Some(tail_count) = args.tail {
-        if args.follow {
-            // Follow mode with tail parameter
-            run_logs(client, &pods, &filters, &mut formatter, true, Some(tail_count)).await
-        } else {
-            // Non-follow mode with tail parameter
-            run_logs(client, &pods, &filters, &mut formatter, false, Some(tail_count)).await
+    // The TUI follows the logs and refreshes the pod list on its own
+    if args.command == Some(Command::Tui) {
+        return run_tui(source, filters, formatter, args).await;
+    }
+
+    // Summary mode replaces the regular output
+    if args.summarize {
+        return run_summary(&source, &filters, args.tail).await;
+    }
+
+    // Statistics mode replaces the regular output
+    if args.stats {
+        return run_stats(&source, &filters, args.follow, args.tail).await;
+    }
+
+    // Create processing stages
+    let mut pipeline = Pipeline::new();
+
+    // Reorder first so every following stage sees lines in timestamp order
+    if let Some(latency) = args.reorder {
+        let containers = source.pods.iter().map(|pod| {
+            (
+                Arc::from(pod.name.as_str()),
+                Arc::from(pod.container_name.as_str()),
+            )
+        });
+        pipeline.push(Box::new(
+            ReorderStage::new(latency).with_containers(containers),
+        ));
+    }
+
+    // Alerts see every filtered line, before deduplication and sampling
+    if !alert_rules.is_empty() {
+        pipeline.push(Box::new(AlertStage::new(AlertEngine::new(alert_rules))));
+    }
+
+    if args.dedup || args.dedup_window.is_some() {
+        pipeline.push(Box::new(DedupStage::new(args.dedup, args.dedup_window)));
+    }
+
+    if args.sample.is_some() || args.rate_limit.is_some() || args.global_rate_limit.is_some() {
+        for rate in [args.rate_limit, args.global_rate_limit]
+            .into_iter()
+            .flatten()
+        {
+            if rate <= 0.0 || !rate.is_finite() {
+                return Err(anyhow!("Invalid rate limit: {}", rate));
+            }
         }
-    } else {
-        // No tail parameter
-        run_logs(client, &pods, &filters, &mut formatter, args.follow, None).await
+
+        pipeline.push(Box::new(ThrottleStage::new(
+            args.sample,
+            args.rate_limit,
+            args.global_rate_limit,
+        )));
+    }
+
+    if !pipeline.is_empty() {
+        info!("Processing stages: {}", pipeline.description());
     }
+
+    // Wait mode follows the logs until the outcome is decided and exits with its code
+    if args.wait_for.is_some() || args.fail_on.is_some() {
+        let compile = |pattern: &Option<String>| {
+            pattern
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .map_err(|e| anyhow!("Invalid wait pattern: {}", e))
+        };
+        let tracker = WaitTracker::new(
+            compile(&args.wait_for)?,
+            compile(&args.fail_on)?,
+            source
+                .pods
+                .iter()
+                .chain(&starting.pods)
+                .map(|pod| pod.name.clone()),
+        )
+        .with_timeout(args.wait_timeout);
+
+        let outcome = run_wait(
+            &source,
+            &filters,
+            &mut pipeline,
+            &mut formatter,
+            args.tail,
+            tracker,
+            starting,
+        )
+        .await?;
+        // Exiting skips destructors, so the output file is flushed here
+        formatter.flush()?;
+        report_output_dir(&formatter);
+        std::process::exit(outcome.exit_code());
+    } else if args.wait_timeout.is_some() {
+        return Err(anyhow!("--wait-timeout requires --wait-for or --fail-on"));
+    }
+
+    // Page dumps that would scroll past, but not logs that keep coming
+    if !args.no_pager && !args.follow && args.output_dir.is_none() && io::stdout().is_terminal() {
+        if let Some(pager) = Pager::from_env(args.color.color_choice())? {
+            formatter.set_pager(pager);
+        }
+    }
+
+    let options = DisplayOptions {
+        follow: args.follow,
+        tail: args.tail,
+        tail_total: args.tail_total,
+        group_by_pod: args.group_by_pod,
+    };
+    run_logs(&source, &filters, &mut pipeline, &mut formatter, &options).await
 }
 
-// Unified log running function, replacing the previous three functions
-async fn run_logs(
-    client: KubeClient,
-    pods: &[PodInfo],
-    filters: &[Box<dyn Filter>],
-    formatter: &mut LogFormatter,
-    follow: bool,
-    tail: Option<usize>,
+/// Follow the logs in the full-screen UI, refreshing the pods in the sidebar
+async fn run_tui(
+    source: LogSource,
+    filters: Vec<Box<dyn Filter>>,
+    formatter: LogFormatter,
+    args: Args,
 ) -> Result<()> {
-    // Log mode information
-    match (follow, tail) {
-        (true, Some(count)) => {
-            info!(
-                "Displaying the last {} log entries and following new ones",
-                count
-            );
-        }
-        (false, Some(count)) => {
-            info!("Displaying the last {} log entries", count);
-        }
-        (true, None) => {
-            info!("Following logs in real-time");
+    let (pod_tx, pod_rx) = mpsc::channel(1);
+    let client = source.client.clone();
+    let (namespace, deployment) = (args.namespace.clone(), args.deployment.clone());
+    let refresh = tokio::spawn(async move {
+        let mut ticker = interval(POD_REFRESH_INTERVAL);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            match client
+                .get_pods_for_deployment(&namespace, &deployment)
+                .await
+            {
+                Ok(pods) => {
+                    if pod_tx.send(pods).await.is_err() {
+                        break;
+                    }
+                }
+                Err(e) => debug!("Failed to refresh pods: {}", e),
+            }
         }
-        (false, None) => {
-            info!("Displaying all available logs");
+    });
+
+    let logs = source.stream(true, args.tail).await;
+    let result = tui::run(logs, source.pods.clone(), pod_rx, filters, formatter).await;
+    refresh.abort();
+    result
+}
+
+/// Display entries that came out of the processing stages
+fn display_entries(formatter: &mut LogFormatter, entries: Vec<LogEntry>) {
+    for entry in entries {
+        if let Err(e) = formatter.format_colored(&entry) {
+            error!("Failed to format log entry: {}", e);
         }
     }
+}
 
-    // Create log aggregator
-    let mut aggregator = LogAggregator::new();
+/// Which log entries are displayed and in what order
+struct DisplayOptions {
+    /// Keep following new lines
+    follow: bool,
+    /// Number of lines per container
+    tail: Option<usize>,
+    /// Number of lines across all pods, without follow
+    tail_total: Option<usize>,
+    /// Show pods in turn instead of merged by timestamp, without follow
+    group_by_pod: bool,
+}
 
-    // Prepare to get log streams in parallel
-    let mut handles = Vec::with_capacity(pods.len());
-    
-    // Get log streams for each pod in parallel
-    for pod in pods {
-        let client_clone = client.clone();
-        let pod_clone = pod.clone();
-        let follow_clone = follow;
-        let tail_clone = tail;
-        
-        debug!("Starting log stream task for pod {} ({})", pod.name, pod.status);
-        
-        // Create an asynchronous task for each pod
-        let handle = tokio::spawn(async move {
-            let result = client_clone.get_pod_logs(&pod_clone, follow_clone, tail_clone).await;
-            (pod_clone, result)
-        });
-        
-        handles.push(handle);
-    }
+/// Pods to read logs from and how their lines are prepared
+struct LogSource {
+    client: KubeClient,
+    pods: Vec<PodInfo>,
+    /// Redacts sensitive values before anything else sees the lines
+    redactor: Option<Arc<Redactor>>,
+}
 
-    // Wait for all log streams to initialize and add them to the aggregator
-    for handle in handles {
-        match handle.await {
-            Ok((pod, log_stream_result)) => {
-                match log_stream_result {
+impl LogSource {
+    /// Start log streams for all pods in parallel and aggregate them into one channel
+    async fn stream(&self, follow: bool, tail: Option<usize>) -> mpsc::Receiver<Result<LogEntry>> {
+        let client = &self.client;
+        let pods = &self.pods;
+
+        // Create log aggregator
+        let mut aggregator = LogAggregator::new();
+
+        // Prepare to get log streams in parallel
+        let mut handles = Vec::with_capacity(pods.len());
+
+        // Get log streams for each pod in parallel
+        for pod in pods {
+            let client_clone = client.clone();
+            let pod_clone = pod.clone();
+            let follow_clone = follow;
+            let tail_clone = tail;
+
+            debug!(
+                "Starting log stream task for pod {} ({})",
+                pod.name, pod.status
+            );
+
+            // Create an asynchronous task for each pod
+            let handle = tokio::spawn(async move {
+                let result = client_clone
+                    .get_pod_logs(&pod_clone, follow_clone, tail_clone)
+                    .await;
+                (pod_clone, result)
+            });
+
+            handles.push(handle);
+        }
+
+        // Wait for all log streams to initialize and add them to the aggregator
+        for handle in handles {
+            match handle.await {
+                Ok((pod, log_stream_result)) => match log_stream_result {
                     Ok(log_stream) => {
                         debug!("Adding log stream for pod {} ({})", pod.name, pod.status);
                         if let Err(e) = aggregator.add_pod_stream(pod, log_stream).await {
@@ -231,66 +542,313 @@ async fn run_logs(
                     Err(e) => {
                         error!("Failed to get logs for pod {}: {}", pod.name, e);
                     }
+                },
+                Err(e) => {
+                    error!("Task failed: {}", e);
                 }
             }
-            Err(e) => {
-                error!("Task failed: {}", e);
-            }
+        }
+
+        let log_stream = aggregator.stream();
+        match &self.redactor {
+            Some(redactor) => redact_stream(log_stream, redactor.clone()),
+            None => log_stream,
         }
     }
+}
 
-    // Get the log stream
-    let mut log_stream = aggregator.stream();
+/// Pods of the deployment that were still starting when klogrs started
+struct StartingPods {
+    namespace: String,
+    deployment: String,
+    pods: Vec<PodInfo>,
+}
+
+impl StartingPods {
+    /// Look the pods up again and take the ones whose logs can be read now
+    ///
+    /// Pods that terminated or disappeared are dropped without being
+    /// returned, so the wait reports them as still pending.
+    async fn take_started(&mut self, client: &KubeClient) -> Vec<PodInfo> {
+        let current = match client
+            .get_pods_for_deployment(&self.namespace, &self.deployment)
+            .await
+        {
+            Ok(pods) => pods,
+            Err(e) => {
+                debug!("Failed to refresh pods: {}", e);
+                return Vec::new();
+            }
+        };
 
-    if !follow && tail.is_some() {
-        let tail_count = tail.unwrap();
-        let mut pod_buffers: HashMap<String, Vec<LogEntry>> = HashMap::new();
+        let mut started = Vec::new();
+        self.pods.retain(|pod| {
+            let latest = current.iter().find(|other| other.name == pod.name);
+            match latest {
+                Some(latest) if latest.can_get_logs() => {
+                    started.push(latest.clone());
+                    false
+                }
+                Some(latest) => latest.status == PodStatus::Pending,
+                None => false,
+            }
+        });
+        started
+    }
+}
 
-        for pod in pods {
-            pod_buffers.insert(pod.name.clone(), Vec::with_capacity(tail_count));
+/// Forward the entries of a log stream to a shared channel in a separate task
+fn forward_stream(
+    mut log_stream: mpsc::Receiver<Result<LogEntry>>,
+    tx: mpsc::Sender<Result<LogEntry>>,
+) {
+    tokio::spawn(async move {
+        while let Some(entry_result) = log_stream.recv().await {
+            if tx.send(entry_result).await.is_err() {
+                break;
+            }
         }
+    });
+}
+
+/// Redact entries of a log stream in a separate task
+fn redact_stream(
+    mut log_stream: mpsc::Receiver<Result<LogEntry>>,
+    redactor: Arc<Redactor>,
+) -> mpsc::Receiver<Result<LogEntry>> {
+    let (tx, rx) = mpsc::channel(REDACT_CHANNEL_SIZE);
 
-        // Buffer logs for each pod
+    tokio::spawn(async move {
         while let Some(entry_result) = log_stream.recv().await {
-            match entry_result {
-                Ok(entry) => {
+            let entry_result = entry_result.map(|mut entry| {
+                redactor.redact_entry(&mut entry);
+                entry
+            });
+            if tx.send(entry_result).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    rx
+}
+
+/// Summarize logs into message templates instead of printing every line
+async fn run_summary(
+    source: &LogSource,
+    filters: &[Box<dyn Filter>],
+    tail: Option<usize>,
+) -> Result<()> {
+    info!("Summarizing logs into templates");
+
+    let mut log_stream = source.stream(false, tail).await;
+    let mut miner = TemplateMiner::new();
+
+    while let Some(entry_result) = log_stream.recv().await {
+        match entry_result {
+            Ok(entry) => {
+                // Apply filters
+                if !filters.is_empty() && !filters.iter().all(|f| f.apply(&entry)) {
+                    continue;
+                }
+
+                miner.add(&entry);
+            }
+            Err(e) => {
+                error!("Error receiving log entry: {}", e);
+            }
+        }
+    }
+
+    print!("{}", miner.render());
+
+    Ok(())
+}
+
+/// Count log lines per pod and level instead of printing them
+async fn run_stats(
+    source: &LogSource,
+    filters: &[Box<dyn Filter>],
+    follow: bool,
+    tail: Option<usize>,
+) -> Result<()> {
+    info!("Collecting log statistics");
+
+    let mut log_stream = source.stream(follow, tail).await;
+    let mut stats = StatsCollector::new();
+    let start = Instant::now();
+
+    // Refresh the table in place while following, indicatif hides it when not on a terminal
+    let progress = if follow {
+        let progress = ProgressBar::new_spinner();
+        progress.set_style(ProgressStyle::with_template("{msg}")?);
+        Some(progress)
+    } else {
+        None
+    };
+    let mut ticker = interval(STATS_REFRESH_INTERVAL);
+
+    loop {
+        tokio::select! {
+            entry_result = log_stream.recv() => match entry_result {
+                Some(Ok(entry)) => {
                     // Apply filters
                     if !filters.is_empty() && !filters.iter().all(|f| f.apply(&entry)) {
                         continue;
                     }
 
-                    // Add to the appropriate pod buffer
-                    if let Some(buffer) = pod_buffers.get_mut(&entry.pod_name) {
-                        buffer.push(entry);
-                    }
+                    stats.record(&entry);
                 }
-                Err(e) => {
+                Some(Err(e)) => {
                     error!("Error receiving log entry: {}", e);
                 }
+                None => break,
+            },
+            _ = ticker.tick(), if progress.is_some() => {
+                if let Some(progress) = &progress {
+                    progress.set_message(stats.render(start.elapsed()));
+                }
             }
+            // Print the final table when following is interrupted
+            _ = tokio::signal::ctrl_c(), if follow => break,
         }
+    }
 
-        // Display the buffered logs for each pod
-        for (pod_name, buffer) in pod_buffers {
-            if !buffer.is_empty() {
-                info!("Logs for pod {}:", pod_name);
+    if let Some(progress) = progress {
+        progress.finish_and_clear();
+    }
+    println!("{}", stats.render(start.elapsed()));
 
-                // If we have more logs than tail_count, only show the last tail_count logs
-                let logs_to_display = if buffer.len() > tail_count {
-                    &buffer[buffer.len() - tail_count..]
-                } else {
-                    &buffer[..]
-                };
+    Ok(())
+}
+
+/// Follow logs until the wait tracker decides the outcome
+async fn run_wait(
+    source: &LogSource,
+    filters: &[Box<dyn Filter>],
+    pipeline: &mut Pipeline,
+    formatter: &mut LogFormatter,
+    tail: Option<usize>,
+    mut tracker: WaitTracker,
+    mut starting: StartingPods,
+) -> Result<WaitOutcome> {
+    info!(
+        "Waiting for {} pods, {} of them still starting",
+        source.pods.len() + starting.pods.len(),
+        starting.pods.len()
+    );
+
+    // Streams of pods that finish starting are attached to the same channel,
+    // which stays open until no pod is left starting
+    let (tx, mut log_stream) = mpsc::channel(WAIT_CHANNEL_SIZE);
+    forward_stream(source.stream(true, tail).await, tx.clone());
+    let mut starting_tx = (!starting.pods.is_empty()).then_some(tx);
+    let mut refresh = interval(POD_REFRESH_INTERVAL);
+    let mut ticker = interval(STAGE_TICK_INTERVAL);
+    let timeout = sleep(tracker.timeout().unwrap_or(Duration::MAX));
+    tokio::pin!(timeout);
+
+    let outcome = loop {
+        tokio::select! {
+            entry_result = log_stream.recv() => match entry_result {
+                Some(Ok(entry)) => {
+                    // Wait patterns see every line, not only the displayed ones
+                    let decided = tracker.observe(&entry);
+
+                    if filters.is_empty() || filters.iter().all(|f| f.apply(&entry)) {
+                        display_entries(formatter, pipeline.process(entry));
+                    }
+                    if log_stream.is_empty() {
+                        formatter.flush_terminal()?;
+                    }
 
-                for entry in logs_to_display {
-                    if let Err(e) = formatter.format_colored(entry) {
-                        error!("Failed to format log entry: {}", e);
+                    if let Some(outcome) = decided {
+                        break outcome;
                     }
                 }
+                Some(Err(e)) => {
+                    error!("Error receiving log entry: {}", e);
+                }
+                None => break tracker.finish(false),
+            },
+            _ = ticker.tick() => {
+                display_entries(formatter, pipeline.tick());
+                formatter.flush()?;
             }
+            _ = refresh.tick(), if starting_tx.is_some() => {
+                for pod in starting.take_started(&source.client).await {
+                    info!("Pod {} started, following its logs", pod.name);
+                    let started = LogSource {
+                        client: source.client.clone(),
+                        pods: vec![pod],
+                        redactor: source.redactor.clone(),
+                    };
+                    if let Some(tx) = &starting_tx {
+                        forward_stream(started.stream(true, tail).await, tx.clone());
+                    }
+                }
+                if starting.pods.is_empty() {
+                    starting_tx = None;
+                }
+            }
+            _ = &mut timeout, if tracker.timeout().is_some() => break tracker.finish(true),
         }
-    } else {
-        // Display logs in real-time
+    };
+
+    // Flush entries still held by the processing stages
+    display_entries(formatter, pipeline.finish());
+
+    eprintln!("{}", tracker.report(outcome));
+    Ok(outcome)
+}
+
+// Unified log running function, replacing the previous three functions
+async fn run_logs(
+    source: &LogSource,
+    filters: &[Box<dyn Filter>],
+    pipeline: &mut Pipeline,
+    formatter: &mut LogFormatter,
+    options: &DisplayOptions,
+) -> Result<()> {
+    let DisplayOptions { follow, tail, .. } = *options;
+
+    // Log mode information
+    match (follow, tail) {
+        (true, Some(count)) => {
+            info!(
+                "Displaying the last {} log entries and following new ones",
+                count
+            );
+        }
+        (false, Some(count)) => {
+            info!("Displaying the last {} log entries", count);
+        }
+        (false, None) if options.tail_total.is_some() => {
+            info!(
+                "Displaying the last {} log entries across all pods",
+                options.tail_total.unwrap_or_default()
+            );
+        }
+        (true, None) => {
+            info!("Following logs in real-time");
+        }
+        (false, None) => {
+            info!("Displaying all available logs");
+        }
+    }
+
+    // Get the log stream
+    let mut log_stream = source.stream(follow, tail).await;
+
+    if !follow {
+        // Collect each container's stream, which arrives in timestamp order. With a
+        // tail, only the most recent entries of each stream are kept
+        let capacity = match (tail, options.tail_total) {
+            (Some(tail), Some(total)) => Some(tail.min(total)),
+            (tail, total) => tail.or(total),
+        };
+        let mut buffers = StreamBuffers::new(capacity);
+
         while let Some(entry_result) = log_stream.recv().await {
             match entry_result {
                 Ok(entry) => {
@@ -299,17 +857,98 @@ async fn run_logs(
                         continue;
                     }
 
-                    // Display the log entry
-                    if let Err(e) = formatter.format_colored(&entry) {
-                        error!("Failed to format log entry: {}", e);
-                    }
+                    buffers.push(entry)?;
                 }
                 Err(e) => {
                     error!("Error receiving log entry: {}", e);
                 }
             }
         }
+        let streams = buffers.into_streams()?;
+
+        if let Some(tail_total) = options.tail_total {
+            // Only show the last tail_total logs across all pods
+            let mut entries = last_entries(merge_streams(streams.into_values()), tail_total);
+            if options.group_by_pod {
+                entries.sort_by(|a, b| a.pod_name.cmp(&b.pod_name));
+            }
+
+            let mut current_pod: Option<Arc<str>> = None;
+            for entry in entries {
+                if options.group_by_pod && current_pod.as_ref() != Some(&entry.pod_name) {
+                    info!("Logs for pod {}:", entry.pod_name);
+                    current_pod = Some(entry.pod_name.clone());
+                }
+                display_entries(formatter, pipeline.process(entry));
+            }
+        } else if options.group_by_pod {
+            // Display the logs of each pod in turn, in pod name order
+            let mut pods: BTreeMap<Arc<str>, Vec<BufferedStream>> = BTreeMap::new();
+            for ((pod_name, _), stream) in streams {
+                pods.entry(pod_name).or_default().push(stream);
+            }
+            for (pod_name, streams) in pods {
+                info!("Logs for pod {}:", pod_name);
+                for entry in merge_streams(streams) {
+                    display_entries(formatter, pipeline.process(entry));
+                }
+            }
+        } else {
+            // Display all pods as one stream ordered by timestamp
+            for entry in merge_streams(streams.into_values()) {
+                display_entries(formatter, pipeline.process(entry));
+            }
+        }
+    } else {
+        // Display logs in real-time, letting stages emit held entries periodically
+        let mut ticker = interval(STAGE_TICK_INTERVAL);
+
+        loop {
+            tokio::select! {
+                entry_result = log_stream.recv() => match entry_result {
+                    Some(Ok(entry)) => {
+                        // Apply filters and display the log entry
+                        if filters.is_empty() || filters.iter().all(|f| f.apply(&entry)) {
+                            display_entries(formatter, pipeline.process(entry));
+                        }
+
+                        // Show buffered lines before waiting for more
+                        if log_stream.is_empty() {
+                            formatter.flush_terminal()?;
+                        }
+                    }
+                    Some(Err(e)) => {
+                        error!("Error receiving log entry: {}", e);
+                    }
+                    None => break,
+                },
+                _ = ticker.tick() => {
+                    display_entries(formatter, pipeline.tick());
+                    formatter.flush()?;
+                }
+                // Stop cleanly so held entries and the output file are flushed
+                _ = tokio::signal::ctrl_c() => break,
+            }
+        }
     }
 
+    // Flush entries still held by the processing stages
+    display_entries(formatter, pipeline.finish());
+    formatter.flush()?;
+    report_output_dir(formatter);
+    formatter.close_pager()?;
+
     Ok(())
 }
+
+/// Tell where the logs went when they were written to a directory
+fn report_output_dir(formatter: &LogFormatter) {
+    if let Some(writer) = formatter.output_dir() {
+        eprintln!(
+            "Wrote {} lines to {} files in {}",
+            writer.line_count(),
+            writer.file_count(),
+            writer.dir().display()
+        );
+    }
+}
@@ -0,0 +1,9 @@
+pub mod pager;
+pub mod rotate;
+pub mod sink;
+pub mod split;
+
+pub use pager::Pager;
+pub use rotate::{Compression, RotatingFile, RotationPolicy};
+pub use sink::OutputSink;
+pub use split::SplitWriter;
@@ -0,0 +1,222 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::io::{self, BufWriter, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use termcolor::{Ansi, ColorChoice, ColorSpec, NoColor, WriteColor};
+use tracing::debug;
+
+/// Pager used when neither `KLOGRS_PAGER` nor `PAGER` is set
+const DEFAULT_PAGER: &str = "less";
+
+/// Options for less when `LESS` is not set: quit if the output fits on one
+/// screen, keep colors and don't clear the screen on exit
+const DEFAULT_LESS: &str = "FRX";
+
+/// Get the pager command from `KLOGRS_PAGER` or `PAGER`, `less` by default
+///
+/// Returns `None` when paging is disabled with an empty value or `cat`.
+pub fn pager_command(klogrs_pager: Option<String>, pager: Option<String>) -> Option<String> {
+    let command = klogrs_pager
+        .or(pager)
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    match command.trim() {
+        "" | "cat" => None,
+        command => Some(command.to_string()),
+    }
+}
+
+/// Writer for the pager's stdin, with or without colors
+enum PagerInput {
+    Ansi(Ansi<BufWriter<ChildStdin>>),
+    NoColor(NoColor<BufWriter<ChildStdin>>),
+}
+
+/// Output piped through a pager process
+///
+/// Once the pager exits, e.g. because the user quit it, further output is
+/// discarded instead of failing with a broken pipe.
+pub struct Pager {
+    command: String,
+    child: Child,
+    input: Option<PagerInput>,
+}
+
+impl Pager {
+    /// Start the pager configured in the environment, if paging is enabled
+    pub fn from_env(color_choice: ColorChoice) -> Result<Option<Self>> {
+        let command = pager_command(env::var("KLOGRS_PAGER").ok(), env::var("PAGER").ok());
+        command
+            .map(|command| Self::spawn(&command, color_choice))
+            .transpose()
+    }
+
+    /// Start a pager command, writing colors unless they are disabled
+    pub fn spawn(command: &str, color_choice: ColorChoice) -> Result<Self> {
+        debug!("Starting pager: {}", command);
+
+        #[cfg(target_os = "windows")]
+        let mut cmd = Command::new("cmd");
+        #[cfg(target_os = "windows")]
+        cmd.args(["/C", command]);
+
+        #[cfg(not(target_os = "windows"))]
+        let mut cmd = Command::new("sh");
+        #[cfg(not(target_os = "windows"))]
+        cmd.args(["-c", command]);
+
+        if env::var_os("LESS").is_none() {
+            cmd.env("LESS", DEFAULT_LESS);
+        }
+        let mut child = cmd
+            .stdin(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to start pager: {}", command))?;
+
+        let stdin = BufWriter::new(child.stdin.take().expect("stdin is piped"));
+        let input = match color_choice {
+            ColorChoice::Never => PagerInput::NoColor(NoColor::new(stdin)),
+            _ => PagerInput::Ansi(Ansi::new(stdin)),
+        };
+        Ok(Self {
+            command: command.to_string(),
+            child,
+            input: Some(input),
+        })
+    }
+
+    /// Run an operation on the pager's stdin, discarding output once the pager exited
+    fn with_input<T: Default>(
+        &mut self,
+        operation: impl FnOnce(&mut PagerInput) -> io::Result<T>,
+    ) -> io::Result<T> {
+        let Some(input) = self.input.as_mut() else {
+            return Ok(T::default());
+        };
+        match operation(input) {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                debug!("Pager exited, discarding further output");
+                self.input = None;
+                Ok(T::default())
+            }
+            result => result,
+        }
+    }
+
+    /// Check whether the pager still accepts output
+    pub fn is_open(&self) -> bool {
+        self.input.is_some()
+    }
+
+    /// Close the pager's input and wait for the user to quit it
+    pub fn wait(mut self) -> Result<()> {
+        // A pager that already exited discards the rest
+        self.flush()?;
+        self.input = None;
+        self.child
+            .wait()
+            .with_context(|| format!("Failed to wait for pager: {}", self.command))?;
+        Ok(())
+    }
+}
+
+impl Write for Pager {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len();
+        self.with_input(|input| match input {
+            PagerInput::Ansi(out) => out.write_all(buf),
+            PagerInput::NoColor(out) => out.write_all(buf),
+        })?;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.with_input(|input| match input {
+            PagerInput::Ansi(out) => out.flush(),
+            PagerInput::NoColor(out) => out.flush(),
+        })
+    }
+}
+
+impl WriteColor for Pager {
+    fn supports_color(&self) -> bool {
+        matches!(self.input, Some(PagerInput::Ansi(_)))
+    }
+
+    fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
+        self.with_input(|input| match input {
+            PagerInput::Ansi(out) => out.set_color(spec),
+            PagerInput::NoColor(out) => out.set_color(spec),
+        })
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        self.with_input(|input| match input {
+            PagerInput::Ansi(out) => out.reset(),
+            PagerInput::NoColor(out) => out.reset(),
+        })
+    }
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        // Close stdin so the pager sees the end of the output
+        self.input = None;
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use termcolor::Color;
+
+    #[test]
+    fn test_pager_command() {
+        assert_eq!(pager_command(None, None).as_deref(), Some("less"));
+        assert_eq!(
+            pager_command(None, Some("most".to_string())).as_deref(),
+            Some("most")
+        );
+        assert_eq!(
+            pager_command(Some("less -S".to_string()), Some("most".to_string())).as_deref(),
+            Some("less -S")
+        );
+        assert_eq!(pager_command(None, Some("cat".to_string())), None);
+        assert_eq!(pager_command(Some(" ".to_string()), None), None);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_pager_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("paged");
+        let command = format!("cat > '{}'", path.display());
+
+        let mut pager = Pager::spawn(&command, ColorChoice::Always).unwrap();
+        assert!(pager.supports_color());
+        pager
+            .set_color(ColorSpec::new().set_fg(Some(Color::Red)))
+            .unwrap();
+        write!(pager, "error").unwrap();
+        pager.reset().unwrap();
+        writeln!(pager).unwrap();
+        pager.wait().unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "\x1b[0m\x1b[31merror\x1b[0m\n"
+        );
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_pager_quit_early() {
+        // A pager that exits right away must not turn output into errors
+        let mut pager = Pager::spawn("true", ColorChoice::Never).unwrap();
+        for _ in 0..10_000 {
+            writeln!(pager, "a line that nobody reads").unwrap();
+        }
+        pager.flush().unwrap();
+        pager.wait().unwrap();
+    }
+}
@@ -0,0 +1,359 @@
+use anyhow::{anyhow, Context, Result};
+use flate2::write::GzEncoder;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Compression applied to rotated files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Get the file extension of compressed files, including the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip => ".gz",
+            Compression::Zstd => ".zst",
+        }
+    }
+
+    /// Compress a file into another file
+    fn compress(&self, source: &Path, target: &Path) -> Result<()> {
+        let mut input = BufReader::new(File::open(source)?);
+        let output = BufWriter::new(File::create(target)?);
+
+        match self {
+            Compression::None => {
+                let mut output = output;
+                io::copy(&mut input, &mut output)?;
+                output.flush()?;
+            }
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(output, flate2::Compression::default());
+                io::copy(&mut input, &mut encoder)?;
+                encoder.finish()?.flush()?;
+            }
+            Compression::Zstd => {
+                let mut encoder = zstd::Encoder::new(output, zstd::DEFAULT_COMPRESSION_LEVEL)?;
+                io::copy(&mut input, &mut encoder)?;
+                encoder.finish()?.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Compression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Compression::None),
+            "gzip" | "gz" => Ok(Compression::Gzip),
+            "zstd" | "zst" => Ok(Compression::Zstd),
+            _ => Err(anyhow!(
+                "Invalid compression: {} (expected none, gzip or zstd)",
+                s
+            )),
+        }
+    }
+}
+
+/// When and how an output file is rotated
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RotationPolicy {
+    /// Rotate before the file would grow beyond this many bytes
+    pub max_size: Option<u64>,
+    /// Rotate when the file has been written to for this long
+    pub interval: Option<Duration>,
+    /// Compression of rotated files
+    pub compression: Compression,
+    /// Number of rotated files to keep, all are kept when not set
+    pub keep: Option<usize>,
+}
+
+/// Output file that is rotated by size or age
+///
+/// Rotated files are numbered like logrotate does: the newest is `PATH.1`,
+/// older ones have higher numbers, followed by the compression extension.
+/// Compression happens synchronously while rotating.
+pub struct RotatingFile {
+    path: PathBuf,
+    policy: RotationPolicy,
+    writer: BufWriter<File>,
+    /// Bytes in the current file
+    size: u64,
+    /// When the current file was opened
+    opened: Instant,
+    /// Whether entries were written to the current file
+    has_entries: bool,
+    /// Line written at the start of every file, e.g. a CSV header
+    header: Option<String>,
+}
+
+impl RotatingFile {
+    /// Open the output file, appending to it if it exists
+    pub fn open(path: impl Into<PathBuf>, policy: RotationPolicy) -> Result<Self> {
+        let path = path.into();
+        let (file, size) = Self::open_file(&path, true)?;
+        Ok(Self {
+            path,
+            policy,
+            writer: BufWriter::new(file),
+            size,
+            opened: Instant::now(),
+            has_entries: size > 0,
+            header: None,
+        })
+    }
+
+    /// Open a file for appending or truncate it, returning its current size
+    fn open_file(path: &Path, append: bool) -> Result<(File, u64)> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(append)
+            .write(true)
+            .truncate(!append)
+            .open(path)
+            .with_context(|| format!("Failed to open output file {}", path.display()))?;
+        let size = file.metadata()?.len();
+        Ok((file, size))
+    }
+
+    /// Set a line written at the start of every file, written now if the file is empty
+    pub fn with_header(mut self, header: String) -> Result<Self> {
+        if self.size == 0 {
+            self.write_raw(&header)?;
+        }
+        self.header = Some(header);
+        Ok(self)
+    }
+
+    /// Get the path of the current file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write a line, rotating first if needed
+    pub fn write_line(&mut self, line: &str) -> Result<()> {
+        self.write_line_at(line, Instant::now())
+    }
+
+    /// Write a line at the given time, rotating first if needed
+    pub fn write_line_at(&mut self, line: &str, now: Instant) -> Result<()> {
+        if self.should_rotate(line.len() as u64 + 1, now) {
+            self.rotate_at(now)?;
+        }
+        self.write_raw(line)?;
+        self.has_entries = true;
+        Ok(())
+    }
+
+    /// Write a line without rotating
+    fn write_raw(&mut self, line: &str) -> Result<()> {
+        writeln!(self.writer, "{}", line)
+            .with_context(|| format!("Failed to write to {}", self.path.display()))?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    /// Check whether the file has to be rotated before writing more bytes
+    fn should_rotate(&self, additional: u64, now: Instant) -> bool {
+        if !self.has_entries {
+            return false;
+        }
+        let too_large = self
+            .policy
+            .max_size
+            .is_some_and(|max| self.size + additional > max);
+        let too_old = self
+            .policy
+            .interval
+            .is_some_and(|interval| now.duration_since(self.opened) >= interval);
+        too_large || too_old
+    }
+
+    /// Get the path of a rotated file
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = OsString::from(self.path.as_os_str());
+        name.push(format!(".{}{}", index, self.policy.compression.extension()));
+        PathBuf::from(name)
+    }
+
+    /// Flush buffered lines to the file
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer
+            .flush()
+            .with_context(|| format!("Failed to write to {}", self.path.display()))
+    }
+
+    /// Rotate the file now
+    pub fn rotate(&mut self) -> Result<()> {
+        self.rotate_at(Instant::now())
+    }
+
+    /// Move the current file to `PATH.1`, shift older files and start a new file
+    fn rotate_at(&mut self, now: Instant) -> Result<()> {
+        self.flush()?;
+        debug!("Rotating output file {}", self.path.display());
+
+        let mut count = 0;
+        while self.rotated_path(count + 1).exists() {
+            count += 1;
+        }
+
+        // Shift older files, dropping those beyond the retention count
+        for index in (1..=count).rev() {
+            let path = self.rotated_path(index);
+            if self.policy.keep.is_some_and(|keep| index >= keep) {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            } else {
+                fs::rename(&path, self.rotated_path(index + 1))
+                    .with_context(|| format!("Failed to rotate {}", path.display()))?;
+            }
+        }
+
+        if self.policy.keep != Some(0) {
+            let target = self.rotated_path(1);
+            match self.policy.compression {
+                Compression::None => fs::rename(&self.path, &target)
+                    .with_context(|| format!("Failed to rotate {}", self.path.display()))?,
+                compression => compression
+                    .compress(&self.path, &target)
+                    .with_context(|| format!("Failed to compress {}", target.display()))?,
+            }
+        }
+
+        let (file, _) = Self::open_file(&self.path, false)?;
+        self.writer = BufWriter::new(file);
+        self.size = 0;
+        self.opened = now;
+        self.has_entries = false;
+        if let Some(header) = self.header.clone() {
+            self.write_raw(&header)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RotatingFile {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn read(path: PathBuf) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn test_parse_compression() {
+        assert_eq!("gzip".parse::<Compression>().unwrap(), Compression::Gzip);
+        assert_eq!("ZST".parse::<Compression>().unwrap(), Compression::Zstd);
+        assert!("lz4".parse::<Compression>().is_err());
+    }
+
+    #[test]
+    fn test_rotate_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture.log");
+        let policy = RotationPolicy {
+            max_size: Some(12),
+            keep: Some(2),
+            ..Default::default()
+        };
+        let mut file = RotatingFile::open(&path, policy).unwrap();
+
+        for line in ["one", "two", "three", "four", "five", "six", "seven"] {
+            file.write_line(line).unwrap();
+        }
+        file.flush().unwrap();
+
+        assert_eq!(read(path.clone()), "seven\n");
+        assert_eq!(read(dir.path().join("capture.log.1")), "five\nsix\n");
+        assert_eq!(read(dir.path().join("capture.log.2")), "three\nfour\n");
+        // Older files beyond the retention count are removed
+        assert!(!dir.path().join("capture.log.3").exists());
+    }
+
+    #[test]
+    fn test_rotate_by_time_with_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture.csv");
+        let policy = RotationPolicy {
+            interval: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        let mut file = RotatingFile::open(&path, policy)
+            .unwrap()
+            .with_header("a,b".to_string())
+            .unwrap();
+
+        let start = Instant::now();
+        file.write_line_at("1,2", start).unwrap();
+        file.write_line_at("3,4", start + Duration::from_secs(30))
+            .unwrap();
+        file.write_line_at("5,6", start + Duration::from_secs(61))
+            .unwrap();
+        file.flush().unwrap();
+
+        assert_eq!(read(dir.path().join("capture.csv.1")), "a,b\n1,2\n3,4\n");
+        assert_eq!(read(path), "a,b\n5,6\n");
+    }
+
+    #[test]
+    fn test_rotate_compressed() {
+        let dir = tempfile::tempdir().unwrap();
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            let path = dir.path().join(format!("{:?}.log", compression));
+            let policy = RotationPolicy {
+                compression,
+                ..Default::default()
+            };
+            let mut file = RotatingFile::open(&path, policy).unwrap();
+            file.write_line("hello").unwrap();
+            file.rotate().unwrap();
+            file.write_line("world").unwrap();
+            file.flush().unwrap();
+
+            let rotated = dir.path().join(format!(
+                "{:?}.log.1{}",
+                compression,
+                compression.extension()
+            ));
+            let input = File::open(&rotated).unwrap();
+            let mut content = String::new();
+            match compression {
+                Compression::Gzip => {
+                    flate2::read::GzDecoder::new(input)
+                        .read_to_string(&mut content)
+                        .unwrap();
+                }
+                _ => {
+                    zstd::Decoder::new(input)
+                        .unwrap()
+                        .read_to_string(&mut content)
+                        .unwrap();
+                }
+            }
+            assert_eq!(content, "hello\n");
+            assert_eq!(read(path), "world\n");
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::io::{self, BufWriter, StdoutLock, Write};
+use termcolor::{Ansi, ColorChoice, ColorSpec, NoColor, WriteColor};
+
+/// Size of the output buffer, written out when full or on flush
+pub const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Buffered writer, with or without colors
+enum SinkOutput<W: Write> {
+    Ansi(Ansi<BufWriter<W>>),
+    NoColor(NoColor<BufWriter<W>>),
+}
+
+/// Buffered terminal output
+///
+/// Holds the stdout lock and a large buffer for the whole run, so lines are
+/// written in batches instead of a write for every colored span. Output only
+/// shows up when the buffer fills or is flushed, so callers flush whenever
+/// they are about to wait, e.g. for more lines to follow.
+pub struct OutputSink<W: Write = StdoutLock<'static>> {
+    output: SinkOutput<W>,
+}
+
+impl OutputSink {
+    /// Lock stdout for buffered output, writing colors unless they are disabled
+    pub fn stdout(color_choice: ColorChoice) -> Self {
+        Self::new(io::stdout().lock(), color_choice)
+    }
+}
+
+impl<W: Write> OutputSink<W> {
+    /// Buffer output to a writer, writing colors unless they are disabled
+    pub fn new(inner: W, color_choice: ColorChoice) -> Self {
+        let inner = BufWriter::with_capacity(BUFFER_SIZE, inner);
+        let output = match color_choice {
+            ColorChoice::Never => SinkOutput::NoColor(NoColor::new(inner)),
+            _ => SinkOutput::Ansi(Ansi::new(inner)),
+        };
+        Self { output }
+    }
+
+    /// Get the number of bytes waiting to be written
+    pub fn buffered(&self) -> usize {
+        match &self.output {
+            SinkOutput::Ansi(out) => out.get_ref().buffer().len(),
+            SinkOutput::NoColor(out) => out.get_ref().buffer().len(),
+        }
+    }
+
+    /// Flush the buffer and get the writer back
+    pub fn into_inner(self) -> io::Result<W> {
+        let inner = match self.output {
+            SinkOutput::Ansi(out) => out.into_inner(),
+            SinkOutput::NoColor(out) => out.into_inner(),
+        };
+        inner.into_inner().map_err(|e| e.into_error())
+    }
+}
+
+impl<W: Write> Write for OutputSink<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.output {
+            SinkOutput::Ansi(out) => out.write(buf),
+            SinkOutput::NoColor(out) => out.write(buf),
+        }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        match &mut self.output {
+            SinkOutput::Ansi(out) => out.write_all(buf),
+            SinkOutput::NoColor(out) => out.write_all(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.output {
+            SinkOutput::Ansi(out) => out.flush(),
+            SinkOutput::NoColor(out) => out.flush(),
+        }
+    }
+}
+
+impl<W: Write> WriteColor for OutputSink<W> {
+    fn supports_color(&self) -> bool {
+        matches!(self.output, SinkOutput::Ansi(_))
+    }
+
+    fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
+        match &mut self.output {
+            SinkOutput::Ansi(out) => out.set_color(spec),
+            SinkOutput::NoColor(out) => out.set_color(spec),
+        }
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        match &mut self.output {
+            SinkOutput::Ansi(out) => out.reset(),
+            SinkOutput::NoColor(out) => out.reset(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use termcolor::Color;
+
+    #[test]
+    fn test_output_is_buffered_until_flushed() {
+        let mut sink = OutputSink::new(Vec::new(), ColorChoice::Never);
+        writeln!(sink, "first line").unwrap();
+        assert_eq!(sink.buffered(), "first line\n".len());
+
+        sink.flush().unwrap();
+        assert_eq!(sink.buffered(), 0);
+        writeln!(sink, "second line").unwrap();
+        assert_eq!(sink.into_inner().unwrap(), b"first line\nsecond line\n");
+    }
+
+    #[test]
+    fn test_colors() {
+        let mut red = ColorSpec::new();
+        red.set_fg(Some(Color::Red));
+
+        let mut sink = OutputSink::new(Vec::new(), ColorChoice::Always);
+        assert!(sink.supports_color());
+        sink.set_color(&red).unwrap();
+        write!(sink, "error").unwrap();
+        sink.reset().unwrap();
+        assert_eq!(sink.into_inner().unwrap(), b"\x1b[0m\x1b[31merror\x1b[0m");
+
+        let mut sink = OutputSink::new(Vec::new(), ColorChoice::Never);
+        assert!(!sink.supports_color());
+        sink.set_color(&red).unwrap();
+        write!(sink, "error").unwrap();
+        sink.reset().unwrap();
+        assert_eq!(sink.into_inner().unwrap(), b"error");
+    }
+
+    #[test]
+    fn test_full_buffer_is_written() {
+        let mut sink = OutputSink::new(Vec::new(), ColorChoice::Never);
+        let line = "x".repeat(1023) + "\n";
+        for _ in 0..(BUFFER_SIZE / line.len() + 1) {
+            sink.write_all(line.as_bytes()).unwrap();
+        }
+        assert!(sink.buffered() < BUFFER_SIZE);
+    }
+}
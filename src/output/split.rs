@@ -0,0 +1,366 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::debug;
+
+use crate::kubernetes::LogEntry;
+
+/// Name of the manifest written to the output directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Identifies one container stream: namespace, pod and container
+type StreamKey = (Arc<str>, Arc<str>, Arc<str>);
+
+/// Open file and counters of one container stream
+struct Stream {
+    /// File path relative to the output directory
+    file: PathBuf,
+    writer: BufWriter<File>,
+    lines: u64,
+    first: Option<DateTime<Utc>>,
+    last: Option<DateTime<Utc>>,
+}
+
+/// Entry of the manifest describing one file
+#[derive(Debug, Serialize)]
+struct ManifestEntry<'a> {
+    namespace: &'a str,
+    pod: &'a str,
+    container: &'a str,
+    file: String,
+    lines: u64,
+    first: Option<DateTime<Utc>>,
+    last: Option<DateTime<Utc>>,
+}
+
+/// Manifest listing the files in the output directory
+#[derive(Debug, Serialize)]
+struct Manifest<'a> {
+    pods: Vec<&'a str>,
+    lines: u64,
+    first: Option<DateTime<Utc>>,
+    last: Option<DateTime<Utc>>,
+    files: Vec<ManifestEntry<'a>>,
+}
+
+/// Writes each container stream to its own file below a directory
+///
+/// Files are named `NAMESPACE/POD/CONTAINER.log` and replace files of
+/// earlier runs. A `manifest.json` lists the files with their line counts
+/// and time ranges.
+pub struct SplitWriter {
+    dir: PathBuf,
+    streams: BTreeMap<StreamKey, Stream>,
+    /// Line written at the start of every file, e.g. a CSV header
+    header: Option<String>,
+    /// Whether lines were written since the manifest was last written
+    dirty: bool,
+}
+
+/// Make a name safe to use as a single path component
+fn path_component(name: &str) -> String {
+    match name {
+        "" | "." | ".." => "_".to_string(),
+        _ => name.replace(['/', '\\'], "_"),
+    }
+}
+
+impl SplitWriter {
+    /// Create the output directory if needed
+    pub fn create(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create output directory {}", dir.display()))?;
+        Ok(Self {
+            dir,
+            streams: BTreeMap::new(),
+            header: None,
+            dirty: true,
+        })
+    }
+
+    /// Set a line written at the start of every file
+    pub fn with_header(mut self, header: String) -> Self {
+        self.header = Some(header);
+        self
+    }
+
+    /// Get the output directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Write a formatted line to the file of the entry's container
+    ///
+    /// Merged duplicates go to the file of each of their pods. Notes from
+    /// klogrs itself don't belong to a container and are skipped.
+    pub fn write_line(&mut self, entry: &LogEntry, line: &str) -> Result<()> {
+        for pod in entry.pods() {
+            let key = (
+                entry.namespace.clone(),
+                pod.clone(),
+                entry.container_name.clone(),
+            );
+            self.write_stream(key, entry, line)?;
+        }
+        Ok(())
+    }
+
+    /// Write a formatted line to the file of a container stream
+    fn write_stream(&mut self, key: StreamKey, entry: &LogEntry, line: &str) -> Result<()> {
+        if !self.streams.contains_key(&key) {
+            let stream = self.open_stream(&key)?;
+            self.streams.insert(key.clone(), stream);
+        }
+        let stream = self.streams.get_mut(&key).expect("stream was just opened");
+
+        writeln!(stream.writer, "{}", line)
+            .with_context(|| format!("Failed to write to {}", stream.file.display()))?;
+        stream.lines += 1;
+        if let Some(timestamp) = entry.timestamp {
+            stream.first = Some(stream.first.map_or(timestamp, |first| first.min(timestamp)));
+            stream.last = Some(stream.last.map_or(timestamp, |last| last.max(timestamp)));
+        }
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Create the file of a container stream
+    fn open_stream(&self, (namespace, pod, container): &StreamKey) -> Result<Stream> {
+        let file = Path::new(&path_component(namespace))
+            .join(path_component(pod))
+            .join(format!("{}.log", path_component(container)));
+        let path = self.dir.join(&file);
+        debug!(
+            "Writing {}/{}/{} to {}",
+            namespace,
+            pod,
+            container,
+            path.display()
+        );
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+        let mut writer = BufWriter::new(
+            File::create(&path)
+                .with_context(|| format!("Failed to create output file {}", path.display()))?,
+        );
+        if let Some(header) = &self.header {
+            writeln!(writer, "{}", header)?;
+        }
+        Ok(Stream {
+            file,
+            writer,
+            lines: 0,
+            first: None,
+            last: None,
+        })
+    }
+
+    /// Get the number of files written
+    pub fn file_count(&self) -> usize {
+        self.streams.len()
+    }
+
+    /// Get the number of lines written to all files
+    pub fn line_count(&self) -> u64 {
+        self.streams.values().map(|stream| stream.lines).sum()
+    }
+
+    /// Flush all files and update the manifest if lines were written
+    pub fn flush(&mut self) -> Result<()> {
+        for stream in self.streams.values_mut() {
+            stream
+                .writer
+                .flush()
+                .with_context(|| format!("Failed to write to {}", stream.file.display()))?;
+        }
+        if self.dirty {
+            self.write_manifest()?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Write the manifest listing all files
+    fn write_manifest(&self) -> Result<()> {
+        let files: Vec<ManifestEntry> = self
+            .streams
+            .iter()
+            .map(|((namespace, pod, container), stream)| ManifestEntry {
+                namespace,
+                pod,
+                container,
+                file: stream.file.to_string_lossy().replace('\\', "/"),
+                lines: stream.lines,
+                first: stream.first,
+                last: stream.last,
+            })
+            .collect();
+
+        let mut pods: Vec<&str> = files.iter().map(|file| file.pod).collect();
+        // Files are ordered by namespace first, so equal pod names aren't adjacent
+        pods.sort_unstable();
+        pods.dedup();
+        let manifest = Manifest {
+            pods,
+            lines: self.line_count(),
+            first: files.iter().filter_map(|file| file.first).min(),
+            last: files.iter().filter_map(|file| file.last).max(),
+            files,
+        };
+
+        // Write to a temporary file first so the manifest is never half written
+        let path = self.dir.join(MANIFEST_FILE);
+        let temp = self.dir.join(format!("{}.tmp", MANIFEST_FILE));
+        fs::write(&temp, serde_json::to_string_pretty(&manifest)? + "\n")
+            .with_context(|| format!("Failed to write {}", temp.display()))?;
+        fs::rename(&temp, &path).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+}
+
+impl Drop for SplitWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kubernetes::Source;
+    use crate::log_processor::{DedupStage, Stage};
+    use serde_json::Value;
+    use std::time::Duration;
+
+    fn entry(namespace: &str, pod: &str, container: &str, line: &str) -> LogEntry {
+        LogEntry::parse(namespace, pod, container, line.to_string())
+    }
+
+    #[test]
+    fn test_path_component() {
+        assert_eq!(path_component("web-1"), "web-1");
+        assert_eq!(path_component("a/b"), "a_b");
+        assert_eq!(path_component(".."), "_");
+        assert_eq!(path_component(""), "_");
+    }
+
+    #[test]
+    fn test_split_writer() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = SplitWriter::create(dir.path().join("incident")).unwrap();
+
+        for (pod, container, line) in [
+            ("web-1", "app", "2024-05-01T10:00:05Z started"),
+            ("web-2", "app", "2024-05-01T10:00:01Z started"),
+            ("web-1", "sidecar", "2024-05-01T10:00:02Z ready"),
+            ("web-1", "app", "2024-05-01T10:00:09Z stopped"),
+        ] {
+            let entry = entry("shop", pod, container, line);
+            writer.write_line(&entry, entry.message()).unwrap();
+        }
+        writer.flush().unwrap();
+
+        assert_eq!(writer.file_count(), 3);
+        assert_eq!(writer.line_count(), 4);
+        let root = dir.path().join("incident");
+        assert_eq!(
+            fs::read_to_string(root.join("shop/web-1/app.log")).unwrap(),
+            "started\nstopped\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("shop/web-1/sidecar.log")).unwrap(),
+            "ready\n"
+        );
+
+        let manifest: Value =
+            serde_json::from_str(&fs::read_to_string(root.join(MANIFEST_FILE)).unwrap()).unwrap();
+        assert_eq!(manifest["pods"], serde_json::json!(["web-1", "web-2"]));
+        assert_eq!(manifest["lines"], 4);
+        assert_eq!(manifest["first"], "2024-05-01T10:00:01Z");
+        assert_eq!(manifest["last"], "2024-05-01T10:00:09Z");
+        assert_eq!(manifest["files"][0]["file"], "shop/web-1/app.log");
+        assert_eq!(manifest["files"][0]["lines"], 2);
+        assert_eq!(manifest["files"][0]["first"], "2024-05-01T10:00:05Z");
+        assert_eq!(manifest["files"][2]["pod"], "web-2");
+    }
+
+    #[test]
+    fn test_split_writer_manifest_pods() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = SplitWriter::create(dir.path()).unwrap();
+
+        for (namespace, pod) in [("shop", "web-1"), ("api", "web-1"), ("api", "db-1")] {
+            let entry = entry(namespace, pod, "app", "started");
+            writer.write_line(&entry, entry.message()).unwrap();
+        }
+        let note =
+            LogEntry::new("", "klogrs", "", "throttled".to_string()).with_source(Source::Note);
+        writer.write_line(&note, note.message()).unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(writer.file_count(), 3);
+        assert!(!dir.path().join("_").exists());
+        let manifest: Value =
+            serde_json::from_str(&fs::read_to_string(dir.path().join(MANIFEST_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(manifest["pods"], serde_json::json!(["db-1", "web-1"]));
+        assert_eq!(manifest["lines"], 3);
+    }
+
+    #[test]
+    fn test_split_writer_dedup() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = SplitWriter::create(dir.path()).unwrap();
+        let mut stage = DedupStage::new(false, Some(Duration::from_secs(1)));
+
+        let mut entries = Vec::new();
+        for pod in ["web-1", "web-2"] {
+            let line = entry("shop", pod, "app", "2024-05-01T10:00:00Z config reloaded");
+            entries.extend(stage.process(line));
+        }
+        entries.extend(stage.finish());
+        assert_eq!(entries.len(), 1);
+        for entry in &entries {
+            writer.write_line(entry, entry.message()).unwrap();
+        }
+        writer.flush().unwrap();
+
+        // The merged line is written to the file of each pod
+        for pod in ["web-1", "web-2"] {
+            assert_eq!(
+                fs::read_to_string(dir.path().join(format!("shop/{}/app.log", pod))).unwrap(),
+                "config reloaded\n"
+            );
+        }
+        let manifest: Value =
+            serde_json::from_str(&fs::read_to_string(dir.path().join(MANIFEST_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(manifest["pods"], serde_json::json!(["web-1", "web-2"]));
+    }
+
+    #[test]
+    fn test_split_writer_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = SplitWriter::create(dir.path())
+            .unwrap()
+            .with_header("a,b".to_string());
+        writer
+            .write_line(&entry("shop", "web-1", "app", "no timestamp"), "1,2")
+            .unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dir.path().join("shop/web-1/app.log")).unwrap(),
+            "a,b\n1,2\n"
+        );
+    }
+}
@@ -0,0 +1,551 @@
+use std::collections::{HashSet, VecDeque};
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use termcolor::{ColorSpec, WriteColor};
+
+use crate::kubernetes::{LogEntry, PodInfo};
+use crate::log_processor::filter::Filter;
+use crate::log_processor::LogFormatter;
+
+/// Number of lines kept for scrollback
+pub const SCROLLBACK: usize = 10_000;
+
+/// Levels that can be toggled with the keys 1 to 6
+pub const LEVELS: [&str; 6] = ["TRACE", "DEBUG", "INFO", "WARN", "ERROR", "FATAL"];
+
+/// Run of text with one style
+pub type Span = (String, ColorSpec);
+
+/// Records the styled output of the formatter as rows of spans
+#[derive(Debug, Default)]
+pub struct SpanRecorder {
+    rows: Vec<Vec<Span>>,
+    spec: ColorSpec,
+}
+
+impl SpanRecorder {
+    /// Get the recorded rows, without the row after the final newline
+    pub fn into_rows(mut self) -> Vec<Vec<Span>> {
+        if self.rows.last().is_some_and(Vec::is_empty) {
+            self.rows.pop();
+        }
+        self.rows
+    }
+
+    /// Append text to the current row, merging it with a run of the same style
+    fn push(&mut self, text: &str) {
+        if self.rows.is_empty() {
+            self.rows.push(Vec::new());
+        }
+        let row = self.rows.last_mut().expect("a row exists");
+        match row.last_mut() {
+            Some((last, spec)) if *spec == self.spec => last.push_str(text),
+            _ => row.push((text.to_string(), self.spec.clone())),
+        }
+    }
+}
+
+impl Write for SpanRecorder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        for (i, part) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.rows.push(Vec::new());
+            }
+            if !part.is_empty() {
+                self.push(part);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl WriteColor for SpanRecorder {
+    fn supports_color(&self) -> bool {
+        true
+    }
+
+    fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
+        self.spec = spec.clone();
+        Ok(())
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        self.spec = ColorSpec::new();
+        Ok(())
+    }
+}
+
+/// Formatted log line kept for display
+#[derive(Debug, Clone)]
+pub struct Line {
+    pub pod: Arc<str>,
+    pub level: Option<&'static str>,
+    /// Lowercase plain text, for searching
+    text: String,
+    /// Styled rows, more than one for pretty-printed JSON
+    pub rows: Vec<Vec<Span>>,
+    /// Whether the line passes the filters from the command line
+    matches_filters: bool,
+}
+
+/// What the keyboard is currently used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputMode {
+    Normal,
+    /// Typing a search query
+    Search,
+}
+
+/// State of the full-screen UI
+pub struct App {
+    formatter: LogFormatter,
+    filters: Vec<Box<dyn Filter>>,
+    /// Lines in arrival order, the oldest are dropped beyond the scrollback
+    lines: VecDeque<Line>,
+    /// Lines that arrived while paused, the oldest are dropped beyond the scrollback
+    pending: VecDeque<Line>,
+    pods: Vec<PodInfo>,
+    hidden_pods: HashSet<String>,
+    hidden_levels: HashSet<&'static str>,
+    /// Whether the filters from the command line are applied
+    filters_enabled: bool,
+    /// Search query, lines not containing it are hidden
+    search: String,
+    mode: InputMode,
+    paused: bool,
+    /// Whether each pod gets its own pane instead of a merged view
+    split: bool,
+    /// Number of rows scrolled up from the end, 0 follows new lines
+    scroll: usize,
+    /// Pod selected in the sidebar
+    selected: usize,
+    /// Rows of the log view, for paging
+    page_size: usize,
+    /// Last error from the log stream
+    error: Option<String>,
+    quit: bool,
+}
+
+impl App {
+    /// Create the UI state for the given pods
+    pub fn new(
+        pods: Vec<PodInfo>,
+        mut formatter: LogFormatter,
+        filters: Vec<Box<dyn Filter>>,
+    ) -> Self {
+        formatter.add_pods(pods.iter().map(|pod| pod.name.as_str()));
+        Self {
+            formatter,
+            filters,
+            lines: VecDeque::new(),
+            pending: VecDeque::new(),
+            pods,
+            hidden_pods: HashSet::new(),
+            hidden_levels: HashSet::new(),
+            filters_enabled: true,
+            search: String::new(),
+            mode: InputMode::Normal,
+            paused: false,
+            split: false,
+            scroll: 0,
+            selected: 0,
+            page_size: 20,
+            error: None,
+            quit: false,
+        }
+    }
+
+    /// Format and add a log entry
+    pub fn push(&mut self, entry: LogEntry) {
+        let mut recorder = SpanRecorder::default();
+        if let Err(e) = self.formatter.write_entry(&mut recorder, &entry) {
+            self.error = Some(format!("Failed to format log entry: {}", e));
+            return;
+        }
+        let rows = recorder.into_rows();
+        let text = rows
+            .iter()
+            .flatten()
+            .map(|(text, _)| text.as_str())
+            .collect::<String>()
+            .to_lowercase();
+
+        let line = Line {
+            level: entry.level(),
+            matches_filters: self.filters.iter().all(|f| f.apply(&entry)),
+            pod: entry.pod_name,
+            text,
+            rows,
+        };
+        if self.paused {
+            self.pending.push_back(line);
+            if self.pending.len() > SCROLLBACK {
+                self.pending.pop_front();
+            }
+        } else {
+            self.append(line);
+        }
+    }
+
+    /// Append a line to the scrollback
+    fn append(&mut self, line: Line) {
+        // Keep the view in place while scrolled back
+        if self.scroll > 0 && self.is_visible(&line) {
+            self.scroll = self.scroll.saturating_add(line.rows.len());
+        }
+        self.lines.push_back(line);
+        if self.lines.len() > SCROLLBACK {
+            self.lines.pop_front();
+        }
+    }
+
+    /// Replace the pods shown in the sidebar
+    pub fn set_pods(&mut self, pods: Vec<PodInfo>) {
+        // Pods that joined get short names that tell them apart
+        self.formatter
+            .add_pods(pods.iter().map(|pod| pod.name.as_str()));
+        self.pods = pods;
+        self.selected = self.selected.min(self.pods.len().saturating_sub(1));
+    }
+
+    /// Show an error from the log stream
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+
+    /// Check whether a line is shown with the current toggles and search
+    fn is_visible(&self, line: &Line) -> bool {
+        !self.hidden_pods.contains(&*line.pod)
+            && line
+                .level
+                .is_none_or(|level| !self.hidden_levels.contains(level))
+            && (!self.filters_enabled || line.matches_filters)
+            && (self.search.is_empty() || line.text.contains(&self.search.to_lowercase()))
+    }
+
+    /// Get the visible lines, optionally of one pod only
+    pub fn visible_lines(&self, pod: Option<&str>) -> Vec<&Line> {
+        self.lines
+            .iter()
+            .filter(|line| pod.is_none_or(|pod| &*line.pod == pod))
+            .filter(|line| self.is_visible(line))
+            .collect()
+    }
+
+    /// Get the pods shown as panes in the split view
+    pub fn visible_pods(&self) -> Vec<&PodInfo> {
+        self.pods
+            .iter()
+            .filter(|pod| !self.hidden_pods.contains(&pod.name))
+            .collect()
+    }
+
+    pub fn pods(&self) -> &[PodInfo] {
+        &self.pods
+    }
+
+    pub fn is_pod_hidden(&self, pod: &str) -> bool {
+        self.hidden_pods.contains(pod)
+    }
+
+    pub fn is_level_hidden(&self, level: &str) -> bool {
+        self.hidden_levels.contains(level)
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn search(&self) -> &str {
+        &self.search
+    }
+
+    pub fn mode(&self) -> InputMode {
+        self.mode
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Get the number of lines that arrived while paused
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_split(&self) -> bool {
+        self.split
+    }
+
+    pub fn filters_enabled(&self) -> bool {
+        self.filters_enabled
+    }
+
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Limit scrolling to the given number of rows above the end
+    pub fn clamp_scroll(&mut self, max: usize) {
+        self.scroll = self.scroll.min(max);
+    }
+
+    /// Remember the height of the log view for paging
+    pub fn set_page_size(&mut self, rows: usize) {
+        self.page_size = rows.max(1);
+    }
+
+    /// Pause or resume the stream, adding the lines that arrived in between
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        if !self.paused {
+            for line in std::mem::take(&mut self.pending) {
+                self.append(line);
+            }
+        }
+    }
+
+    /// Handle a key press
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.quit = true;
+            return;
+        }
+
+        if self.mode == InputMode::Search {
+            match key.code {
+                KeyCode::Enter => self.mode = InputMode::Normal,
+                KeyCode::Esc => {
+                    self.search.clear();
+                    self.mode = InputMode::Normal;
+                }
+                KeyCode::Backspace => {
+                    self.search.pop();
+                }
+                KeyCode::Char(c) => self.search.push(c),
+                _ => {}
+            }
+            self.scroll = 0;
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('/') => {
+                self.search.clear();
+                self.mode = InputMode::Search;
+            }
+            KeyCode::Esc => self.search.clear(),
+            KeyCode::Char('p') | KeyCode::Char(' ') => self.toggle_pause(),
+            KeyCode::Char('v') => self.split = !self.split,
+            KeyCode::Char('f') => self.filters_enabled = !self.filters_enabled,
+            KeyCode::Char(c @ '1'..='6') => {
+                let level = LEVELS[c as usize - '1' as usize];
+                if !self.hidden_levels.remove(level) {
+                    self.hidden_levels.insert(level);
+                }
+            }
+            KeyCode::Tab | KeyCode::Char('J') if !self.pods.is_empty() => {
+                self.selected = (self.selected + 1) % self.pods.len();
+            }
+            KeyCode::BackTab | KeyCode::Char('K') if !self.pods.is_empty() => {
+                self.selected = (self.selected + self.pods.len() - 1) % self.pods.len();
+            }
+            KeyCode::Enter | KeyCode::Char('h') => {
+                if let Some(pod) = self.pods.get(self.selected) {
+                    if !self.hidden_pods.remove(&pod.name) {
+                        self.hidden_pods.insert(pod.name.clone());
+                    }
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_add(self.page_size),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(self.page_size),
+            KeyCode::Home | KeyCode::Char('g') => self.scroll = usize::MAX,
+            KeyCode::End | KeyCode::Char('G') => self.scroll = 0,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kubernetes::PodStatus;
+    use crate::log_processor::filter::GrepFilter;
+    use termcolor::Color;
+
+    fn pod(name: &str) -> PodInfo {
+        PodInfo {
+            name: name.to_string(),
+            namespace: "default".to_string(),
+            status: PodStatus::Running,
+            container_name: "main".to_string(),
+            node_name: None,
+            restart_count: 0,
+        }
+    }
+
+    fn entry(pod: &str, message: &str) -> LogEntry {
+        LogEntry::parse(
+            "default",
+            pod.to_string(),
+            "main".to_string(),
+            format!("2024-05-01T10:00:00Z {}", message),
+        )
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn messages(app: &App, pod: Option<&str>) -> Vec<String> {
+        app.visible_lines(pod)
+            .iter()
+            .map(|line| line.text.clone())
+            .collect()
+    }
+
+    fn app() -> App {
+        App::new(
+            vec![pod("web-1"), pod("web-2")],
+            LogFormatter::new(None, false),
+            Vec::new(),
+        )
+    }
+
+    #[test]
+    fn test_span_recorder() {
+        let mut recorder = SpanRecorder::default();
+        let mut red = ColorSpec::new();
+        red.set_fg(Some(Color::Red));
+
+        write!(recorder, "[pod] ").unwrap();
+        recorder.set_color(&red).unwrap();
+        write!(recorder, "err").unwrap();
+        write!(recorder, "or\n  at").unwrap();
+        recorder.reset().unwrap();
+        writeln!(recorder).unwrap();
+
+        assert_eq!(
+            recorder.into_rows(),
+            vec![
+                vec![
+                    ("[pod] ".to_string(), ColorSpec::new()),
+                    ("error".to_string(), red.clone())
+                ],
+                vec![("  at".to_string(), red)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_toggles_and_search() {
+        let mut app = app();
+        app.push(entry("web-1", "INFO started"));
+        app.push(entry("web-2", "ERROR failed"));
+        app.push(entry("web-1", "DEBUG cache miss"));
+        assert_eq!(app.visible_lines(None).len(), 3);
+        assert_eq!(messages(&app, Some("web-2")), ["[web-2] error failed"]);
+
+        // Hide DEBUG lines
+        app.handle_key(key(KeyCode::Char('2')));
+        assert_eq!(app.visible_lines(None).len(), 2);
+        app.handle_key(key(KeyCode::Char('2')));
+
+        // Hide the selected pod
+        app.handle_key(key(KeyCode::Enter));
+        assert_eq!(messages(&app, None), ["[web-2] error failed"]);
+        assert_eq!(app.visible_pods().len(), 1);
+        app.handle_key(key(KeyCode::Enter));
+
+        // Search as the query is typed, case-insensitively
+        app.handle_key(key(KeyCode::Char('/')));
+        for c in "CACHE".chars() {
+            app.handle_key(key(KeyCode::Char(c)));
+        }
+        assert_eq!(messages(&app, None), ["[web-1] debug cache miss"]);
+        app.handle_key(key(KeyCode::Esc));
+        assert_eq!(app.visible_lines(None).len(), 3);
+    }
+
+    #[test]
+    fn test_filters_toggle() {
+        let filters: Vec<Box<dyn Filter>> = vec![Box::new(GrepFilter::new("fail").unwrap())];
+        let mut app = App::new(vec![pod("web-1")], LogFormatter::new(None, false), filters);
+        app.push(entry("web-1", "started"));
+        app.push(entry("web-1", "failed"));
+        assert_eq!(app.visible_lines(None).len(), 1);
+
+        app.handle_key(key(KeyCode::Char('f')));
+        assert_eq!(app.visible_lines(None).len(), 2);
+    }
+
+    #[test]
+    fn test_pause_and_scroll() {
+        let mut app = app();
+        app.push(entry("web-1", "one"));
+        app.handle_key(key(KeyCode::Char('p')));
+        app.push(entry("web-1", "two"));
+        assert_eq!(app.visible_lines(None).len(), 1);
+        assert_eq!(app.pending(), 1);
+
+        app.handle_key(key(KeyCode::Char('p')));
+        assert_eq!(app.visible_lines(None).len(), 2);
+        assert_eq!(app.pending(), 0);
+
+        // New lines don't move the view while scrolled back
+        app.handle_key(key(KeyCode::Up));
+        app.push(entry("web-2", "three"));
+        assert_eq!(app.scroll(), 2);
+        app.handle_key(key(KeyCode::End));
+        assert_eq!(app.scroll(), 0);
+
+        app.handle_key(key(KeyCode::Char('q')));
+        assert!(app.should_quit());
+    }
+
+    #[test]
+    fn test_scroll_from_top() {
+        let mut app = app();
+        app.push(entry("web-1", "one"));
+        app.handle_key(key(KeyCode::Home));
+        app.push(entry("web-1", "two"));
+        app.handle_key(key(KeyCode::Up));
+        assert_eq!(app.scroll(), usize::MAX);
+
+        app.clamp_scroll(1);
+        assert_eq!(app.scroll(), 1);
+    }
+
+    #[test]
+    fn test_pending_limit() {
+        let mut app = app();
+        app.handle_key(key(KeyCode::Char('p')));
+        for i in 0..SCROLLBACK + 5 {
+            app.push(entry("web-1", &i.to_string()));
+        }
+        assert_eq!(app.pending(), SCROLLBACK);
+
+        app.handle_key(key(KeyCode::Char('p')));
+        let lines = app.visible_lines(None);
+        assert_eq!(lines.len(), SCROLLBACK);
+        assert!(lines[0].text.ends_with(" 5"));
+    }
+}
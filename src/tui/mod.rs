@@ -0,0 +1,83 @@
+//! Full-screen terminal UI for following logs
+
+pub mod app;
+mod ui;
+
+use anyhow::{Context, Result};
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::kubernetes::{LogEntry, PodInfo};
+use crate::log_processor::filter::Filter;
+use crate::log_processor::LogFormatter;
+
+pub use app::App;
+
+/// How often keyboard input is checked and the screen redrawn
+const FRAME_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Run the UI until the user quits
+///
+/// Log entries and pod updates are read from the channels; the UI keeps
+/// running with the last known state when either of them closes.
+pub async fn run(
+    mut logs: mpsc::Receiver<Result<LogEntry>>,
+    pods: Vec<PodInfo>,
+    mut pod_updates: mpsc::Receiver<Vec<PodInfo>>,
+    filters: Vec<Box<dyn Filter>>,
+    formatter: LogFormatter,
+) -> Result<()> {
+    let mut app = App::new(pods, formatter, filters);
+    let mut terminal = ratatui::try_init().context("Failed to initialize the terminal")?;
+    let result = async {
+        let mut frames = tokio::time::interval(FRAME_INTERVAL);
+        let mut logs_open = true;
+        let mut pods_open = true;
+        let mut dirty = true;
+
+        while !app.should_quit() {
+            tokio::select! {
+                entry = logs.recv(), if logs_open => match entry {
+                    Some(Ok(entry)) => {
+                        app.push(entry);
+                        dirty = true;
+                    }
+                    Some(Err(e)) => {
+                        app.set_error(format!("Error reading logs: {}", e));
+                        dirty = true;
+                    }
+                    None => logs_open = false,
+                },
+                update = pod_updates.recv(), if pods_open => match update {
+                    Some(pods) => {
+                        app.set_pods(pods);
+                        dirty = true;
+                    }
+                    None => pods_open = false,
+                },
+                _ = frames.tick() => {
+                    while event::poll(Duration::ZERO)? {
+                        match event::read()? {
+                            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                                app.handle_key(key)
+                            }
+                            Event::Resize(_, _) => {}
+                            _ => continue,
+                        }
+                        dirty = true;
+                    }
+                    if dirty {
+                        terminal.draw(|frame| ui::draw(frame, &mut app))?;
+                        dirty = false;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    .await;
+
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,296 @@
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line as TextLine, Span as TextSpan};
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph};
+use ratatui::Frame;
+use termcolor::ColorSpec;
+
+use crate::kubernetes::PodStatus;
+use crate::tui::app::{App, InputMode, LEVELS};
+
+/// Width of the pod sidebar
+const SIDEBAR_WIDTH: u16 = 36;
+
+/// Draw the whole screen
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let [main, status] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+    let [sidebar, logs] =
+        Layout::horizontal([Constraint::Length(SIDEBAR_WIDTH), Constraint::Min(1)]).areas(main);
+
+    draw_sidebar(frame, app, sidebar);
+    if app.is_split() {
+        draw_panes(frame, app, logs);
+    } else {
+        // Borders take two rows
+        let height = logs.height.saturating_sub(2) as usize;
+        app.set_page_size(height);
+        let rows = rows(app, None);
+        app.clamp_scroll(rows.len().saturating_sub(height));
+        let title = format!(" Logs ({} pods) ", app.visible_pods().len());
+        draw_rows(frame, logs, title, rows, app.scroll());
+    }
+    draw_status(frame, app, status);
+}
+
+/// Draw the pods with their status and restart count
+fn draw_sidebar(frame: &mut Frame, app: &App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .pods()
+        .iter()
+        .enumerate()
+        .map(|(i, pod)| {
+            let mut name_style = Style::default();
+            if app.is_pod_hidden(&pod.name) {
+                name_style = name_style.add_modifier(Modifier::DIM | Modifier::CROSSED_OUT);
+            }
+            if i == app.selected() {
+                name_style = name_style.add_modifier(Modifier::REVERSED);
+            }
+            let mut details = vec![TextSpan::styled(
+                format!("  {}", pod.status),
+                status_style(&pod.status),
+            )];
+            if pod.restart_count > 0 {
+                details.push(TextSpan::styled(
+                    format!(" ({} restarts)", pod.restart_count),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+            ListItem::new(vec![
+                TextLine::from(TextSpan::styled(pod.name.clone(), name_style)),
+                TextLine::from(details),
+            ])
+        })
+        .collect();
+
+    let list = List::new(items).block(Block::default().borders(Borders::ALL).title(" Pods "));
+    frame.render_widget(list, area);
+}
+
+/// Draw a pane per visible pod, stacked vertically
+fn draw_panes(frame: &mut Frame, app: &mut App, area: Rect) {
+    let pods: Vec<String> = app
+        .visible_pods()
+        .iter()
+        .map(|pod| pod.name.clone())
+        .collect();
+    if pods.is_empty() {
+        draw_rows(frame, area, " No pods shown ".to_string(), Vec::new(), 0);
+        return;
+    }
+
+    let areas = Layout::vertical(vec![Constraint::Fill(1); pods.len()]).split(area);
+    let height = areas[0].height.saturating_sub(2) as usize;
+    app.set_page_size(height);
+    let panes: Vec<_> = pods.iter().map(|pod| rows(app, Some(pod))).collect();
+    // Stop scrolling at the first line of the longest pane
+    let longest = panes.iter().map(Vec::len).max().unwrap_or(0);
+    app.clamp_scroll(longest.saturating_sub(height));
+    for ((pod, area), rows) in pods.iter().zip(areas.iter()).zip(panes) {
+        // Each pane stops at its own first line
+        let scroll = app.scroll().min(rows.len().saturating_sub(height));
+        draw_rows(frame, *area, format!(" {} ", pod), rows, scroll);
+    }
+}
+
+/// Get the styled rows of the visible lines
+fn rows(app: &App, pod: Option<&str>) -> Vec<TextLine<'static>> {
+    app.visible_lines(pod)
+        .into_iter()
+        .flat_map(|line| line.rows.iter())
+        .map(|row| {
+            TextLine::from(
+                row.iter()
+                    .map(|(text, spec)| TextSpan::styled(text.clone(), to_style(spec)))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}
+
+/// Draw the rows ending `scroll` rows above the last one
+fn draw_rows(
+    frame: &mut Frame,
+    area: Rect,
+    title: String,
+    rows: Vec<TextLine<'static>>,
+    scroll: usize,
+) {
+    let height = area.height.saturating_sub(2) as usize;
+    let end = rows.len().saturating_sub(scroll);
+    let start = end.saturating_sub(height);
+    let shown: Vec<TextLine> = rows.into_iter().take(end).skip(start).collect();
+
+    let paragraph =
+        Paragraph::new(shown).block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(paragraph, area);
+}
+
+/// Draw the status bar with the search, toggles and key help
+fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
+    let highlight = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let mut spans = Vec::new();
+
+    if app.mode() == InputMode::Search {
+        spans.push(TextSpan::styled(format!("/{}", app.search()), highlight));
+        spans.push(TextSpan::raw(" (Enter to keep, Esc to clear)"));
+        frame.render_widget(Paragraph::new(TextLine::from(spans)), area);
+        return;
+    }
+
+    if let Some(error) = app.error() {
+        spans.push(TextSpan::styled(
+            format!("{} ", error),
+            Style::default().fg(Color::Red),
+        ));
+    }
+    if app.is_paused() {
+        spans.push(TextSpan::styled(
+            format!(" PAUSED ({} new) ", app.pending()),
+            highlight,
+        ));
+        spans.push(TextSpan::raw(" "));
+    }
+    if !app.search().is_empty() {
+        spans.push(TextSpan::styled(format!("/{}", app.search()), highlight));
+        spans.push(TextSpan::raw(" "));
+    }
+    if app.scroll() > 0 {
+        spans.push(TextSpan::raw(format!("+{} ", app.scroll())));
+    }
+
+    for (i, level) in LEVELS.iter().enumerate() {
+        let style = if app.is_level_hidden(level) {
+            Style::default().add_modifier(Modifier::DIM | Modifier::CROSSED_OUT)
+        } else {
+            Style::default()
+        };
+        spans.push(TextSpan::styled(format!("{}:{} ", i + 1, level), style));
+    }
+    let filters = if app.filters_enabled() { "on" } else { "off" };
+    spans.push(TextSpan::raw(format!(
+        "| f:filters {} | /:search p:pause v:view Tab:pod Enter:hide q:quit",
+        filters
+    )));
+    frame.render_widget(Paragraph::new(TextLine::from(spans)), area);
+}
+
+/// Get the style for a pod status in the sidebar
+fn status_style(status: &PodStatus) -> Style {
+    let color = match status {
+        PodStatus::Running => Color::Green,
+        PodStatus::Pending => Color::Yellow,
+        PodStatus::CrashLoopBackOff => Color::Red,
+        PodStatus::Terminated => Color::DarkGray,
+        PodStatus::Unknown => Color::Gray,
+    };
+    Style::default().fg(color)
+}
+
+/// Convert a terminal color from the formatter to a ratatui color
+fn to_color(color: &termcolor::Color, intense: bool) -> Color {
+    use termcolor::Color as C;
+    match (color, intense) {
+        (C::Black, false) => Color::Black,
+        (C::Black, true) => Color::DarkGray,
+        (C::Red, false) => Color::Red,
+        (C::Red, true) => Color::LightRed,
+        (C::Green, false) => Color::Green,
+        (C::Green, true) => Color::LightGreen,
+        (C::Yellow, false) => Color::Yellow,
+        (C::Yellow, true) => Color::LightYellow,
+        (C::Blue, false) => Color::Blue,
+        (C::Blue, true) => Color::LightBlue,
+        (C::Magenta, false) => Color::Magenta,
+        (C::Magenta, true) => Color::LightMagenta,
+        (C::Cyan, false) => Color::Cyan,
+        (C::Cyan, true) => Color::LightCyan,
+        (C::White, false) => Color::Gray,
+        (C::White, true) => Color::White,
+        (C::Ansi256(index), _) => Color::Indexed(*index),
+        (C::Rgb(r, g, b), _) => Color::Rgb(*r, *g, *b),
+        _ => Color::Reset,
+    }
+}
+
+/// Convert a color spec from the formatter to a ratatui style
+fn to_style(spec: &ColorSpec) -> Style {
+    let mut style = Style::default();
+    if let Some(fg) = spec.fg() {
+        style = style.fg(to_color(fg, spec.intense()));
+    }
+    if let Some(bg) = spec.bg() {
+        style = style.bg(to_color(bg, false));
+    }
+    if spec.bold() {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    if spec.dimmed() {
+        style = style.add_modifier(Modifier::DIM);
+    }
+    if spec.italic() {
+        style = style.add_modifier(Modifier::ITALIC);
+    }
+    if spec.underline() {
+        style = style.add_modifier(Modifier::UNDERLINED);
+    }
+    style
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kubernetes::{LogEntry, PodInfo};
+    use crate::log_processor::LogFormatter;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    #[test]
+    fn test_to_style() {
+        let mut spec = ColorSpec::new();
+        spec.set_fg(Some(termcolor::Color::Red))
+            .set_intense(true)
+            .set_bold(true);
+        assert_eq!(
+            to_style(&spec),
+            Style::default()
+                .fg(Color::LightRed)
+                .add_modifier(Modifier::BOLD)
+        );
+        assert_eq!(to_style(&ColorSpec::new()), Style::default());
+    }
+
+    #[test]
+    fn test_draw() {
+        let pod = PodInfo {
+            name: "web-1".to_string(),
+            namespace: "default".to_string(),
+            status: PodStatus::CrashLoopBackOff,
+            container_name: "main".to_string(),
+            node_name: None,
+            restart_count: 4,
+        };
+        let mut app = App::new(vec![pod], LogFormatter::new(None, false), Vec::new());
+        app.push(LogEntry::parse(
+            "default",
+            "web-1".to_string(),
+            "main".to_string(),
+            "2024-05-01T10:00:00Z ERROR connection refused".to_string(),
+        ));
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 10)).unwrap();
+        terminal.draw(|frame| draw(frame, &mut app)).unwrap();
+
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("CrashLoopBackOff"));
+        assert!(screen.contains("(4 restarts)"));
+        assert!(screen.contains("ERROR connection refused"));
+    }
+}
@@ -1,6 +1,171 @@
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
-use termcolor::Color;
+use anyhow::{anyhow, Result};
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::io::IsTerminal;
+use std::str::FromStr;
+use termcolor::{Color, ColorChoice};
+
+use crate::utils::hash::fnv1a;
+
+/// When to write colored output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Color when stdout is a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    /// Always color
+    Always,
+    /// Never color
+    Never,
+}
+
+impl ColorMode {
+    /// Resolve the mode for stdout
+    pub fn color_choice(&self) -> ColorChoice {
+        self.resolve(env::var_os("NO_COLOR"), std::io::stdout().is_terminal())
+    }
+
+    /// Resolve the mode given the `NO_COLOR` variable and whether stdout is a terminal
+    fn resolve(&self, no_color: Option<OsString>, is_terminal: bool) -> ColorChoice {
+        match self {
+            ColorMode::Always => ColorChoice::Always,
+            ColorMode::Never => ColorChoice::Never,
+            // See https://no-color.org: any non-empty value disables colors
+            ColorMode::Auto if no_color.is_some_and(|value| !value.is_empty()) => {
+                ColorChoice::Never
+            }
+            ColorMode::Auto if is_terminal => ColorChoice::Always,
+            ColorMode::Auto => ColorChoice::Never,
+        }
+    }
+}
+
+impl FromStr for ColorMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            _ => Err(anyhow!(
+                "Invalid color mode: {} (expected auto, always or never)",
+                s
+            )),
+        }
+    }
+}
+
+/// Set of colors pods are colored with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Palette {
+    /// Detect from `COLORTERM` and `TERM`
+    #[default]
+    Auto,
+    /// The basic terminal colors
+    Basic,
+    /// Colors of the 256-color palette
+    Ansi256,
+    /// 24-bit colors
+    TrueColor,
+}
+
+impl Palette {
+    /// Detect the palette supported by the terminal
+    pub fn detect() -> Self {
+        Self::detect_from(env::var("COLORTERM").ok(), env::var("TERM").ok())
+    }
+
+    /// Detect the palette from the `COLORTERM` and `TERM` variables
+    fn detect_from(colorterm: Option<String>, term: Option<String>) -> Self {
+        if colorterm.is_some_and(|c| c == "truecolor" || c == "24bit") {
+            Palette::TrueColor
+        } else if term.is_some_and(|t| t.contains("256color")) {
+            Palette::Ansi256
+        } else {
+            Palette::Basic
+        }
+    }
+
+    /// Get the colors of the palette
+    fn colors(&self) -> Vec<Color> {
+        match self {
+            Palette::Auto => Self::detect().colors(),
+            Palette::Basic => vec![
+                Color::Red,
+                Color::Green,
+                Color::Blue,
+                Color::Cyan,
+                Color::Magenta,
+                Color::Yellow,
+                Color::White,
+            ],
+            // Bright, saturated colors of the 6x6x6 color cube
+            Palette::Ansi256 => (0..216u8)
+                .filter(|i| {
+                    let (r, g, b) = (i / 36, i / 6 % 6, i % 6);
+                    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+                    max >= 4 && max - min >= 2
+                })
+                .map(|i| Color::Ansi256(16 + i))
+                .collect(),
+            // Evenly spaced hues with the same saturation and lightness
+            Palette::TrueColor => (0..36)
+                .map(|i| {
+                    let (r, g, b) = hsl_to_rgb(i as f64 * 10.0, 0.7, 0.6);
+                    Color::Rgb(r, g, b)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Palette::Auto),
+            "basic" | "16" => Ok(Palette::Basic),
+            "256" => Ok(Palette::Ansi256),
+            "truecolor" | "24bit" => Ok(Palette::TrueColor),
+            _ => Err(anyhow!(
+                "Invalid palette: {} (expected auto, basic, 256 or truecolor)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Palette::Auto => write!(f, "auto"),
+            Palette::Basic => write!(f, "basic"),
+            Palette::Ansi256 => write!(f, "256"),
+            Palette::TrueColor => write!(f, "truecolor"),
+        }
+    }
+}
+
+/// Convert a color from HSL (hue in degrees) to RGB
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> (u8, u8, u8) {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let channel = |v: f64| ((v + m) * 255.0).round() as u8;
+    (channel(r), channel(g), channel(b))
+}
 
 /// Color generator for assigning colors to pods
 pub struct ColorGenerator {
@@ -13,17 +178,23 @@ pub struct ColorGenerator {
 impl ColorGenerator {
     /// Create a new color generator
     pub fn new() -> Self {
+        Self::with_palette(Palette::Basic)
+    }
+
+    /// Create a color generator using the colors of a palette
+    pub fn with_palette(palette: Palette) -> Self {
+        Self::with_colors(palette.colors())
+    }
+
+    /// Create a color generator using the given colors, which must not be empty
+    pub fn with_colors(colors: Vec<Color>) -> Self {
+        assert!(
+            !colors.is_empty(),
+            "color generator needs at least one color"
+        );
         Self {
             current_index: 0,
-            colors: vec![
-                Color::Red,
-                Color::Green,
-                Color::Blue,
-                Color::Cyan,
-                Color::Magenta,
-                Color::Yellow,
-                Color::White,
-            ],
+            colors,
         }
     }
 
@@ -35,12 +206,16 @@ impl ColorGenerator {
     }
 
     /// Get a color based on a string hash
+    ///
+    /// FNV-1a keeps the color of a name the same across builds and Rust versions.
     pub fn color_for_string(&self, s: &str) -> Color {
-        let mut hasher = DefaultHasher::new();
-        s.hash(&mut hasher);
-        let hash = hasher.finish();
+        self.colors[(fnv1a(s) % self.colors.len() as u64) as usize]
+    }
+}
 
-        self.colors[(hash % self.colors.len() as u64) as usize]
+impl Default for ColorGenerator {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -80,5 +255,60 @@ mod tests {
         let color2 = generator.color_for_string("test2");
         // We don't assert inequality here because hash collisions are possible
         assert_ne!(color1, color2);
+
+        // Names keep their color across builds
+        assert_eq!(generator.color_for_string("web-1"), Color::Blue);
+    }
+
+    #[test]
+    fn test_color_mode() {
+        assert_eq!("never".parse::<ColorMode>().unwrap(), ColorMode::Never);
+        assert!("sometimes".parse::<ColorMode>().is_err());
+
+        assert_eq!(ColorMode::Auto.resolve(None, true), ColorChoice::Always);
+        assert_eq!(ColorMode::Auto.resolve(None, false), ColorChoice::Never);
+        assert_eq!(
+            ColorMode::Auto.resolve(Some(OsString::from("1")), true),
+            ColorChoice::Never
+        );
+        assert_eq!(
+            ColorMode::Auto.resolve(Some(OsString::new()), true),
+            ColorChoice::Always
+        );
+        assert_eq!(
+            ColorMode::Always.resolve(Some(OsString::from("1")), false),
+            ColorChoice::Always
+        );
+    }
+
+    #[test]
+    fn test_palettes() {
+        assert_eq!(
+            Palette::detect_from(Some("truecolor".to_string()), None),
+            Palette::TrueColor
+        );
+        assert_eq!(
+            Palette::detect_from(None, Some("xterm-256color".to_string())),
+            Palette::Ansi256
+        );
+        assert_eq!(
+            Palette::detect_from(None, Some("xterm".to_string())),
+            Palette::Basic
+        );
+
+        let colors = Palette::Ansi256.colors();
+        assert!(colors.len() > 100);
+        assert!(!colors.contains(&Color::Ansi256(16)));
+
+        let colors = Palette::TrueColor.colors();
+        assert_eq!(colors.len(), 36);
+        assert_eq!(colors[0], Color::Rgb(224, 82, 82));
+
+        // Pods keep their color for the same palette
+        let generator = ColorGenerator::with_palette(Palette::TrueColor);
+        assert_eq!(
+            generator.color_for_string("web-1"),
+            ColorGenerator::with_palette(Palette::TrueColor).color_for_string("web-1")
+        );
     }
 }
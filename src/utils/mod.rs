@@ -1,3 +1,10 @@
 pub mod color;
+pub mod duration;
+pub mod hash;
+pub mod size;
+pub mod theme;
 
-pub use color::ColorGenerator;
+pub use color::{ColorGenerator, ColorMode, Palette};
+pub use duration::parse_duration;
+pub use size::parse_size;
+pub use theme::Theme;
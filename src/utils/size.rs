@@ -0,0 +1,45 @@
+use anyhow::{anyhow, Result};
+
+/// Parse a human-readable size such as `512K`, `100MB`, `1.5GiB` or `4096`
+///
+/// Units are binary (1K = 1024 bytes) and case-insensitive. A number without
+/// a unit is interpreted as bytes.
+pub fn parse_size(s: &str) -> Result<u64> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let value: f64 = number.parse().map_err(|_| anyhow!("Invalid size: {}", s))?;
+
+    let multiplier: u64 = match unit.trim().to_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        _ => return Err(anyhow!("Invalid size unit in: {}", s)),
+    };
+
+    Ok((value * multiplier as f64) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096").unwrap(), 4096);
+        assert_eq!(parse_size("512K").unwrap(), 512 * 1024);
+        assert_eq!(parse_size("100MB").unwrap(), 100 * 1024 * 1024);
+        assert_eq!(parse_size("1.5gib").unwrap(), 1536 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_parse_invalid_size() {
+        assert!(parse_size("").is_err());
+        assert!(parse_size("big").is_err());
+        assert!(parse_size("10 TB").is_err());
+    }
+}
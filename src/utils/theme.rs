@@ -0,0 +1,250 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::str::FromStr;
+use termcolor::{Color, ColorSpec};
+
+use crate::config::{Config, ThemeConfig};
+use crate::kubernetes::log::canonical_level;
+use crate::log_processor::pretty::JsonToken;
+use crate::utils::color::{ColorGenerator, Palette};
+
+/// Name of the theme used when none is selected
+pub const DEFAULT_THEME: &str = "default";
+
+/// Colors used for pods, levels and highlighted matches
+#[derive(Debug, Clone)]
+pub struct Theme {
+    /// Palette pods are colored from
+    pub palette: Palette,
+    /// Explicit pod colors, used instead of the palette when not empty
+    pub pod_colors: Vec<Color>,
+    /// Style of messages per canonical level
+    pub levels: HashMap<&'static str, ColorSpec>,
+    /// Style of highlighted matches
+    pub highlight: ColorSpec,
+    /// Style of timestamps shown with `--timestamps`
+    pub timestamp: ColorSpec,
+    /// Style of JSON syntax elements in pretty and compact output
+    pub json: HashMap<JsonToken, ColorSpec>,
+}
+
+/// Parse a style such as `bold red`, `dim`, `black on yellow` or `#ff8800`
+///
+/// `none` gives an empty style, which leaves the text uncolored.
+pub fn parse_style(style: &str) -> Result<ColorSpec> {
+    let mut spec = ColorSpec::new();
+    let mut words = style.split_whitespace();
+
+    while let Some(word) = words.next() {
+        match word.to_lowercase().as_str() {
+            "none" => {}
+            "bold" => {
+                spec.set_bold(true);
+            }
+            "dim" | "dimmed" => {
+                spec.set_dimmed(true);
+            }
+            "italic" => {
+                spec.set_italic(true);
+            }
+            "underline" => {
+                spec.set_underline(true);
+            }
+            "intense" | "bright" => {
+                spec.set_intense(true);
+            }
+            "on" => {
+                let color = words
+                    .next()
+                    .ok_or_else(|| anyhow!("Missing background color in style: {}", style))?;
+                spec.set_bg(Some(parse_color(color)?));
+            }
+            _ => {
+                spec.set_fg(Some(parse_color(word)?));
+            }
+        }
+    }
+    Ok(spec)
+}
+
+/// Parse a color name, 256-color index, `r,g,b` triple or `#rrggbb` hex color
+fn parse_color(color: &str) -> Result<Color> {
+    if let Some(hex) = color.strip_prefix('#') {
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+        };
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+            _ => Err(anyhow!("Invalid color {}: expected #rrggbb", color)),
+        };
+    }
+    Color::from_str(color).map_err(|e| anyhow!("Invalid color {}: {}", color, e))
+}
+
+impl Theme {
+    /// Get a built-in theme by name
+    pub fn builtin(name: &str) -> Option<Self> {
+        let levels: &[(&'static str, &str)] = match name {
+            "default" => &[
+                ("FATAL", "bold red"),
+                ("ERROR", "red"),
+                ("WARN", "yellow"),
+                ("DEBUG", "dim"),
+                ("TRACE", "dim"),
+            ],
+            // Pod colors and highlights only, messages keep the terminal color
+            "plain" => &[],
+            _ => return None,
+        };
+
+        Some(Self {
+            palette: Palette::Auto,
+            pod_colors: Vec::new(),
+            levels: levels
+                .iter()
+                .map(|(level, style)| (*level, parse_style(style).expect("valid built-in style")))
+                .collect(),
+            highlight: parse_style("bold intense yellow").expect("valid built-in style"),
+            timestamp: parse_style("dim").expect("valid built-in style"),
+            json: [
+                (JsonToken::Key, "blue"),
+                (JsonToken::String, "green"),
+                (JsonToken::Number, "cyan"),
+                (JsonToken::Literal, "magenta"),
+            ]
+            .iter()
+            .map(|(token, style)| (*token, parse_style(style).expect("valid built-in style")))
+            .collect(),
+        })
+    }
+
+    /// Build a theme from the configuration file, on top of the default theme
+    pub fn from_config(config: &ThemeConfig) -> Result<Self> {
+        let mut theme = Self::builtin(DEFAULT_THEME).expect("default theme exists");
+
+        if let Some(palette) = &config.palette {
+            theme.palette = palette.parse()?;
+        }
+        theme.pod_colors = config
+            .pods
+            .iter()
+            .map(|color| parse_color(color))
+            .collect::<Result<_>>()?;
+        for (level, style) in &config.levels {
+            let level = canonical_level(level)
+                .ok_or_else(|| anyhow!("Invalid level in theme: {}", level))?;
+            theme.levels.insert(level, parse_style(style)?);
+        }
+        if let Some(highlight) = &config.highlight {
+            theme.highlight = parse_style(highlight)?;
+        }
+        if let Some(timestamp) = &config.timestamp {
+            theme.timestamp = parse_style(timestamp)?;
+        }
+        for (token, style) in &config.json {
+            theme.json.insert(token.parse()?, parse_style(style)?);
+        }
+        Ok(theme)
+    }
+
+    /// Load a theme by name, from the configuration file or the built-in themes
+    pub fn load(name: &str, config: &Config) -> Result<Self> {
+        if let Some(theme) = config.themes.get(name) {
+            return Self::from_config(theme).with_context(|| format!("In theme {}", name));
+        }
+        Self::builtin(name).ok_or_else(|| anyhow!("Unknown theme: {}", name))
+    }
+
+    /// Get the style of messages with the given level
+    pub fn level_style(&self, level: Option<&str>) -> Option<&ColorSpec> {
+        self.levels.get(level?).filter(|spec| !spec.is_none())
+    }
+
+    /// Get the style of a JSON syntax element
+    pub fn json_style(&self, token: JsonToken) -> Option<&ColorSpec> {
+        self.json.get(&token).filter(|spec| !spec.is_none())
+    }
+
+    /// Create the color generator for pods
+    pub fn color_generator(&self) -> ColorGenerator {
+        if self.pod_colors.is_empty() {
+            ColorGenerator::with_palette(self.palette)
+        } else {
+            ColorGenerator::with_colors(self.pod_colors.clone())
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::builtin(DEFAULT_THEME).expect("default theme exists")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_style() {
+        let spec = parse_style("bold red on #202020").unwrap();
+        assert_eq!(spec.fg(), Some(&Color::Red));
+        assert_eq!(spec.bg(), Some(&Color::Rgb(0x20, 0x20, 0x20)));
+        assert!(spec.bold());
+
+        assert_eq!(parse_style("208").unwrap().fg(), Some(&Color::Ansi256(208)));
+        assert!(parse_style("none").unwrap().is_none());
+        assert!(parse_style("blurple").is_err());
+        assert!(parse_style("#12345").is_err());
+        assert!(parse_style("red on").is_err());
+    }
+
+    #[test]
+    fn test_load() {
+        let config = Config::from_yaml(
+            r##"
+themes:
+  solarized:
+    palette: truecolor
+    pods: ["#268bd2", "#2aa198"]
+    levels:
+      warning: "#b58900"
+      debug: none
+    highlight: black on yellow
+    json:
+      key: bold blue
+      number: none
+"##,
+        )
+        .unwrap();
+
+        let theme = Theme::load("solarized", &config).unwrap();
+        assert_eq!(theme.palette, Palette::TrueColor);
+        assert_eq!(theme.pod_colors.len(), 2);
+        assert_eq!(
+            theme.level_style(Some("WARN")).unwrap().fg(),
+            Some(&Color::Rgb(0xb5, 0x89, 0x00))
+        );
+        assert!(theme.level_style(Some("DEBUG")).is_none());
+        // Levels that are not configured come from the default theme
+        assert_eq!(
+            theme.level_style(Some("ERROR")).unwrap().fg(),
+            Some(&Color::Red)
+        );
+        assert_eq!(theme.highlight.bg(), Some(&Color::Yellow));
+        assert!(theme.json_style(JsonToken::Key).unwrap().bold());
+        assert!(theme.json_style(JsonToken::Number).is_none());
+        assert_eq!(
+            theme.json_style(JsonToken::String).unwrap().fg(),
+            Some(&Color::Green)
+        );
+
+        assert!(Theme::load("plain", &config).unwrap().levels.is_empty());
+        assert!(Theme::load("missing", &config).is_err());
+
+        let config =
+            Config::from_yaml("themes:\n  broken:\n    levels:\n      loud: red\n").unwrap();
+        assert!(Theme::load("broken", &config).is_err());
+    }
+}
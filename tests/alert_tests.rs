@@ -0,0 +1,128 @@
+use anyhow::Result;
+use klogrs::{
+    config::Config,
+    kubernetes::log::LogEntry,
+    log_processor::{
+        alert::{send_webhook, AlertRule},
+        AlertEngine, AlertStage, Stage,
+    },
+};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+fn create_test_entry(pod_name: &str, message: &str) -> LogEntry {
+    LogEntry::new("default", pod_name, "main", message.to_string())
+}
+
+fn create_engine(yaml: &str) -> Result<AlertEngine> {
+    let config = Config::from_yaml(yaml)?;
+    let rules = config
+        .alerts
+        .iter()
+        .map(AlertRule::from_config)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(AlertEngine::new(rules))
+}
+
+/// Accept one HTTP request, answer 200 and return the request line and body
+fn serve_once(listener: TcpListener) -> thread::JoinHandle<(String, String)> {
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap();
+            if header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+        }
+
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+            .unwrap();
+
+        (request_line, String::from_utf8(body).unwrap())
+    })
+}
+
+/// Test that a fired alert is posted to a webhook as JSON
+#[tokio::test]
+async fn test_webhook_alert() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}/hooks/klogrs", listener.local_addr()?);
+    let server = serve_once(listener);
+
+    let mut engine = create_engine(&format!(
+        "alerts:\n  - name: timeouts\n    pattern: timeout\n    threshold: 1\n    window: 1m\n    webhook: {}\n",
+        url
+    ))?;
+
+    let now = chrono::Utc::now();
+    assert!(engine
+        .observe(&create_test_entry("web-1", "upstream timeout"), now)
+        .is_empty());
+    let fired = engine.observe(&create_test_entry("web-2", "db timeout"), now);
+    assert_eq!(fired.len(), 1);
+
+    send_webhook(&url, &fired[0].0).await?;
+
+    let (request_line, body) = server.join().unwrap();
+    assert!(request_line.starts_with("POST /hooks/klogrs HTTP/1.1"));
+
+    let payload: serde_json::Value = serde_json::from_str(&body)?;
+    assert_eq!(payload["alert"], "timeouts");
+    assert_eq!(payload["count"], 2);
+    assert_eq!(payload["lines"][0]["message"], "upstream timeout");
+    assert_eq!(payload["lines"][1]["pod"], "web-2");
+    Ok(())
+}
+
+/// Test that the alert stage passes lines through and runs the command once per cooldown
+#[test]
+fn test_command_alert() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let output = dir.path().join("alerts.jsonl");
+
+    let engine = create_engine(&format!(
+        "alerts:\n  - name: errors\n    level: ERROR\n    threshold: 2\n    window: 1m\n    cooldown: 1h\n    command: \"cat >> '{}'; echo >> '{}'\"\n",
+        output.display(),
+        output.display()
+    ))?;
+    let mut stage = AlertStage::new(engine);
+
+    let mut passed = 0;
+    for i in 0..10 {
+        passed += stage
+            .process(create_test_entry(
+                "web-1",
+                &format!("[ERROR] failure {}", i),
+            ))
+            .len();
+    }
+    assert_eq!(passed, 10);
+    assert!(stage.finish().is_empty());
+
+    let content = std::fs::read_to_string(&output)?;
+    let alerts: Vec<serde_json::Value> = content
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0]["alert"], "errors");
+    assert_eq!(alerts[0]["count"], 3);
+    Ok(())
+}
@@ -57,7 +57,7 @@ async fn test_basic_functionality() -> Result<()> {
     println!("Logs from coredns pods:");
     while let Some(entry_result) = log_stream.recv().await {
         if let Ok(entry) = entry_result {
-            println!("{}: {}", entry.pod_name, entry.message);
+            println!("{}: {}", entry.pod_name, entry.message());
             count += 1;
             if count >= max_logs {
                 break;
@@ -117,7 +117,7 @@ async fn test_grep_functionality() -> Result<()> {
     while let Some(entry_result) = log_stream.recv().await {
         if let Ok(entry) = entry_result {
             if grep_filter.apply(&entry) {
-                println!("{}: {}", entry.pod_name, entry.message);
+                println!("{}: {}", entry.pod_name, entry.message());
                 found_match = true;
                 count += 1;
                 if count >= max_logs {
@@ -145,7 +145,7 @@ fn test_help_command() -> Result<()> {
     println!("Testing help information");
 
     let output = Command::new("cargo")
-        .args(&["run", "--", "--help"])
+        .args(["run", "--", "--help"])
         .output()?;
 
     let stdout = str::from_utf8(&output.stdout)?;
@@ -179,7 +179,7 @@ fn test_version_command() -> Result<()> {
     println!("Testing version information");
 
     let output = Command::new("cargo")
-        .args(&["run", "--", "--version"])
+        .args(["run", "--", "--version"])
         .output()?;
 
     let stdout = str::from_utf8(&output.stdout)?;
@@ -98,7 +98,7 @@ fn test_level_filter_with_different_formats() -> Result<()> {
             warn_filter.apply(entry),
             expected_warn[i],
             "Failed on WARN filter entry: {}",
-            entry.message
+            entry.message()
         );
     }
     
@@ -108,7 +108,7 @@ fn test_level_filter_with_different_formats() -> Result<()> {
             error_filter.apply(entry),
             expected_error[i],
             "Failed on ERROR filter entry: {}",
-            entry.message
+            entry.message()
         );
     }
 
@@ -117,9 +117,5 @@ fn test_level_filter_with_different_formats() -> Result<()> {
 
 /// Helper function to create a test log entry
 fn create_test_entry(message: &str) -> LogEntry {
-    LogEntry {
-        pod_name: "test-pod".to_string(),
-        raw_line: message.to_string(),
-        message: message.to_string(),
-    }
+    LogEntry::new("default", "test-pod", "main", message.to_string())
 }
@@ -1,5 +1,11 @@
-use klogrs::cli::parse_args;
+use klogrs::cli::{parse_args, Command};
+use klogrs::log_processor::filter::MatchScope;
+use klogrs::log_processor::{JsonStyle, OutputFormat, Template, TimestampMode};
+use klogrs::output::Compression;
+use klogrs::utils::ColorMode;
 use std::ffi::OsString;
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// Test running with no arguments
 #[test]
@@ -22,7 +28,7 @@ fn test_minimal_args() {
     let parsed = result.unwrap();
     assert_eq!(parsed.namespace, "default");
     assert_eq!(parsed.deployment, "nginx");
-    assert_eq!(parsed.follow, false);
+    assert!(!parsed.follow);
     assert_eq!(parsed.grep, None);
     assert_eq!(parsed.tail, None);
 }
@@ -55,7 +61,7 @@ fn test_follow_arg() {
         OsString::from("--follow"),
     ];
     let result = parse_args(args).unwrap();
-    assert_eq!(result.follow, true);
+    assert!(result.follow);
 }
 
 /// Test follow logs (-f)
@@ -70,7 +76,7 @@ fn test_follow_short_arg() {
         OsString::from("-f"),
     ];
     let result = parse_args(args).unwrap();
-    assert_eq!(result.follow, true);
+    assert!(result.follow);
 }
 
 /// Test grep filter (--grep)
@@ -101,6 +107,239 @@ fn test_grep_short_arg() {
     assert_eq!(result.grep, Some("ERROR".to_string()));
 }
 
+/// Test grep pattern file (--grep-file)
+#[test]
+fn test_grep_file_arg() {
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--grep-file"),
+        OsString::from("signatures.txt"),
+    ];
+    let result = parse_args(args).unwrap();
+    assert_eq!(result.grep_file, Some(PathBuf::from("signatures.txt")));
+    assert_eq!(result.grep, None);
+}
+
+/// Test statistics mode (--stats)
+#[test]
+fn test_stats_arg() {
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("-f"),
+        OsString::from("--stats"),
+    ];
+    let result = parse_args(args).unwrap();
+    assert!(result.stats);
+    assert!(result.follow);
+
+    // Statistics and summary mode both replace the output
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--stats"),
+        OsString::from("--summarize"),
+    ];
+    assert!(parse_args(args).is_err());
+}
+
+/// Test wait mode (--wait-for, --fail-on, --wait-timeout)
+#[test]
+fn test_wait_args() {
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--wait-for"),
+        OsString::from("Server started"),
+        OsString::from("--fail-on"),
+        OsString::from("FATAL"),
+        OsString::from("--wait-timeout"),
+        OsString::from("2m"),
+    ];
+    let result = parse_args(args).unwrap();
+    assert_eq!(result.wait_for, Some("Server started".to_string()));
+    assert_eq!(result.fail_on, Some("FATAL".to_string()));
+    assert_eq!(result.wait_timeout, Some(Duration::from_secs(120)));
+}
+
+/// Test redaction (--redact)
+#[test]
+fn test_redact_arg() {
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--redact"),
+    ];
+    let result = parse_args(args).unwrap();
+    assert!(result.redact);
+}
+
+/// Test output format (-o, --output)
+#[test]
+fn test_output_arg() {
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("-o"),
+        OsString::from("json"),
+    ];
+    let result = parse_args(args).unwrap();
+    assert_eq!(result.output, OutputFormat::Json);
+
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--output"),
+        OsString::from("yaml"),
+    ];
+    assert!(parse_args(args).is_err());
+}
+
+/// Test template argument
+#[test]
+fn test_template_arg() {
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--template"),
+        OsString::from("{ts:%H:%M:%S} {pod:<20.20} {message}"),
+    ];
+    let result = parse_args(args).unwrap();
+    assert_eq!(
+        result.template,
+        Some(Template::parse("{ts:%H:%M:%S} {pod:<20.20} {message}").unwrap())
+    );
+
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--template"),
+        OsString::from("{hostname} {message}"),
+    ];
+    assert!(parse_args(args).is_err());
+
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("-o"),
+        OsString::from("json"),
+        OsString::from("--template"),
+        OsString::from("{message}"),
+    ];
+    assert!(parse_args(args).is_err());
+}
+
+/// Test timestamps argument
+#[test]
+fn test_timestamps_arg() {
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--timestamps"),
+        OsString::from("relative"),
+    ];
+    assert_eq!(
+        parse_args(args).unwrap().timestamps,
+        Some(TimestampMode::Relative)
+    );
+
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--timestamps"),
+        OsString::from("Europe/Berlin %H:%M:%S"),
+    ];
+    assert!(parse_args(args).unwrap().timestamps.is_some());
+
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--timestamps"),
+        OsString::from("Mars/Olympus"),
+    ];
+    assert!(parse_args(args).is_err());
+}
+
+/// Test JSON style argument
+#[test]
+fn test_json_style_arg() {
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+    ];
+    assert_eq!(parse_args(args).unwrap().json_style, JsonStyle::Raw);
+
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--json-style"),
+        OsString::from("pretty"),
+    ];
+    assert_eq!(parse_args(args).unwrap().json_style, JsonStyle::Pretty);
+
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--json-style"),
+        OsString::from("compact"),
+        OsString::from("-o"),
+        OsString::from("json"),
+    ];
+    assert!(parse_args(args).is_err());
+}
+
+/// Test color arguments
+#[test]
+fn test_color_args() {
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+    ];
+    let result = parse_args(args).unwrap();
+    assert_eq!(result.color, ColorMode::Auto);
+    assert_eq!(result.theme, None);
+
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--color"),
+        OsString::from("never"),
+        OsString::from("--theme"),
+        OsString::from("plain"),
+    ];
+    let result = parse_args(args).unwrap();
+    assert_eq!(result.color, ColorMode::Never);
+    assert_eq!(result.theme, Some("plain".to_string()));
+
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--color"),
+        OsString::from("sometimes"),
+    ];
+    assert!(parse_args(args).is_err());
+}
+
 /// Test combined arguments
 #[test]
 fn test_combined_args() {
@@ -117,7 +356,7 @@ fn test_combined_args() {
     let result = parse_args(args).unwrap();
     assert_eq!(result.namespace, "default");
     assert_eq!(result.deployment, "nginx");
-    assert_eq!(result.follow, true);
+    assert!(result.follow);
     assert_eq!(result.grep, Some("ERROR".to_string()));
     assert_eq!(result.tail, None);
 }
@@ -162,7 +401,7 @@ fn test_parse_args() {
     let parsed = parse_args(args).unwrap();
     assert_eq!(parsed.namespace, "default");
     assert_eq!(parsed.deployment, "test-deployment");
-    assert_eq!(parsed.follow, false);
+    assert!(!parsed.follow);
     assert_eq!(parsed.grep, None);
     assert_eq!(parsed.tail, None);
 
@@ -177,7 +416,7 @@ fn test_parse_args() {
     let parsed = parse_args(args).unwrap();
     assert_eq!(parsed.namespace, "test-namespace");
     assert_eq!(parsed.deployment, "test-deployment");
-    assert_eq!(parsed.follow, false);
+    assert!(!parsed.follow);
     assert_eq!(parsed.grep, None);
     assert_eq!(parsed.tail, None);
 
@@ -191,7 +430,7 @@ fn test_parse_args() {
     let parsed = parse_args(args).unwrap();
     assert_eq!(parsed.namespace, "default");
     assert_eq!(parsed.deployment, "test-deployment");
-    assert_eq!(parsed.follow, true);
+    assert!(parsed.follow);
     assert_eq!(parsed.grep, None);
     assert_eq!(parsed.tail, None);
 
@@ -206,7 +445,7 @@ fn test_parse_args() {
     let parsed = parse_args(args).unwrap();
     assert_eq!(parsed.namespace, "default");
     assert_eq!(parsed.deployment, "test-deployment");
-    assert_eq!(parsed.follow, false);
+    assert!(!parsed.follow);
     assert_eq!(parsed.grep, Some("error".to_string()));
     assert_eq!(parsed.tail, None);
 
@@ -221,7 +460,7 @@ fn test_parse_args() {
     let parsed = parse_args(args).unwrap();
     assert_eq!(parsed.namespace, "default");
     assert_eq!(parsed.deployment, "test-deployment");
-    assert_eq!(parsed.follow, false);
+    assert!(!parsed.follow);
     assert_eq!(parsed.grep, None);
     assert_eq!(parsed.tail, Some(20));
 
@@ -241,7 +480,285 @@ fn test_parse_args() {
     let parsed = parse_args(args).unwrap();
     assert_eq!(parsed.namespace, "test-namespace");
     assert_eq!(parsed.deployment, "test-deployment");
-    assert_eq!(parsed.follow, true);
+    assert!(parsed.follow);
     assert_eq!(parsed.grep, Some("error".to_string()));
     assert_eq!(parsed.tail, Some(30));
 }
+
+/// Test output file and rotation parameters
+#[test]
+fn test_output_file_args() {
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--output-file"),
+        OsString::from("nginx.log"),
+        OsString::from("--rotate-size"),
+        OsString::from("10M"),
+        OsString::from("--rotate-interval"),
+        OsString::from("1h"),
+        OsString::from("--compress"),
+        OsString::from("zstd"),
+        OsString::from("--keep"),
+        OsString::from("5"),
+    ];
+    let args = parse_args(args).unwrap();
+    assert_eq!(args.output_file, Some(PathBuf::from("nginx.log")));
+    assert_eq!(args.rotate_size, Some(10 * 1024 * 1024));
+    assert_eq!(args.rotate_interval, Some(Duration::from_secs(3600)));
+    assert_eq!(args.compress, Compression::Zstd);
+    assert_eq!(args.keep, Some(5));
+
+    // Rotation options need an output file
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--rotate-size"),
+        OsString::from("10M"),
+    ];
+    assert!(parse_args(args).is_err());
+
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--output-file"),
+        OsString::from("nginx.log"),
+        OsString::from("--compress"),
+        OsString::from("lz4"),
+    ];
+    assert!(parse_args(args).is_err());
+}
+
+/// Test output directory parameter
+#[test]
+fn test_output_dir_arg() {
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--output-dir"),
+        OsString::from("incident"),
+    ];
+    assert_eq!(
+        parse_args(args).unwrap().output_dir,
+        Some(PathBuf::from("incident"))
+    );
+
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--output-dir"),
+        OsString::from("incident"),
+        OsString::from("--stats"),
+    ];
+    assert!(parse_args(args).is_err());
+}
+
+/// Test group by pod parameter
+#[test]
+fn test_group_by_pod_arg() {
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--group-by-pod"),
+    ];
+    assert!(parse_args(args).unwrap().group_by_pod);
+
+    // Follow mode always shows lines as they arrive
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("-f"),
+        OsString::from("--group-by-pod"),
+    ];
+    assert!(parse_args(args).is_err());
+}
+
+/// Test reorder parameter
+#[test]
+fn test_reorder_arg() {
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("-f"),
+        OsString::from("--reorder"),
+        OsString::from("500ms"),
+    ];
+    assert_eq!(
+        parse_args(args).unwrap().reorder,
+        Some(Duration::from_millis(500))
+    );
+
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--reorder"),
+        OsString::from("soon"),
+    ];
+    assert!(parse_args(args).is_err());
+}
+
+/// Test total tail parameter
+#[test]
+fn test_tail_total_arg() {
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--tail-total"),
+        OsString::from("100"),
+    ];
+    let args = parse_args(args).unwrap();
+    assert_eq!(args.tail_total, Some(100));
+    assert_eq!(args.tail, None);
+
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("-f"),
+        OsString::from("--tail-total"),
+        OsString::from("100"),
+    ];
+    assert!(parse_args(args).is_err());
+}
+
+/// Test no pager parameter
+#[test]
+fn test_no_pager_arg() {
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+    ];
+    assert!(!parse_args(args).unwrap().no_pager);
+
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--no-pager"),
+    ];
+    assert!(parse_args(args).unwrap().no_pager);
+}
+
+#[test]
+fn test_tui_subcommand() {
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+    ];
+    assert_eq!(parse_args(args).unwrap().command, None);
+
+    // Options may come before or after the subcommand
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-n"),
+        OsString::from("prod"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("tui"),
+    ];
+    assert_eq!(parse_args(args).unwrap().command, Some(Command::Tui));
+
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("tui"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--level"),
+        OsString::from("ERROR"),
+    ];
+    let args = parse_args(args).unwrap();
+    assert_eq!(args.command, Some(Command::Tui));
+    assert_eq!(args.deployment, "nginx");
+    assert_eq!(args.level.as_deref(), Some("ERROR"));
+
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-n"),
+        OsString::from("prod"),
+        OsString::from("tui"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+    ];
+    let args = parse_args(args).unwrap();
+    assert_eq!(args.command, Some(Command::Tui));
+    assert_eq!(args.namespace, "prod");
+    assert_eq!(args.deployment, "nginx");
+
+    // The deployment is still required
+    let args = vec![
+        OsString::from("klogrs"),
+        OsString::from("-n"),
+        OsString::from("prod"),
+        OsString::from("tui"),
+    ];
+    assert!(parse_args(args).is_err());
+}
+
+#[test]
+fn test_line_width_args() {
+    let args = parse_args(vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+    ])
+    .unwrap();
+    assert!(!args.truncate);
+    assert!(!args.no_wrap);
+
+    let args = parse_args(vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--truncate"),
+    ])
+    .unwrap();
+    assert!(args.truncate);
+
+    // Truncating and not wrapping at all contradict each other
+    assert!(parse_args(vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--truncate"),
+        OsString::from("--no-wrap"),
+    ])
+    .is_err());
+}
+
+/// Test grep and level scope arguments
+#[test]
+fn test_match_scope_args() {
+    let args = parse_args(vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+    ])
+    .unwrap();
+    assert_eq!(args.grep_scope, MatchScope::Message);
+    assert_eq!(args.level_scope, MatchScope::Level);
+
+    let args = parse_args(vec![
+        OsString::from("klogrs"),
+        OsString::from("-d"),
+        OsString::from("nginx"),
+        OsString::from("--grep-scope"),
+        OsString::from("raw"),
+        OsString::from("--level-scope"),
+        OsString::from("field:severity"),
+    ])
+    .unwrap();
+    assert_eq!(args.grep_scope, MatchScope::Raw);
+    assert_eq!(args.level_scope, MatchScope::Field("severity".to_string()));
+}